@@ -2,6 +2,139 @@ use std::env;
 use std::fs;
 use std::io::Write;
 use mdict_parser::parser;
+use regex::Regex;
+
+/// 导出时同时写入的词典元数据文件，供 import-obunsha 写入数据库
+const METADATA_FILE: &str = "exported_dict_meta.json";
+
+/// 读取MDX文件头中的属性（Title、Description等）
+/// 文件头格式：4字节大端长度 + UTF-16LE编码的XML
+fn read_header_attributes(data: &[u8]) -> Vec<(String, String)> {
+    if data.len() < 4 {
+        return Vec::new();
+    }
+
+    let header_len = u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as usize;
+    let header_bytes = match data.get(4..4 + header_len) {
+        Some(bytes) => bytes,
+        None => return Vec::new(),
+    };
+
+    let units: Vec<u16> = header_bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+    let header = String::from_utf16_lossy(&units);
+
+    let attr_regex = Regex::new(r#"(\w+)="([^"]*)""#).unwrap();
+    attr_regex
+        .captures_iter(&header)
+        .map(|cap| {
+            let value = cap[2]
+                .replace("&lt;", "<")
+                .replace("&gt;", ">")
+                .replace("&quot;", "\"")
+                .replace("&amp;", "&");
+            (cap[1].to_string(), value)
+        })
+        .collect()
+}
+
+/// 导出时最多显示的错位记录数
+const MAX_MISPAIRED_WARNINGS: usize = 20;
+
+/// 检查关键字与定义是否成对：关键字不能为空也不能像定义，定义必须是词条HTML或重定向
+/// 返回错位原因，成对时返回None
+fn mispair_reason(key: &str, definition: &str) -> Option<&'static str> {
+    if key.trim().is_empty() {
+        return Some("关键字为空");
+    }
+    if key.contains("<container") || key.starts_with("@@@LINK=") {
+        return Some("关键字行包含定义内容");
+    }
+    if !definition.contains("<container") && !definition.starts_with("@@@LINK=") {
+        return Some("定义行缺少<container");
+    }
+    None
+}
+
+/// 写入导出词典的元数据（MDX文件名、词条数、错位记录数、标题、描述）
+fn write_metadata(mdx_file_path: &str, data: &[u8], record_count: usize, mispaired_count: usize) -> std::io::Result<()> {
+    let mdx_file = std::path::Path::new(mdx_file_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| mdx_file_path.to_string());
+
+    let mut metadata = serde_json::Map::new();
+    metadata.insert("mdx_file".to_string(), mdx_file.into());
+    metadata.insert("record_count".to_string(), record_count.to_string().into());
+    metadata.insert("mispaired_records".to_string(), mispaired_count.to_string().into());
+
+    for (key, value) in read_header_attributes(data) {
+        match key.as_str() {
+            "Title" => { metadata.insert("title".to_string(), value.into()); }
+            "Description" => { metadata.insert("description".to_string(), value.into()); }
+            "GeneratedByEngineVersion" => { metadata.insert("engine_version".to_string(), value.into()); }
+            _ => {}
+        }
+    }
+
+    let content = serde_json::to_string_pretty(&serde_json::Value::Object(metadata))?;
+    fs::write(METADATA_FILE, content)
+}
+
+/// 导出格式
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ExportFormat {
+    /// 关键字一行、定义一行（clean-data的输入格式）
+    Txt,
+    /// 每行一个 {"key", "definition"} JSON对象
+    Jsonl,
+}
+
+impl ExportFormat {
+    /// 解析 --format 参数，jsonl与ndjson等价
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "txt" => Some(Self::Txt),
+            "jsonl" | "ndjson" => Some(Self::Jsonl),
+            _ => None,
+        }
+    }
+
+    fn output_file(self) -> &'static str {
+        match self {
+            Self::Txt => "exported_dict_full.txt",
+            Self::Jsonl => "exported_dict_full.jsonl",
+        }
+    }
+}
+
+/// 按导出格式写入一条记录
+fn write_record(file: &mut impl Write, format: ExportFormat, key: &str, definition: &str) -> std::io::Result<()> {
+    match format {
+        ExportFormat::Txt => {
+            writeln!(file, "{}", key)?;
+            writeln!(file, "{}", definition)
+        }
+        ExportFormat::Jsonl => {
+            let record = serde_json::json!({ "key": key, "definition": definition });
+            writeln!(file, "{}", record)
+        }
+    }
+}
+
+/// 读取 `--flag N` 形式的数值参数；未提供时返回Ok(None)，不是非负整数时返回错误信息
+fn usize_flag(args: &[String], flag: &str) -> Result<Option<usize>, String> {
+    match args.iter().position(|arg| arg == flag) {
+        None => Ok(None),
+        Some(index) => args
+            .get(index + 1)
+            .and_then(|value| value.parse().ok())
+            .map(Some)
+            .ok_or_else(|| format!("{} 需要一个非负整数参数", flag)),
+    }
+}
 
 fn main() {
     println!("MDX词典解析器与导出工具");
@@ -11,14 +144,34 @@ fn main() {
     if args.len() < 2 {
         println!("用法: cargo run --bin mdx_parser <mdx文件路径> [--export] [--verbose]");
         println!("示例: cargo run --bin mdx_parser data/dictionary.mdx");
-        println!("导出模式: cargo run --bin mdx_parser data/dictionary.mdx --export");
+        println!("导出模式: cargo run --bin mdx_parser data/dictionary.mdx --export [--format txt|jsonl]");
         println!("详细模式: cargo run --bin mdx_parser data/dictionary.mdx --verbose");
+        println!("查看指定范围: cargo run --bin mdx_parser data/dictionary.mdx --verbose --skip 5000 --limit 10");
         return;
     }
     
     let mdx_file_path = &args[1];
     let export_mode = args.contains(&"--export".to_string());
     let verbose = args.contains(&"--verbose".to_string());
+    // --format 控制导出格式，默认txt
+    let export_format = match args.iter().position(|arg| arg == "--format").map(|index| args.get(index + 1)) {
+        None => ExportFormat::Txt,
+        Some(value) => match value.and_then(|value| ExportFormat::parse(value)) {
+            Some(format) => format,
+            None => {
+                eprintln!("❌ --format 只支持 txt、jsonl、ndjson");
+                return;
+            }
+        },
+    };
+    // --skip/--limit 控制详细模式和简洁模式显示的词条范围
+    let (skip, limit) = match (usize_flag(&args, "--skip"), usize_flag(&args, "--limit")) {
+        (Ok(skip), Ok(limit)) => (skip.unwrap_or(0), limit),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("❌ {}", e);
+            return;
+        }
+    };
     
     println!("正在解析MDX文件: {}", mdx_file_path);
     
@@ -39,10 +192,11 @@ fn main() {
                 // 导出模式：导出全部词条数据为txt格式
                 println!("\n📤 正在导出全部词条数据...");
                 
-                let output_file = "exported_dict_full.txt";
+                let output_file = export_format.output_file();
                 match fs::File::create(output_file) {
                     Ok(mut file) => {
                         let mut count = 0;
+                        let mut mispaired = 0;
                         for record in dict.items() {
                             // 清理关键字和定义中的特殊字符
                             let key = record.key.replace('\r', "").replace('\n', " ");
@@ -52,14 +206,20 @@ fn main() {
                                 .replace("<br>", " ")
                                 .trim()
                                 .to_string();
-                            
-                            // 写入关键字一行，定义一行
-                            if let Err(e) = writeln!(file, "{}", key) {
-                                eprintln!("❌ 写入关键字失败: {}", e);
-                                break;
+
+                            // 关键字与定义错位的记录会打乱后续的两行一组结构，跳过并记录
+                            if let Some(reason) = mispair_reason(&key, &definition) {
+                                mispaired += 1;
+                                if mispaired <= MAX_MISPAIRED_WARNINGS {
+                                    let preview: String = definition.chars().take(40).collect();
+                                    eprintln!("⚠️  跳过错位记录（{}）: key={:?} definition={:?}", reason, key, preview);
+                                }
+                                continue;
                             }
-                            if let Err(e) = writeln!(file, "{}", definition) {
-                                eprintln!("❌ 写入定义失败: {}", e);
+                            
+                            // txt：关键字一行，定义一行；jsonl：每条记录一行JSON
+                            if let Err(e) = write_record(&mut file, export_format, &key, &definition) {
+                                eprintln!("❌ 写入词条失败: {}", e);
                                 break;
                             }
                             
@@ -72,33 +232,45 @@ fn main() {
                         }
                         
                         println!("✅ 成功导出{}条词条到文件: {}", count, output_file);
+                        if mispaired > 0 {
+                            println!("⚠️  跳过 {} 条关键字与定义错位的记录", mispaired);
+                        } else {
+                            println!("✅ 所有记录的关键字与定义均成对");
+                        }
+
+                        match write_metadata(mdx_file_path, &data, count, mispaired) {
+                            Ok(()) => println!("📝 词典元数据已写入: {}", METADATA_FILE),
+                            Err(e) => eprintln!("❌ 写入词典元数据失败: {}", e),
+                        }
                     },
                     Err(e) => {
                         eprintln!("❌ 创建输出文件失败: {}", e);
                     }
                 }
             } else if verbose {
-                // 详细模式：显示前10个词条
-                println!("\n📝 前10个词条:");
-                for (i, key) in keys.iter().take(10).enumerate() {
-                    println!("{}. {:?}", i + 1, key);
+                // 详细模式：从第skip条开始显示词条（默认10个）
+                let key_count = limit.unwrap_or(10);
+                println!("\n📝 第{}条起的{}个词条:", skip + 1, key_count);
+                for (i, key) in keys.iter().skip(skip).take(key_count).enumerate() {
+                    println!("{}. {:?}", skip + i + 1, key);
                 }
                 
-                // 显示前3个词条的详细信息
-                println!("\n📖 前3个词条的详细信息:");
-                for (i, item) in dict.items().enumerate() {
-                    if i >= 3 { break; }
-                    println!("{}. {:?}", i + 1, item);
+                // 显示词条的详细信息（默认3个）
+                let detail_count = limit.unwrap_or(3);
+                println!("\n📖 第{}条起的{}个词条的详细信息:", skip + 1, detail_count);
+                for (i, item) in dict.items().skip(skip).take(detail_count).enumerate() {
+                    println!("{}. {:?}", skip + i + 1, item);
                 }
             } else {
-                // 简洁模式：显示前5个词条的完整内容
-                println!("\n📝 前5个词条的完整内容:");
-                for (i, record) in dict.items().enumerate() {
-                    if i >= 5 { break; }
-                    println!("\n{}. 词条: {:?}", i + 1, record.key);
+                // 简洁模式：从第skip条开始显示词条的完整内容（默认5个）
+                let count = limit.unwrap_or(5);
+                println!("\n📝 第{}条起的{}个词条的完整内容:", skip + 1, count);
+                for (i, record) in dict.items().skip(skip).take(count).enumerate() {
+                    println!("\n{}. 词条: {:?}", skip + i + 1, record.key);
                     println!("   定义: {:?}", record.definition);
                 }
                 println!("\n💡 使用 --verbose 参数查看更多详细信息");
+                println!("💡 使用 --skip M --limit N 查看第M+1条起的N个词条");
                 println!("💡 使用 --export 参数导出前100条数据为txt格式");
             }
         },
@@ -107,4 +279,52 @@ fn main() {
             eprintln!("请确认文件路径是否正确: {}", mdx_file_path);
         }
     }
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mispair_reason() {
+        let html = r#"<link rel="stylesheet"><container data-id="1">本文</container>"#;
+        assert_eq!(mispair_reason("あい【愛】", html), None);
+        assert_eq!(mispair_reason("愛", "@@@LINK=あい【愛】"), None);
+        assert_eq!(mispair_reason("  ", html), Some("关键字为空"));
+        // 关键字与定义错开一行时，关键字位置上是上一条的定义
+        assert_eq!(mispair_reason(html, "こい【恋】"), Some("关键字行包含定义内容"));
+        assert_eq!(mispair_reason("あい【愛】", "こい【恋】"), Some("定义行缺少<container"));
+    }
+
+    #[test]
+    fn test_usize_flag() {
+        let args: Vec<String> = ["mdx_parser", "dict.mdx", "--verbose", "--skip", "5000", "--limit", "x"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        assert_eq!(usize_flag(&args, "--skip"), Ok(Some(5000)));
+        assert_eq!(usize_flag(&args, "--count"), Ok(None));
+        assert!(usize_flag(&args, "--limit").is_err());
+        // 参数缺少值时同样报错
+        assert!(usize_flag(&args[..4], "--skip").is_err());
+    }
+
+    #[test]
+    fn test_export_formats() {
+        assert_eq!(ExportFormat::parse("txt"), Some(ExportFormat::Txt));
+        assert_eq!(ExportFormat::parse("ndjson"), Some(ExportFormat::Jsonl));
+        assert_eq!(ExportFormat::parse("csv"), None);
+
+        let html = r#"<container data-id="1">"本文"</container>"#;
+        let mut txt = Vec::new();
+        write_record(&mut txt, ExportFormat::Txt, "あい【愛】", html).unwrap();
+        assert_eq!(String::from_utf8(txt).unwrap(), format!("あい【愛】\n{}\n", html));
+
+        // jsonl每条记录一行，引号等按JSON转义
+        let mut jsonl = Vec::new();
+        write_record(&mut jsonl, ExportFormat::Jsonl, "あい【愛】", html).unwrap();
+        let line = String::from_utf8(jsonl).unwrap();
+        assert_eq!(line.lines().count(), 1);
+        let record: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(record, serde_json::json!({ "key": "あい【愛】", "definition": html }));
+    }
+}
@@ -0,0 +1,160 @@
+//! 导出文件的压缩；gzip和zstd需要 `compression` feature（默认开启），关闭时只支持不压缩
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+#[cfg(feature = "compression")]
+use flate2::write::GzEncoder;
+
+/// zstd的默认压缩级别（与zstd命令行工具相同）
+pub const DEFAULT_ZSTD_LEVEL: i32 = 3;
+
+/// zstd的最高压缩级别
+const MAX_ZSTD_LEVEL: i32 = 22;
+
+/// 导出文件的压缩方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Compression {
+    /// 不压缩（默认）
+    #[default]
+    None,
+    /// gzip（deflate）
+    Gzip,
+    /// zstd，附带压缩级别（1-22）
+    Zstd(i32),
+}
+
+impl std::str::FromStr for Compression {
+    type Err = String;
+
+    /// 解析 `none`、`gzip`、`zstd` 或 `zstd:<级别>`
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if !cfg!(feature = "compression") && value != "none" {
+            return Err(format!("不支持的压缩方式 {:?}：编译时未启用compression feature，只支持none", value));
+        }
+        match value.split_once(':') {
+            None => match value {
+                "none" => Ok(Compression::None),
+                "gzip" | "gz" => Ok(Compression::Gzip),
+                "zstd" => Ok(Compression::Zstd(DEFAULT_ZSTD_LEVEL)),
+                _ => Err(format!("不支持的压缩方式 {:?}，支持: none, gzip, zstd[:级别]", value)),
+            },
+            Some(("zstd", level)) => match level.trim().parse::<i32>() {
+                Ok(parsed) if (1..=MAX_ZSTD_LEVEL).contains(&parsed) => Ok(Compression::Zstd(parsed)),
+                _ => Err(format!("zstd压缩级别必须是1-{}的整数: {:?}", MAX_ZSTD_LEVEL, level)),
+            },
+            Some(_) => Err(format!("只有zstd可以指定压缩级别: {:?}", value)),
+        }
+    }
+}
+
+/// 按压缩方式写出的导出文件，写完后必须调用finish写入压缩流的结尾
+pub enum CompressedWriter {
+    Plain(BufWriter<File>),
+    #[cfg(feature = "compression")]
+    Gzip(GzEncoder<BufWriter<File>>),
+    #[cfg(feature = "compression")]
+    Zstd(zstd::Encoder<'static, BufWriter<File>>),
+}
+
+impl CompressedWriter {
+    /// 创建输出文件，按compression包装压缩编码器
+    pub fn create(path: &str, compression: Compression) -> std::io::Result<Self> {
+        let file = BufWriter::new(File::create(path)?);
+        Ok(match compression {
+            Compression::None => CompressedWriter::Plain(file),
+            #[cfg(feature = "compression")]
+            Compression::Gzip => CompressedWriter::Gzip(GzEncoder::new(file, flate2::Compression::default())),
+            #[cfg(feature = "compression")]
+            Compression::Zstd(level) => CompressedWriter::Zstd(zstd::Encoder::new(file, level)?),
+            #[cfg(not(feature = "compression"))]
+            Compression::Gzip | Compression::Zstd(_) => {
+                return Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "编译时未启用compression feature"));
+            }
+        })
+    }
+
+    /// 结束压缩流并刷新到文件
+    pub fn finish(self) -> std::io::Result<()> {
+        match self {
+            CompressedWriter::Plain(mut file) => file.flush(),
+            #[cfg(feature = "compression")]
+            CompressedWriter::Gzip(encoder) => encoder.finish()?.flush(),
+            #[cfg(feature = "compression")]
+            CompressedWriter::Zstd(encoder) => encoder.finish()?.flush(),
+        }
+    }
+}
+
+impl Write for CompressedWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            CompressedWriter::Plain(file) => file.write(buf),
+            #[cfg(feature = "compression")]
+            CompressedWriter::Gzip(encoder) => encoder.write(buf),
+            #[cfg(feature = "compression")]
+            CompressedWriter::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            CompressedWriter::Plain(file) => file.flush(),
+            #[cfg(feature = "compression")]
+            CompressedWriter::Gzip(encoder) => encoder.flush(),
+            #[cfg(feature = "compression")]
+            CompressedWriter::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "compression")]
+    fn test_parse_compression() {
+        assert_eq!("gzip".parse(), Ok(Compression::Gzip));
+        assert_eq!("zstd".parse(), Ok(Compression::Zstd(DEFAULT_ZSTD_LEVEL)));
+        assert_eq!("zstd:19".parse(), Ok(Compression::Zstd(19)));
+        assert!("zstd:0".parse::<Compression>().is_err());
+        assert!("gzip:9".parse::<Compression>().is_err());
+        assert!("brotli".parse::<Compression>().is_err());
+    }
+
+    #[test]
+    #[cfg(not(feature = "compression"))]
+    fn test_compression_disabled() {
+        assert_eq!("none".parse(), Ok(Compression::None));
+        assert!("gzip".parse::<Compression>().is_err());
+        assert!("zstd:3".parse::<Compression>().is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "compression")]
+    fn test_compressed_round_trip() {
+        use std::io::Read;
+
+        let content = "{\"headword\":\"あい【愛】\"}\n".repeat(100);
+        let path = std::env::temp_dir().join(format!("compressed_writer_test_{}", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        for compression in [Compression::None, Compression::Gzip, Compression::Zstd(DEFAULT_ZSTD_LEVEL)] {
+            let mut writer = CompressedWriter::create(path, compression).unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+            writer.finish().unwrap();
+
+            let file = File::open(path).unwrap();
+            let mut decoded = String::new();
+            match compression {
+                Compression::None => std::io::BufReader::new(file).read_to_string(&mut decoded),
+                Compression::Gzip => flate2::read::GzDecoder::new(file).read_to_string(&mut decoded),
+                Compression::Zstd(_) => zstd::Decoder::new(file).unwrap().read_to_string(&mut decoded),
+            }
+            .unwrap();
+            assert_eq!(decoded, content, "{:?}", compression);
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+}
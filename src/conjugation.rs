@@ -0,0 +1,244 @@
+//! 根据词性生成动词、形容词的活用形，用于 /conjugate 接口
+
+/// 可以生成活用形的词类（由词典的词性文本判断）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConjugationClass {
+    /// 五段动词（自五、他五）
+    Godan,
+    /// 一段动词（上一、下一）
+    Ichidan,
+    /// サ行变格动词（サ変，名词兼サ変时词典形为 名词+する）
+    Suru,
+    /// カ行变格动词（来る）
+    Kuru,
+    /// 形容词（形）
+    Adjective,
+    /// 形容动词（形動）
+    AdjectivalNoun,
+}
+
+/// 五段动词词尾对应的 (ア段, イ段, エ段, オ段, て形, た形)
+const GODAN_ENDINGS: [(char, &str, &str, &str, &str, &str, &str); 9] = [
+    ('う', "わ", "い", "え", "お", "って", "った"),
+    ('く', "か", "き", "け", "こ", "いて", "いた"),
+    ('ぐ', "が", "ぎ", "げ", "ご", "いで", "いだ"),
+    ('す', "さ", "し", "せ", "そ", "して", "した"),
+    ('つ', "た", "ち", "て", "と", "って", "った"),
+    ('ぬ', "な", "に", "ね", "の", "んで", "んだ"),
+    ('ぶ', "ば", "び", "べ", "ぼ", "んで", "んだ"),
+    ('む', "ま", "み", "め", "も", "んで", "んだ"),
+    ('る', "ら", "り", "れ", "ろ", "って", "った"),
+];
+
+/// 从词性文本判断词类，如 "（自五）"、"他下一"、"名・他サ変"、"形動"
+/// 词性由中点分隔多项时取第一个可活用的一项，文语活用（四段、ナ変、形ク等）返回None
+pub fn conjugation_class(part_of_speech: &str) -> Option<ConjugationClass> {
+    let cleaned: String = part_of_speech
+        .chars()
+        .filter(|ch| !matches!(ch, '（' | '）' | '(' | ')' | '〘' | '〙' | '〔' | '〕') && !ch.is_whitespace())
+        .collect();
+
+    cleaned.split(['・', '･', '、']).find_map(|part| {
+        let part = part.trim_start_matches(['自', '他']);
+        match part {
+            "五" => Some(ConjugationClass::Godan),
+            "上一" | "下一" => Some(ConjugationClass::Ichidan),
+            "サ変" => Some(ConjugationClass::Suru),
+            "カ変" => Some(ConjugationClass::Kuru),
+            "形" => Some(ConjugationClass::Adjective),
+            "形動" | "形動ダ" => Some(ConjugationClass::AdjectivalNoun),
+            _ => None,
+        }
+    })
+}
+
+/// 确定用于活用的词典形：词尾假名与读音一致时优先用汉字表记（足搔く），否则用读音（あがく）
+/// 名词兼サ変的词条（愛・あい）补上する
+pub fn dictionary_form(kana_reading: Option<&str>, kanji_writing: Option<&str>, class: ConjugationClass) -> Option<String> {
+    let reading = kana_reading.filter(|reading| !reading.is_empty());
+    let kanji = kanji_writing.filter(|kanji| !kanji.is_empty());
+    let base = match (reading, kanji) {
+        (Some(reading), Some(kanji)) if reading.chars().last() == kanji.chars().last() => kanji,
+        (Some(reading), Some(kanji)) if class == ConjugationClass::Suru && !reading.ends_with("する") => kanji,
+        (Some(reading), _) => reading,
+        (None, kanji) => kanji?,
+    };
+
+    if class == ConjugationClass::Suru && !base.ends_with("する") {
+        return Some(format!("{}する", base));
+    }
+    Some(base.to_string())
+}
+
+/// 生成词典形的活用形：(活用形名称, 活用后的形式)
+/// 词典形的词尾与词类不符（如标为五段却不以ウ段假名结尾）时返回空列表
+pub fn conjugate(word: &str, class: ConjugationClass) -> Vec<(&'static str, String)> {
+    match class {
+        ConjugationClass::Godan => conjugate_godan(word),
+        ConjugationClass::Ichidan => word
+            .strip_suffix('る')
+            .map(|stem| {
+                verb_forms(
+                    [
+                        "ない", "ます", "た", "て", "れば", "よう", "られる", "られる", "させる", "ろ",
+                    ]
+                    .map(|suffix| format!("{}{}", stem, suffix)),
+                )
+            })
+            .unwrap_or_default(),
+        ConjugationClass::Suru => word
+            .strip_suffix("する")
+            .map(|stem| {
+                // 单字汉语词的可能形为～せる（愛せる、察せる），其余为～できる（勉強できる）
+                let potential = if stem.chars().count() == 1 { "せる" } else { "できる" };
+                verb_forms(
+                    [
+                        "しない", "します", "した", "して", "すれば", "しよう", potential, "される", "させる", "しろ",
+                    ]
+                    .map(|suffix| format!("{}{}", stem, suffix)),
+                )
+            })
+            .unwrap_or_default(),
+        ConjugationClass::Kuru => {
+            if let Some(stem) = word.strip_suffix('る').filter(|stem| stem.ends_with('来')) {
+                // 汉字表记时词干不变
+                verb_forms(
+                    [
+                        "ない", "ます", "た", "て", "れば", "よう", "られる", "られる", "させる", "い",
+                    ]
+                    .map(|suffix| format!("{}{}", stem, suffix)),
+                )
+            } else if let Some(stem) = word.strip_suffix("くる") {
+                verb_forms(
+                    [
+                        "こない", "きます", "きた", "きて", "くれば", "こよう", "こられる", "こられる", "こさせる", "こい",
+                    ]
+                    .map(|suffix| format!("{}{}", stem, suffix)),
+                )
+            } else {
+                Vec::new()
+            }
+        }
+        ConjugationClass::Adjective => {
+            // いい及其复合词（かっこいい、格好いい）的活用用よい的词干（よくない、よかった）
+            // かわいい等词尾碰巧为いい的形容词照常活用
+            let stem = match word.strip_suffix("いい").filter(|prefix| is_ii_compound_prefix(prefix)) {
+                Some(prefix) => Some(format!("{}よ", prefix)),
+                None => word.strip_suffix('い').map(str::to_string),
+            };
+            let Some(stem) = stem else { return Vec::new() };
+            ["negative", "past", "past_negative", "te", "conditional", "adverbial"]
+                .into_iter()
+                .zip(["くない", "かった", "くなかった", "くて", "ければ", "く"])
+                .map(|(name, suffix)| (name, format!("{}{}", stem, suffix)))
+                .chain(std::iter::once(("polite", format!("{}です", word))))
+                .collect()
+        }
+        ConjugationClass::AdjectivalNoun => {
+            let stem = word.strip_suffix('だ').unwrap_or(word);
+            ["attributive", "negative", "past", "te", "conditional", "adverbial", "polite"]
+                .into_iter()
+                .zip(["な", "ではない", "だった", "で", "なら", "に", "です"])
+                .map(|(name, suffix)| (name, format!("{}{}", stem, suffix)))
+                .collect()
+        }
+    }
+}
+
+/// 词尾いい之前的部分是否构成いい的复合词：いい本身、汉字结尾（格好いい）或っこ结尾（かっこいい）
+fn is_ii_compound_prefix(prefix: &str) -> bool {
+    match prefix.chars().last() {
+        None => true,
+        Some(last) => ('\u{4e00}'..='\u{9fff}').contains(&last) || prefix.ends_with("っこ"),
+    }
+}
+
+/// 动词活用形的名称，与verb_forms的参数顺序对应
+const VERB_FORM_NAMES: [&str; 10] = [
+    "negative", "polite", "past", "te", "conditional", "volitional", "potential", "passive", "causative", "imperative",
+];
+
+fn verb_forms(forms: [String; 10]) -> Vec<(&'static str, String)> {
+    VERB_FORM_NAMES.into_iter().zip(forms).collect()
+}
+
+fn conjugate_godan(word: &str) -> Vec<(&'static str, String)> {
+    let Some(last) = word.chars().last() else { return Vec::new() };
+    let Some(&(_, a, i, e, o, te, ta)) = GODAN_ENDINGS.iter().find(|ending| ending.0 == last) else {
+        return Vec::new();
+    };
+    let stem = &word[..word.len() - last.len_utf8()];
+
+    // 行く的て形・た形为促音（行って），ある的否定形为ない
+    let (te, ta) = if word.ends_with("行く") || word == "いく" || word == "ゆく" { ("って", "った") } else { (te, ta) };
+    let negative = if matches!(word, "ある" | "有る" | "在る") { "ない".to_string() } else { format!("{}{}ない", stem, a) };
+
+    verb_forms([
+        negative,
+        format!("{}{}ます", stem, i),
+        format!("{}{}", stem, ta),
+        format!("{}{}", stem, te),
+        format!("{}{}ば", stem, e),
+        format!("{}{}う", stem, o),
+        format!("{}{}る", stem, e),
+        format!("{}{}れる", stem, a),
+        format!("{}{}せる", stem, a),
+        format!("{}{}", stem, e),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn form<'a>(forms: &'a [(&'static str, String)], name: &str) -> &'a str {
+        forms.iter().find(|(form_name, _)| *form_name == name).map(|(_, surface)| surface.as_str()).unwrap()
+    }
+
+    #[test]
+    fn test_conjugation_class() {
+        assert_eq!(conjugation_class("（自五）"), Some(ConjugationClass::Godan));
+        assert_eq!(conjugation_class("他下一"), Some(ConjugationClass::Ichidan));
+        assert_eq!(conjugation_class("名・他サ変"), Some(ConjugationClass::Suru));
+        assert_eq!(conjugation_class("形動"), Some(ConjugationClass::AdjectivalNoun));
+        assert_eq!(conjugation_class("形"), Some(ConjugationClass::Adjective));
+        assert_eq!(conjugation_class("名"), None);
+        assert_eq!(conjugation_class("自四"), None);
+    }
+
+    #[test]
+    fn test_conjugate_godan() {
+        let forms = conjugate("あがく", ConjugationClass::Godan);
+        assert_eq!(form(&forms, "negative"), "あがかない");
+        assert_eq!(form(&forms, "te"), "あがいて");
+        assert_eq!(form(&forms, "volitional"), "あがこう");
+
+        let forms = conjugate("行く", ConjugationClass::Godan);
+        assert_eq!(form(&forms, "past"), "行った");
+        assert_eq!(form(&forms, "negative"), "行かない");
+
+        assert_eq!(form(&conjugate("買う", ConjugationClass::Godan), "negative"), "買わない");
+        assert!(conjugate("食べ", ConjugationClass::Godan).is_empty());
+    }
+
+    #[test]
+    fn test_conjugate_other_classes() {
+        assert_eq!(form(&conjugate("食べる", ConjugationClass::Ichidan), "te"), "食べて");
+        assert_eq!(form(&conjugate("愛する", ConjugationClass::Suru), "potential"), "愛せる");
+        assert_eq!(form(&conjugate("勉強する", ConjugationClass::Suru), "potential"), "勉強できる");
+        assert_eq!(form(&conjugate("来る", ConjugationClass::Kuru), "negative"), "来ない");
+        assert_eq!(form(&conjugate("くる", ConjugationClass::Kuru), "negative"), "こない");
+        assert_eq!(form(&conjugate("いい", ConjugationClass::Adjective), "past"), "よかった");
+        assert_eq!(form(&conjugate("かっこいい", ConjugationClass::Adjective), "negative"), "かっこよくない");
+        assert_eq!(form(&conjugate("かわいい", ConjugationClass::Adjective), "past"), "かわいかった");
+        assert_eq!(form(&conjugate("静か", ConjugationClass::AdjectivalNoun), "attributive"), "静かな");
+    }
+
+    #[test]
+    fn test_dictionary_form() {
+        assert_eq!(dictionary_form(Some("あがく"), Some("足搔く"), ConjugationClass::Godan).as_deref(), Some("足搔く"));
+        assert_eq!(dictionary_form(Some("あい"), Some("愛"), ConjugationClass::Suru).as_deref(), Some("愛する"));
+        assert_eq!(dictionary_form(Some("あい"), None, ConjugationClass::Suru).as_deref(), Some("あいする"));
+        assert_eq!(dictionary_form(Some("たべる"), Some("食"), ConjugationClass::Ichidan).as_deref(), Some("たべる"));
+    }
+}
@@ -0,0 +1,111 @@
+//! CSV读写（RFC 4180）：字段含分隔符、引号或换行时用双引号包裹，引号写成两个引号
+
+/// CSV中的一条记录及其起始行号（从1开始，用于报错）
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvRecord {
+    pub line: usize,
+    pub fields: Vec<String>,
+}
+
+/// 解析CSV文本，支持引号内的分隔符和换行；跳过完全空白的行
+/// 引号未闭合时返回错误
+pub fn parse_records(content: &str, delimiter: char) -> Result<Vec<CsvRecord>, String> {
+    let content = content.strip_prefix('\u{FEFF}').unwrap_or(content);
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut chars = content.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        if in_quotes {
+            match ch {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                _ => {
+                    if ch == '\n' {
+                        line += 1;
+                    }
+                    field.push(ch);
+                }
+            }
+            continue;
+        }
+
+        match ch {
+            '"' if field.is_empty() => in_quotes = true,
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                fields.push(std::mem::take(&mut field));
+                push_record(&mut records, std::mem::take(&mut fields), record_line);
+                line += 1;
+                record_line = line;
+            }
+            _ if ch == delimiter => fields.push(std::mem::take(&mut field)),
+            _ => field.push(ch),
+        }
+    }
+
+    if in_quotes {
+        return Err(format!("第{}行开始的记录中引号未闭合", record_line));
+    }
+    if !field.is_empty() || !fields.is_empty() {
+        fields.push(field);
+        push_record(&mut records, fields, record_line);
+    }
+    Ok(records)
+}
+
+fn push_record(records: &mut Vec<CsvRecord>, fields: Vec<String>, line: usize) {
+    let blank = fields.iter().all(|field| field.trim().is_empty());
+    if !blank {
+        records.push(CsvRecord { line, fields });
+    }
+}
+
+/// 将一条记录格式化为CSV行（不含换行符）
+pub fn format_record<S: AsRef<str>>(fields: &[S], delimiter: char) -> String {
+    fields
+        .iter()
+        .map(|field| {
+            let field = field.as_ref();
+            if field.contains(delimiter) || field.contains(['"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(&delimiter.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_with_quotes_and_newlines() {
+        let fields = ["1", "あい【愛】", "❶かわいがる\n❷こいしたう", "say \"hi\", ok", ""];
+        let line = format_record(&fields, ',');
+        assert_eq!(line, "1,あい【愛】,\"❶かわいがる\n❷こいしたう\",\"say \"\"hi\"\", ok\",");
+
+        let content = format!("a,b,c,d,e\r\n{}\r\n\r\n", line);
+        let records = parse_records(&content, ',').unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].line, 2);
+        assert_eq!(records[1].fields, fields);
+    }
+
+    #[test]
+    fn test_custom_delimiter_and_unclosed_quote() {
+        let records = parse_records("a;b\n1;\"x;y\"", ';').unwrap();
+        assert_eq!(records[1].fields, vec!["1", "x;y"]);
+
+        assert!(parse_records("a,b\n1,\"open", ',').is_err());
+    }
+}
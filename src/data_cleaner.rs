@@ -1,8 +1,132 @@
 use std::fs::File;
 use std::io::{BufRead, BufReader, Write};
+use std::cell::Cell;
 use std::collections::HashMap;
 use scraper::{Html, Selector};
 
+/// 清理选项 - 控制写出的HTML中去除哪些内容
+/// 默认保留完整的HTML片段；注意导入时依赖样式表link行和data-id属性识别词条，
+/// 去除它们后的文件只适合其他工具使用
+#[derive(Debug, Clone, Copy)]
+pub struct CleanOptions {
+    /// 去除<link>样式表标签
+    pub strip_links: bool,
+    /// 保留data-id、data-type等data-*属性
+    pub keep_data_attrs: bool,
+    /// 保留HTML注释
+    pub keep_comments: bool,
+}
+
+impl Default for CleanOptions {
+    fn default() -> Self {
+        CleanOptions {
+            strip_links: false,
+            keep_data_attrs: true,
+            keep_comments: true,
+        }
+    }
+}
+
+impl CleanOptions {
+    /// 需要从HTML中删除的内容对应的正则表达式
+    fn strip_patterns(&self) -> Vec<regex::Regex> {
+        let mut patterns = Vec::new();
+        if self.strip_links {
+            patterns.push(regex::Regex::new(r"<link\b[^>]*>").unwrap());
+        }
+        if !self.keep_data_attrs {
+            patterns.push(regex::Regex::new(r#"\s+data-[\w-]+(="[^"]*")?"#).unwrap());
+        }
+        if !self.keep_comments {
+            patterns.push(regex::Regex::new(r"(?s)<!--.*?-->").unwrap());
+        }
+        patterns
+    }
+}
+
+/// 去除不可见字符：C0/C1控制字符（换行除外）、零宽字符和BOM
+/// 这些字符会导致精确匹配失败，导入时用于清理标题、读音、释义等文本字段
+pub fn strip_invisible(s: &str) -> String {
+    s.chars()
+        .filter(|&ch| {
+            let is_control = ch.is_control() && ch != '\n';
+            let is_zero_width = matches!(ch, '\u{200B}'..='\u{200F}' | '\u{2060}' | '\u{FEFF}');
+            !is_control && !is_zero_width
+        })
+        .collect()
+}
+
+/// 首字不是假名的词条（英文缩写、符号等）写入的文件名
+const MISC_SPLIT_FILE: &str = "misc.txt";
+
+/// 按标题首字所在的五十音行拆分清理后的文件，写到output_dir下的あ行.txt、か行.txt…，
+/// 首字不是假名的词条写入misc.txt。词条以空行分隔，首行为标题，内容按原样写出（不要求是合法的UTF-8）
+/// 返回各文件名及其词条数，按五十音顺序排列，misc.txt在最后；没有词条的文件不创建
+pub fn split_by_kana_row(input_path: &str, output_dir: &str) -> Result<Vec<(String, usize)>, Box<dyn std::error::Error>> {
+    std::fs::create_dir_all(output_dir)?;
+    let row_count = crate::kana::GOJUON_ROW_NAMES.len();
+    // 下标row_count为misc.txt
+    let mut writers: Vec<Option<std::io::BufWriter<File>>> = (0..=row_count).map(|_| None).collect();
+    let mut counts = vec![0usize; row_count + 1];
+    let file_name = |row: usize| match crate::kana::GOJUON_ROW_NAMES.get(row) {
+        Some(name) => format!("{}.txt", name),
+        None => MISC_SPLIT_FILE.to_string(),
+    };
+
+    let mut reader = BufReader::new(File::open(input_path)?);
+    let mut block: Vec<Vec<u8>> = Vec::new();
+    let mut flush_block = |block: &mut Vec<Vec<u8>>| -> std::io::Result<()> {
+        let Some(title) = block.first() else {
+            return Ok(());
+        };
+        let row = crate::kana::gojuon_row(String::from_utf8_lossy(title).trim()).unwrap_or(row_count);
+        if writers[row].is_none() {
+            let path = std::path::Path::new(output_dir).join(file_name(row));
+            writers[row] = Some(std::io::BufWriter::new(File::create(path)?));
+        }
+        let writer = writers[row].as_mut().unwrap();
+        for line in block.iter() {
+            writer.write_all(line)?;
+            writer.write_all(b"\n")?;
+        }
+        writer.write_all(b"\n")?;
+        counts[row] += 1;
+        block.clear();
+        Ok(())
+    };
+
+    let mut buffer = Vec::new();
+    loop {
+        buffer.clear();
+        if reader.read_until(b'\n', &mut buffer)? == 0 {
+            break;
+        }
+        if buffer.ends_with(b"\n") {
+            buffer.pop();
+            if buffer.ends_with(b"\r") {
+                buffer.pop();
+            }
+        }
+        if buffer.iter().all(u8::is_ascii_whitespace) {
+            // 空行表示词条结束
+            flush_block(&mut block)?;
+        } else {
+            block.push(buffer.clone());
+        }
+    }
+    flush_block(&mut block)?;
+
+    for writer in writers.iter_mut().flatten() {
+        writer.flush()?;
+    }
+    Ok(counts
+        .iter()
+        .enumerate()
+        .filter(|(_, count)| **count > 0)
+        .map(|(row, count)| (file_name(row), *count))
+        .collect())
+}
+
 /// 数据清理器 - 用于清理exported_dict_full.txt文件
 pub struct DataCleaner {
     /// 重定向映射表：{重定向源 -> 目标词条}
@@ -11,8 +135,14 @@ pub struct DataCleaner {
     pub valid_entries: usize,
     /// 重定向条目统计  
     pub redirect_entries: usize,
-    /// 是否已遇到分界点词条
-    boundary_reached: bool,
+    /// analyze_file_structure检测到的分界点（汉字重定向区域开始）的行号，未检测到时为None
+    boundary_line: Option<usize>,
+    /// 分析时从总行数的该比例处开始查找分界点（汉字重定向区域位于文件后部）
+    pub boundary_search_fraction: f64,
+    /// 写出HTML时的清理选项
+    pub options: CleanOptions,
+    /// clean_kana_text把非空输入清理为空字符串的次数（假名全是符号，无法解析）
+    empty_kana_results: Cell<usize>,
 }
 
 impl DataCleaner {
@@ -21,22 +151,52 @@ impl DataCleaner {
             redirect_map: HashMap::new(),
             valid_entries: 0,
             redirect_entries: 0,
-            boundary_reached: false,
+            boundary_line: None,
+            boundary_search_fraction: 0.5,
+            options: CleanOptions::default(),
+            empty_kana_results: Cell::new(0),
+        }
+    }
+
+    /// 最近一次analyze_file_structure检测到的分界点行号（从1开始），未检测到时为None
+    pub fn boundary_line(&self) -> Option<usize> {
+        self.boundary_line
+    }
+
+    /// clean_kana_text把非空输入清理为空字符串的次数，用于统计需要人工整理的词条
+    pub fn empty_kana_count(&self) -> usize {
+        self.empty_kana_results.get()
+    }
+
+    /// 使用指定的清理选项创建清理器
+    pub fn with_options(options: CleanOptions) -> Self {
+        DataCleaner {
+            options,
+            ..Self::new()
         }
     }
 
     /// 清理exported_dict_full.txt文件
     /// 提取所有包含HTML内容的词条，智能解析标题和内容
-    pub fn clean_exported_dict(&mut self, input_path: &str, output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    /// `dry_run` 为true时只解析和统计，不写出清理后的文件
+    pub fn clean_exported_dict(&mut self, input_path: &str, output_path: &str, dry_run: bool) -> Result<(), Box<dyn std::error::Error>> {
         println!("🚀 开始清理文件: {}", input_path);
+        if dry_run {
+            println!("🧪 dry-run模式：只统计，不写出文件");
+        }
         
         let input_file = File::open(input_path)?;
         let reader = BufReader::new(input_file);
         
-        let mut output_file = File::create(output_path)?;
+        let mut output_file = if dry_run {
+            None
+        } else {
+            Some(File::create(output_path)?)
+        };
         
         let mut lines = reader.lines();
         let mut current_title: Option<String> = None;
+        let strip_patterns = self.options.strip_patterns();
         
         while let Some(line_result) = lines.next() {
             let line = line_result?;
@@ -48,7 +208,13 @@ impl DataCleaner {
             // 检测重定向行
             if line.starts_with("@@@LINK=") {
                 let target = line.strip_prefix("@@@LINK=").unwrap().trim().to_string();
-                // 提取前一行可能的标题（这个逻辑我们暂时简化）
+                // 前一行的标题即为重定向源
+                if let Some(source) = current_title.take() {
+                    let source = source.trim().to_string();
+                    if !source.is_empty() && !target.is_empty() {
+                        self.redirect_map.insert(source, target);
+                    }
+                }
                 self.redirect_entries += 1;
                 continue;
             }
@@ -73,9 +239,11 @@ impl DataCleaner {
                 };
                 
                 // 输出格式：标题\nHTML内容\n空行
-                writeln!(output_file, "{}", title)?;
-                writeln!(output_file, "{}", line)?;
-                writeln!(output_file)?; // 空行分隔
+                if let Some(output_file) = output_file.as_mut() {
+                    writeln!(output_file, "{}", title)?;
+                    writeln!(output_file, "{}", Self::strip_html(&line, &strip_patterns))?;
+                    writeln!(output_file)?; // 空行分隔
+                }
                 
                 self.valid_entries += 1;
                 continue;
@@ -89,11 +257,25 @@ impl DataCleaner {
         println!("📊 统计信息:");
         println!("  - 有效词条: {}", self.valid_entries);
         println!("  - 重定向条目: {}", self.redirect_entries);
-        println!("  - 清理后文件: {}", output_path);
+        if self.empty_kana_count() > 0 {
+            println!("  - ⚠️  假名无法解析（清理后为空）: {}", self.empty_kana_count());
+        }
+        if dry_run {
+            println!("  - 清理后文件: （dry-run，未写出）");
+        } else {
+            println!("  - 清理后文件: {}", output_path);
+        }
         
         Ok(())
     }
     
+    /// 按清理选项删除HTML中的内容
+    fn strip_html(html: &str, patterns: &[regex::Regex]) -> String {
+        patterns
+            .iter()
+            .fold(html.to_string(), |html, pattern| pattern.replace_all(&html, "").into_owned())
+    }
+
     /// 从标题行（headline）中提取标题
     fn extract_title_from_headline(&self, headline: &str) -> String {
         // 保留原始headline格式，只做最基本的清理
@@ -116,34 +298,6 @@ impl DataCleaner {
         result.trim().to_string()
     }
     
-    /// 为标题清理汉字文本，保留更多符号
-    fn clean_kanji_text_for_title(&self, text: &str) -> String {
-        let mut result = String::new();
-        
-        for ch in text.chars() {
-            match ch {
-                // 保留汉字 (CJK统一汉字)
-                '\u{4e00}'..='\u{9fff}' => result.push(ch),
-                // 保留平假名
-                '\u{3040}'..='\u{309f}' => result.push(ch),
-                // 保留片假名
-                '\u{30a0}'..='\u{30ff}' => result.push(ch),
-                // 保留更多基本符号
-                '・' | '‧' | '·' | '-' | 'ー' | '〔' | '〕' | '（' | '）' => result.push(ch),
-                // 只过滤掉一些明显的装饰符号
-                '◇' | '△' | '▽' | '▲' | '▼' | '○' | '●' | '◯' | '□' | '■' | 
-                '▢' | '▣' | '◆' | '※' | '＊' | '☆' | '★' => {
-                    // 跳过这些标记符号
-                },
-                // 保留其他可能有用的字符（如英文、数字）
-                _ if ch.is_alphanumeric() => result.push(ch),
-                _ => {} // 跳过其他特殊符号
-            }
-        }
-        
-        result.trim().to_string()
-    }
-    
     /// 从HTML内容中提取标题（备用方法）
     fn extract_title_from_html(&self, html: &str) -> String {
         // 解析HTML
@@ -187,14 +341,9 @@ impl DataCleaner {
             kana_reading
         } else {
             // 如果找不到标题，尝试提取data-id作为标识
-            if let Some(container) = document.select(&Selector::parse("container").unwrap()).next() {
-                if let Some(data_id) = container.value().attr("data-id") {
-                    format!("entry_{}", data_id)
-                } else {
-                    "unknown_entry".to_string()
-                }
-            } else {
-                "unknown_entry".to_string()
+            match self.extract_data_id(html) {
+                Some(data_id) => format!("entry_{}", data_id),
+                None => "unknown_entry".to_string(),
             }
         }
     }
@@ -227,16 +376,6 @@ impl DataCleaner {
         result.trim().to_string()
     }
     
-    /// 清理HTML标签，只保留文本内容（保留作为备用方法）
-    fn clean_html_tags(&self, html: &str) -> String {
-        // 使用正则表达式移除所有HTML标签
-        let re = regex::Regex::new(r"<[^>]*>").unwrap();
-        let cleaned = re.replace_all(html, "");
-        
-        // 清理多余的空白
-        cleaned.trim().to_string()
-    }
-
     /// 清理假名读音，去除特殊符号
     fn clean_kana_text(&self, text: &str) -> String {
         let mut result = String::new();
@@ -258,14 +397,13 @@ impl DataCleaner {
                 _ => {} // 跳过其他特殊符号
             }
         }
-        result.trim().to_string()
+        let result = result.trim().to_string();
+        if result.is_empty() && !text.trim().is_empty() {
+            self.empty_kana_results.set(self.empty_kana_results.get() + 1);
+        }
+        result
     }
     
-    /// 检查是否是汉字重定向的开始
-    fn is_kanji_redirect_start(&self, line: &str) -> bool {
-        line.contains("漢字重定向") || line.contains("kanji redirect")
-    }
-
     /// 检查HTML内容是否是重定向词条
     fn is_html_redirect(&self, html: &str) -> bool {
         // 检查是否包含重定向链接模式
@@ -286,39 +424,32 @@ impl DataCleaner {
         false
     }
     
-    /// 检测是否到达分界点（汉字重定向区域开始）
-    fn is_boundary_reached(&self, current_line: &str) -> Result<bool, Box<dyn std::error::Error>> {
-        // 精确的分界点检测：使用data-id="3011400"作为分界点
-        // 这是ヴォ词条，之后紧接着就是汉字重定向区域
-        
-        if current_line.contains("data-id=") {
-            if let Some(id_str) = self.extract_data_id(current_line) {
-                if id_str == "3011400" {
-                    println!("🎯 检测到精确分界点: data-id={} (ヴォ词条)", id_str);
-                    println!("📍 此词条之后开始汉字重定向区域，将在处理完此词条后停止");
-                    return Ok(false); // 先处理完这个词条
-                }
-            }
-        }
-        
-        // 检测汉字行：如果已经处理了data-id=3011400的词条，遇到汉字行就停止
-        let line = current_line.trim();
-        if line.len() >= 1 && line.len() <= 3 && self.is_likely_kanji_only(line) {
-            if self.valid_entries > 0 {
-                println!("🔍 确认分界点: 汉字行 '{}' (已处理{}个词条)", line, self.valid_entries);
-                return Ok(true);
-            }
-        }
-        
-        Ok(false)
-    }
-    
     /// 从HTML行中提取data-id值
+    /// 支持单引号、双引号以及等号两侧的空白，如 data-id = '123'；属性名前必须是空白（xdata-id不算）
     fn extract_data_id(&self, line: &str) -> Option<String> {
-        if let Some(start) = line.find("data-id=\"") {
-            let start = start + 9; // "data-id=\"".len()
-            if let Some(end) = line[start..].find("\"") {
-                return Some(line[start..start + end].to_string());
+        const ATTR: &str = "data-id";
+
+        let mut search_from = 0;
+        while let Some(found) = line[search_from..].find(ATTR) {
+            let start = search_from + found;
+            let after_name = start + ATTR.len();
+            search_from = after_name;
+
+            if !line[..start].ends_with(char::is_whitespace) {
+                continue;
+            }
+
+            let rest = line[after_name..].trim_start();
+            let Some(rest) = rest.strip_prefix('=') else {
+                continue;
+            };
+            let rest = rest.trim_start();
+            let Some(quote) = rest.chars().next().filter(|ch| *ch == '"' || *ch == '\'') else {
+                continue;
+            };
+            let value = &rest[quote.len_utf8()..];
+            if let Some(end) = value.find(quote) {
+                return Some(value[..end].to_string());
             }
         }
         None
@@ -343,16 +474,21 @@ impl DataCleaner {
     }
     
     /// 分析文件结构，不进行清理，只统计
+    /// 先统计总行数，再从 `boundary_search_fraction` 对应的行开始查找分界点
     pub fn analyze_file_structure(&mut self, file_path: &str) -> Result<(), Box<dyn std::error::Error>> {
         println!("🔍 分析文件结构: {}", file_path);
         
+        // 预先统计总行数，用于确定分界点的查找起点
+        let line_count = BufReader::new(File::open(file_path)?).split(b'\n').count();
+        let search_start = (line_count as f64 * self.boundary_search_fraction) as usize;
+        
         let file = File::open(file_path)?;
         let reader = BufReader::new(file);
         
         let mut total_lines = 0;
         let mut html_entries = 0;
         let mut redirect_lines = 0;
-        let mut boundary_found = false;
+        let mut boundary_line: Option<usize> = None;
         
         for line_result in reader.lines() {
             let line = line_result?;
@@ -365,23 +501,174 @@ impl DataCleaner {
             }
             
             // 检测分界点附近
-            if !boundary_found && total_lines > 300000 && self.is_likely_kanji_only(&line) {
+            if boundary_line.is_none() && total_lines > search_start && self.is_likely_kanji_only(&line) {
                 println!("🔍 疑似分界点位置: 第{}行 - {}", total_lines, line);
-                boundary_found = true;
+                boundary_line = Some(total_lines);
             }
         }
         
+        self.boundary_line = boundary_line;
+        
         println!("📊 文件结构分析:");
         println!("  - 总行数: {}", total_lines);
         println!("  - HTML词条: {}", html_entries);
         println!("  - 重定向行: {}", redirect_lines);
         println!("  - 预计有效词条: {}", html_entries);
+        match boundary_line {
+            Some(line_number) => println!(
+                "  - 分界点: 第{}行（从第{}行起查找，比例 {}）",
+                line_number, search_start, self.boundary_search_fraction
+            ),
+            None => println!("  - 分界点: 未检测到（从第{}行起查找）", search_start),
+        }
         
         Ok(())
     }
     
+    /// 将重定向映射写出为TSV文件（每行：重定向源\t目标词条），按源排序
+    pub fn write_redirects(&self, output_path: &str) -> Result<usize, Box<dyn std::error::Error>> {
+        let mut output_file = File::create(output_path)?;
+        
+        let mut redirects: Vec<(&String, &String)> = self.redirect_map.iter().collect();
+        redirects.sort();
+        for (source, target) in &redirects {
+            writeln!(output_file, "{}\t{}", source, target)?;
+        }
+        
+        Ok(redirects.len())
+    }
+    
     /// 获取统计信息
     pub fn get_stats(&self) -> (usize, usize, usize) {
         (self.valid_entries, self.redirect_entries, self.redirect_map.len())
     }
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_boundary_line_uses_search_fraction() {
+        let path = std::env::temp_dir().join(format!("boundary_test_{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+        std::fs::write(path, "国語\nあい\n<link rel=\"stylesheet\">\nかい\n漢字\n@@@LINK=あい\n").unwrap();
+
+        let mut cleaner = DataCleaner::new();
+        cleaner.analyze_file_structure(path).unwrap();
+        assert_eq!(cleaner.boundary_line(), Some(5));
+
+        cleaner.boundary_search_fraction = 0.0;
+        cleaner.analyze_file_structure(path).unwrap();
+        assert_eq!(cleaner.boundary_line(), Some(1));
+
+        cleaner.boundary_search_fraction = 0.9;
+        cleaner.analyze_file_structure(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(cleaner.boundary_line(), None);
+    }
+
+    #[test]
+    fn test_dry_run_counts_without_writing() {
+        let dir = std::env::temp_dir().join(format!("dry_run_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("input.txt");
+        std::fs::write(
+            &input,
+            "あい【愛】\n<link rel=\"stylesheet\"><container data-id=\"1\">本文</container>\n\
+             こい【恋】\n<link rel=\"stylesheet\"><container data-id=\"2\">本文</container>\n\
+             恋\n@@@LINK=こい\n",
+        )
+        .unwrap();
+        let input = input.to_str().unwrap();
+        let output = dir.join("cleaned.txt");
+        let output = output.to_str().unwrap();
+
+        // dry-run与实际清理的统计相同，但不写出文件
+        let mut dry = DataCleaner::new();
+        dry.clean_exported_dict(input, output, true).unwrap();
+        assert!(!std::path::Path::new(output).exists());
+
+        let mut cleaner = DataCleaner::new();
+        cleaner.clean_exported_dict(input, output, false).unwrap();
+        assert!(std::path::Path::new(output).exists());
+        assert_eq!((dry.valid_entries, dry.redirect_entries), (2, 1));
+        assert_eq!((cleaner.valid_entries, cleaner.redirect_entries), (2, 1));
+        assert_eq!(dry.redirect_map, cleaner.redirect_map);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_empty_kana_count() {
+        let cleaner = DataCleaner::new();
+        let html = r#"<container data-id="7"><span class="headword_kana">▽◆</span></container>"#;
+        assert_eq!(cleaner.extract_title_from_html(html), "entry_7");
+        assert_eq!(cleaner.empty_kana_count(), 1);
+    }
+
+    #[test]
+    fn test_strip_invisible() {
+        assert_eq!(strip_invisible("あい\u{200B}【愛】"), "あい【愛】");
+        assert_eq!(strip_invisible("\u{FEFF}あい\u{0007}\u{0085}"), "あい");
+        assert_eq!(strip_invisible("❶一\n❷二"), "❶一\n❷二");
+    }
+
+    #[test]
+    fn test_extract_data_id_quote_styles() {
+        let cleaner = DataCleaner::new();
+
+        assert_eq!(cleaner.extract_data_id(r#"<container data-id="123" data-type="1">"#), Some("123".to_string()));
+        assert_eq!(cleaner.extract_data_id("<container data-id='456' data-type='1'>"), Some("456".to_string()));
+        assert_eq!(cleaner.extract_data_id(r#"<container data-id = "789">"#), Some("789".to_string()));
+        assert_eq!(cleaner.extract_data_id("<container data-id  =  '012'>"), Some("012".to_string()));
+        // 其他属性名中包含data-id时不误判
+        assert_eq!(cleaner.extract_data_id(r#"<div data-id-ref="x" data-id="345">"#), Some("345".to_string()));
+        assert_eq!(cleaner.extract_data_id(r#"<div xdata-id="x" data-id="678">"#), Some("678".to_string()));
+        assert_eq!(cleaner.extract_data_id(r#"<div xdata-id="x">"#), None);
+
+        // 没有标题时用data-id作为标识
+        assert_eq!(cleaner.extract_title_from_html("<container data-id = '9'><div>本文</div></container>"), "entry_9");
+        assert_eq!(cleaner.extract_data_id("<container data-type=\"1\">"), None);
+    }
+
+    #[test]
+    fn test_split_by_kana_row() {
+        let dir = std::env::temp_dir().join(format!("split_export_test_{}", std::process::id()));
+        let input = dir.join("input.txt");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            &input,
+            "あい【愛】\n<p>1</p>\n\nがくせい【学生】\n<p>2</p>\n\n\nDNA\n<p>3</p>\n\nアイロン\r\n<p>4</p>\r\n",
+        )
+        .unwrap();
+        let output = dir.join("out");
+        let output = output.to_str().unwrap();
+
+        let counts = split_by_kana_row(input.to_str().unwrap(), output).unwrap();
+        assert_eq!(
+            counts,
+            vec![("あ行.txt".to_string(), 2), ("か行.txt".to_string(), 1), ("misc.txt".to_string(), 1)]
+        );
+        let a_row = std::fs::read_to_string(dir.join("out").join("あ行.txt")).unwrap();
+        assert_eq!(a_row, "あい【愛】\n<p>1</p>\n\nアイロン\n<p>4</p>\n\n");
+        assert!(!dir.join("out").join("さ行.txt").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_clean_options_strip_html() {
+        let html = r#"<link rel="stylesheet" href="oko.css"><!-- note --><container data-id="1" data-type="1"><div class="mean_normal">本文</div></container>"#;
+
+        let default_patterns = CleanOptions::default().strip_patterns();
+        assert_eq!(DataCleaner::strip_html(html, &default_patterns), html);
+
+        let options = CleanOptions {
+            strip_links: true,
+            keep_data_attrs: false,
+            keep_comments: false,
+        };
+        assert_eq!(
+            DataCleaner::strip_html(html, &options.strip_patterns()),
+            r#"<container><div class="mean_normal">本文</div></container>"#
+        );
+    }
+}
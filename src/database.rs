@@ -1,23 +1,8 @@
 use rusqlite::{Connection, Result, params};
-use serde::{Deserialize, Serialize};
-
-/// 表現読解国語辞典 - 日语词典条目结构
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DictionaryEntry {
-    pub id: Option<i64>,
-    /// 假名entry - 词条的假名读音
-    pub kana_entry: String,
-    /// 汉字格式 - 汉字表记（可能为空，如纯假名词汇）
-    pub kanji_form: Option<String>,
-    /// 释义字段 - 词汇的含义解释
-    pub meaning: String,
-    /// 发音字段 - 发音信息（音读、训读等）
-    pub pronunciation: Option<String>,
-    /// 词条类型 - 如汉字条目、一般条目等
-    pub entry_type: String,
-    /// 原始HTML内容 - 保留原始数据用于调试
-    pub raw_html: String,
-}
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+pub use crate::parser::{DictionaryEntry, HyoCategory, JouyouFlag, KanjiWriting, Reading, ReadingKind};
 
 /// 数据库管理结构
 pub struct Database {
@@ -43,12 +28,20 @@ impl Database {
                 pronunciation TEXT,                         -- 发音字段
                 entry_type TEXT NOT NULL,                   -- 词条类型
                 raw_html TEXT NOT NULL,                     -- 原始HTML
+                readings TEXT,                              -- 带类型的读音(JSON)
+                jouyou_flags TEXT,                          -- 表外字标记(JSON)
+                kanji_writings TEXT,                        -- 带类别的汉字表记(JSON)
                 created_at DATETIME DEFAULT CURRENT_TIMESTAMP
             )
             "#,
             [],
         )?;
 
+        // 旧数据库补充新增的列
+        self.ensure_column("readings", "TEXT")?;
+        self.ensure_column("jouyou_flags", "TEXT")?;
+        self.ensure_column("kanji_writings", "TEXT")?;
+
         // 创建索引以提高查询性能
         self.conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_kana_entry ON dictionary_entries(kana_entry)",
@@ -63,13 +56,27 @@ impl Database {
         Ok(())
     }
 
+    /// 为旧数据库补充缺失的列
+    fn ensure_column(&self, column: &str, definition: &str) -> Result<()> {
+        let exists = self.conn
+            .prepare("SELECT 1 FROM pragma_table_info('dictionary_entries') WHERE name = ?1")?
+            .exists([column])?;
+        if !exists {
+            self.conn.execute(
+                &format!("ALTER TABLE dictionary_entries ADD COLUMN {} {}", column, definition),
+                [],
+            )?;
+        }
+        Ok(())
+    }
+
     /// 插入词典条目
     pub fn insert_entry(&self, entry: &DictionaryEntry) -> Result<i64> {
         let mut stmt = self.conn.prepare(
             r#"
             INSERT INTO dictionary_entries 
-            (kana_entry, kanji_form, meaning, pronunciation, entry_type, raw_html)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            (kana_entry, kanji_form, meaning, pronunciation, entry_type, raw_html, readings, jouyou_flags, kanji_writings)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
             "#,
         )?;
 
@@ -80,58 +87,80 @@ impl Database {
             entry.pronunciation,
             entry.entry_type,
             entry.raw_html,
+            list_to_json(&entry.readings),
+            list_to_json(&entry.jouyou_flags),
+            list_to_json(&entry.kanji_writings),
         ])?;
 
         Ok(self.conn.last_insert_rowid())
     }
 
     /// 批量插入词典条目
+    /// 全部成功或全部失败：任何一条插入失败都会显式回滚整个批次，不会提交部分数据
     pub fn insert_entries_batch(&self, entries: &[DictionaryEntry]) -> Result<()> {
         let tx = self.conn.unchecked_transaction()?;
         
-        {
-            let mut stmt = tx.prepare(
-                r#"
-                INSERT INTO dictionary_entries 
-                (kana_entry, kanji_form, meaning, pronunciation, entry_type, raw_html)
-                VALUES (?1, ?2, ?3, ?4, ?5, ?6)
-                "#,
-            )?;
-
-            for entry in entries {
-                stmt.execute(params![
-                    entry.kana_entry,
-                    entry.kanji_form,
-                    entry.meaning,
-                    entry.pronunciation,
-                    entry.entry_type,
-                    entry.raw_html,
-                ])?;
+        match Self::insert_rows(&tx, entries) {
+            Ok(()) => tx.commit(),
+            Err(e) => {
+                if let Err(rollback_err) = tx.rollback() {
+                    eprintln!("❌ 批量插入回滚失败: {}", rollback_err);
+                }
+                Err(e)
             }
-        } // stmt在这里被丢弃
+        }
+    }
+
+    /// 在给定连接（事务）上逐条插入词条
+    fn insert_rows(conn: &Connection, entries: &[DictionaryEntry]) -> Result<()> {
+        let mut stmt = conn.prepare(
+            r#"
+            INSERT INTO dictionary_entries 
+            (kana_entry, kanji_form, meaning, pronunciation, entry_type, raw_html, readings, jouyou_flags, kanji_writings)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            "#,
+        )?;
 
-        tx.commit()?;
+        for entry in entries {
+            stmt.execute(params![
+                entry.kana_entry,
+                entry.kanji_form,
+                entry.meaning,
+                entry.pronunciation,
+                entry.entry_type,
+                entry.raw_html,
+                list_to_json(&entry.readings),
+                list_to_json(&entry.jouyou_flags),
+                list_to_json(&entry.kanji_writings),
+            ])?;
+        }
         Ok(())
     }
 
     /// 根据假名查询词条
     pub fn find_by_kana(&self, kana: &str) -> Result<Vec<DictionaryEntry>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, kana_entry, kanji_form, meaning, pronunciation, entry_type, raw_html 
+            "SELECT id, kana_entry, kanji_form, meaning, pronunciation, entry_type, raw_html, readings, jouyou_flags, kanji_writings 
              FROM dictionary_entries WHERE kana_entry = ?1"
         )?;
 
-        let entry_iter = stmt.query_map([kana], |row| {
-            Ok(DictionaryEntry {
-                id: Some(row.get(0)?),
-                kana_entry: row.get(1)?,
-                kanji_form: row.get(2)?,
-                meaning: row.get(3)?,
-                pronunciation: row.get(4)?,
-                entry_type: row.get(5)?,
-                raw_html: row.get(6)?,
-            })
-        })?;
+        let entry_iter = stmt.query_map([kana], Self::entry_from_row)?;
+
+        let mut entries = Vec::new();
+        for entry in entry_iter {
+            entries.push(entry?);
+        }
+        Ok(entries)
+    }
+
+    /// 按id顺序读取全部词条
+    pub fn all_entries(&self) -> Result<Vec<DictionaryEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, kana_entry, kanji_form, meaning, pronunciation, entry_type, raw_html, readings, jouyou_flags, kanji_writings 
+             FROM dictionary_entries ORDER BY id"
+        )?;
+
+        let entry_iter = stmt.query_map([], Self::entry_from_row)?;
 
         let mut entries = Vec::new();
         for entry in entry_iter {
@@ -140,6 +169,22 @@ impl Database {
         Ok(entries)
     }
 
+    /// 将查询结果行映射为词条（列顺序见find_by_kana）
+    fn entry_from_row(row: &rusqlite::Row) -> Result<DictionaryEntry> {
+        Ok(DictionaryEntry {
+            id: Some(row.get(0)?),
+            kana_entry: row.get(1)?,
+            kanji_form: row.get(2)?,
+            meaning: row.get(3)?,
+            pronunciation: row.get(4)?,
+            entry_type: row.get(5)?,
+            raw_html: row.get(6)?,
+            readings: list_from_json(row.get(7)?),
+            jouyou_flags: list_from_json(row.get(8)?),
+            kanji_writings: list_from_json(row.get(9)?),
+        })
+    }
+
     /// 获取词条总数
     pub fn get_entry_count(&self) -> Result<i32> {
         let mut stmt = self.conn.prepare("SELECT COUNT(*) FROM dictionary_entries")?;
@@ -156,6 +201,16 @@ impl Database {
     }
 }
 
+/// 读音、表外字标记等列表序列化为JSON
+fn list_to_json<T: Serialize>(items: &[T]) -> String {
+    serde_json::to_string(items).unwrap_or_else(|_| "[]".to_string())
+}
+
+/// 从JSON解析列表（旧数据为NULL时返回空列表）
+fn list_from_json<T: DeserializeOwned>(json: Option<String>) -> Vec<T> {
+    json.and_then(|json| serde_json::from_str(&json).ok()).unwrap_or_default()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -169,25 +224,70 @@ mod tests {
         assert_eq!(count, 0);
     }
 
-    #[test]
-    fn test_entry_insertion() {
-        let db = Database::new(":memory:").unwrap();
-        db.initialize().unwrap();
-
-        let entry = DictionaryEntry {
+    fn sample_entry() -> DictionaryEntry {
+        DictionaryEntry {
             id: None,
             kana_entry: "あい".to_string(),
             kanji_form: Some("愛".to_string()),
             meaning: "かわいがりいつくしむ気持ち".to_string(),
             pronunciation: Some("アイ".to_string()),
+            readings: vec![Reading { text: "アイ".to_string(), kind: ReadingKind::On }],
             entry_type: "item_kiso".to_string(),
             raw_html: "<div>test</div>".to_string(),
-        };
+            jouyou_flags: vec![JouyouFlag { text: "愛".to_string(), marker: "◇".to_string() }],
+            kanji_writings: vec![KanjiWriting { form: "愛".to_string(), category: HyoCategory::Hyo1 }],
+        }
+    }
+
+    #[test]
+    fn test_entry_insertion() {
+        let db = Database::new(":memory:").unwrap();
+        db.initialize().unwrap();
 
-        let id = db.insert_entry(&entry).unwrap();
+        let id = db.insert_entry(&sample_entry()).unwrap();
         assert!(id > 0);
 
         let count = db.get_entry_count().unwrap();
         assert_eq!(count, 1);
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_entry_json_columns_round_trip() {
+        let db = Database::new(":memory:").unwrap();
+        db.initialize().unwrap();
+
+        let entry = sample_entry();
+        db.insert_entry(&entry).unwrap();
+
+        let found = db.find_by_kana("あい").unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].readings, entry.readings);
+        assert_eq!(found[0].jouyou_flags, entry.jouyou_flags);
+        assert_eq!(found[0].kanji_writings, entry.kanji_writings);
+    }
+
+    #[test]
+    fn test_batch_insert_rolls_back_on_failure() {
+        let db = Database::new(":memory:").unwrap();
+        db.initialize().unwrap();
+        // 人为制造约束：kana_entry唯一，使批次中的重复词条插入失败
+        db.conn.execute("CREATE UNIQUE INDEX idx_unique_kana ON dictionary_entries(kana_entry)", []).unwrap();
+
+        let entry = |kana: &str| DictionaryEntry {
+            id: None,
+            kana_entry: kana.to_string(),
+            kanji_form: None,
+            meaning: "テスト".to_string(),
+            pronunciation: None,
+            readings: Vec::new(),
+            entry_type: "item_ippan".to_string(),
+            raw_html: "<div>test</div>".to_string(),
+            jouyou_flags: Vec::new(),
+            kanji_writings: Vec::new(),
+        };
+
+        let batch = vec![entry("あい"), entry("あお"), entry("あい")];
+        assert!(db.insert_entries_batch(&batch).is_err());
+        assert_eq!(db.get_entry_count().unwrap(), 0);
+    }
+}
@@ -0,0 +1,147 @@
+use std::fs::File;
+use std::io::{Read, Write};
+use std::time::Duration;
+
+use sha2::{Digest, Sha256};
+
+/// 下载时的读写缓冲区大小
+const DOWNLOAD_BUFFER_SIZE: usize = 64 * 1024;
+
+/// 默认的下载超时（整个请求，包括读取响应内容）
+pub const DEFAULT_DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// 出站HTTP请求的User-Agent
+const USER_AGENT: &str = concat!("jp-dict/", env!("CARGO_PKG_VERSION"));
+
+/// 从HTTP(S)地址下载文件到本地路径（如S3上的obunsha_dict.db），返回写入的字节数
+///
+/// 先写入同目录下的 `<output_path>.part`，服务端返回Content-Length时校验下载长度，
+/// 提供 `expected_sha256` 时校验SHA-256，全部通过后才重命名为 `output_path`；
+/// 失败时删除临时文件，不会在目标路径留下不完整的数据库。
+/// 代理沿用reqwest的默认行为，读取 `HTTP_PROXY`、`HTTPS_PROXY`、`NO_PROXY` 环境变量。
+pub fn download_file(
+    url: &str,
+    output_path: &str,
+    expected_sha256: Option<&str>,
+    timeout: Duration,
+) -> Result<u64, Box<dyn std::error::Error>> {
+    println!("⬇️  开始下载: {} -> {}（超时 {} 秒）", url, output_path, timeout.as_secs());
+
+    let client = reqwest::blocking::Client::builder()
+        .user_agent(USER_AGENT)
+        .timeout(timeout)
+        .build()?;
+    let mut response = client.get(url).send()?;
+    if response.status() != reqwest::StatusCode::OK {
+        return Err(format!("下载失败: 服务器返回 {}", response.status()).into());
+    }
+    let expected_len = response.content_length();
+
+    let written = save_download(&mut response, output_path, expected_len, expected_sha256)?;
+    println!("✅ 下载完成: {} 字节", written);
+    Ok(written)
+}
+
+/// 将下载内容写入 `<output_path>.part`，校验通过后重命名为 `output_path`，返回写入的字节数；
+/// 写入或校验失败时删除临时文件
+fn save_download(
+    reader: &mut impl Read,
+    output_path: &str,
+    expected_len: Option<u64>,
+    expected_sha256: Option<&str>,
+) -> Result<u64, Box<dyn std::error::Error>> {
+    let temp_path = format!("{}.part", output_path);
+    let result = write_response(reader, &temp_path).and_then(|(written, digest)| {
+        verify_download(written, expected_len, &digest, expected_sha256)?;
+        std::fs::rename(&temp_path, output_path)?;
+        Ok(written)
+    });
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// 将响应内容写入文件并同步到磁盘，同时计算SHA-256；返回(字节数, 十六进制摘要)
+fn write_response(reader: &mut impl Read, output_path: &str) -> Result<(u64, String), Box<dyn std::error::Error>> {
+    let mut file = File::create(output_path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; DOWNLOAD_BUFFER_SIZE];
+    let mut written = 0u64;
+
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        file.write_all(&buffer[..read])?;
+        hasher.update(&buffer[..read]);
+        written += read as u64;
+    }
+    file.flush()?;
+    file.sync_all()?;
+
+    Ok((written, format!("{:x}", hasher.finalize())))
+}
+
+/// 校验下载长度和SHA-256摘要（摘要比较不区分大小写）
+fn verify_download(
+    written: u64,
+    expected_len: Option<u64>,
+    digest: &str,
+    expected_sha256: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(expected_len) = expected_len
+        && written != expected_len
+    {
+        return Err(format!("下载不完整: 收到 {} 字节，Content-Length 为 {}", written, expected_len).into());
+    }
+
+    if let Some(expected) = expected_sha256
+        && !digest.eq_ignore_ascii_case(expected.trim())
+    {
+        return Err(format!("SHA-256校验失败: 期望 {}，实际 {}", expected.trim(), digest).into());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_and_verify_download() {
+        let path = std::env::temp_dir().join(format!("download_test_{}.bin", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        let (written, digest) = write_response(&mut &b"abc"[..], path).unwrap();
+        assert_eq!(written, 3);
+        assert_eq!(digest, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        std::fs::remove_file(path).unwrap();
+
+        assert!(verify_download(written, Some(3), &digest, Some(&digest.to_uppercase())).is_ok());
+        assert!(verify_download(written, Some(4), &digest, None).is_err());
+        assert!(verify_download(written, None, &digest, Some("00")).is_err());
+    }
+
+    #[test]
+    fn test_failed_download_leaves_no_files() {
+        let path = std::env::temp_dir().join(format!("download_fail_test_{}.db", std::process::id()));
+        let path = path.to_str().unwrap();
+        let temp_path = format!("{}.part", path);
+
+        // 长度或摘要不符时临时文件被删除，目标路径也没有文件
+        assert!(save_download(&mut &b"abc"[..], path, Some(4), None).is_err());
+        assert!(save_download(&mut &b"abc"[..], path, None, Some("00")).is_err());
+        assert!(!std::path::Path::new(&temp_path).exists());
+        assert!(!std::path::Path::new(path).exists());
+
+        // 校验通过时重命名为目标路径
+        assert_eq!(save_download(&mut &b"abc"[..], path, Some(3), None).unwrap(), 3);
+        assert!(!std::path::Path::new(&temp_path).exists());
+        assert_eq!(std::fs::read(path).unwrap(), b"abc");
+        std::fs::remove_file(path).unwrap();
+    }
+}
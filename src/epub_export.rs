@@ -0,0 +1,369 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Write;
+
+use scraper::{ElementRef, Html, Node};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::kana;
+use crate::obunsha_dict::{ObunshaDictDatabase, ObunshaDictEntry};
+
+/// 词条正文中保留的标签，其他标签改为span（container改为div）
+const ALLOWED_TAGS: [&str; 18] = [
+    "div", "span", "p", "b", "i", "em", "strong", "u", "small", "sup", "sub",
+    "ruby", "rt", "rp", "br", "ul", "ol", "li",
+];
+
+/// 不输出的标签（连同内容）
+const DROPPED_TAGS: [&str; 4] = ["link", "script", "style", "img"];
+
+/// 写章节时每批从数据库读取的词条数
+const CHAPTER_FETCH_CHUNK: usize = 500;
+
+/// 一个章节（五十音的一行）中的词条，只记录写章节和检索键所需的少量信息
+#[derive(Default)]
+struct Chapter {
+    /// 按读音排序的词条data_id
+    data_ids: Vec<String>,
+    /// 检索键：(词条锚点, 检索词列表)
+    search_keys: Vec<(String, Vec<String>)>,
+}
+
+/// 将数据库导出为EPUB3词典（EPUB Dictionaries and Glossaries）
+/// 词条按五十音行分章，假名读音和汉字表记作为检索键；返回导出的词条数
+/// 先扫描一遍确定各词条所在的章节，再逐章分批读取词条直接写入ZIP，不把全部正文留在内存中
+pub fn export_epub(db: &ObunshaDictDatabase, output_path: &str) -> Result<usize, Box<dyn std::error::Error>> {
+    println!("📚 开始导出EPUB词典: {}", output_path);
+
+    let title = db
+        .get_metadata()?
+        .get("title")
+        .cloned()
+        .unwrap_or_else(|| "旺文社国語辞典".to_string());
+    let modified = db.last_modified()?;
+
+    let mut chapters: BTreeMap<usize, Chapter> = BTreeMap::new();
+    let mut count = 0;
+    db.for_each_entry(|entry| {
+        let row = entry.kana_reading.as_deref().and_then(kana::gojuon_row).unwrap_or(kana::GOJUON_ROW_NAMES.len());
+        let chapter = chapters.entry(row).or_default();
+        chapter.search_keys.push((anchor(&entry), search_keys(&entry)));
+        chapter.data_ids.push(entry.data_id);
+        count += 1;
+    })?;
+
+    let mut zip = ZipWriter::new(File::create(output_path)?);
+    let stored = FileOptions::default().compression_method(CompressionMethod::Stored);
+    let deflated = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    // mimetype必须是第一个且不压缩
+    zip.start_file("mimetype", stored)?;
+    zip.write_all(b"application/epub+zip")?;
+
+    zip.start_file("META-INF/container.xml", deflated)?;
+    zip.write_all(CONTAINER_XML.as_bytes())?;
+
+    let mut chapter_files = Vec::new();
+    for (row, chapter) in &chapters {
+        let file_name = format!("row_{:02}.xhtml", row);
+        let row_name = kana::GOJUON_ROW_NAMES.get(*row).copied().unwrap_or("その他");
+
+        zip.start_file(format!("OEBPS/{}", file_name), deflated)?;
+        zip.write_all(chapter_header(row_name).as_bytes())?;
+        for data_ids in chapter.data_ids.chunks(CHAPTER_FETCH_CHUNK) {
+            for entry in db.get_by_data_ids(data_ids)? {
+                zip.write_all(render_article(&anchor(&entry), &entry).as_bytes())?;
+                zip.write_all(b"\n")?;
+            }
+        }
+        zip.write_all(CHAPTER_FOOTER.as_bytes())?;
+        chapter_files.push((file_name, row_name));
+        println!("  - {}: {} 条词条", row_name, chapter.data_ids.len());
+    }
+
+    zip.start_file("OEBPS/search-key-map.xml", deflated)?;
+    zip.write_all(search_key_map(&chapters).as_bytes())?;
+
+    zip.start_file("OEBPS/nav.xhtml", deflated)?;
+    zip.write_all(nav_xhtml(&title, &chapter_files).as_bytes())?;
+
+    zip.start_file("OEBPS/content.opf", deflated)?;
+    zip.write_all(content_opf(&title, &modified, &chapter_files).as_bytes())?;
+
+    zip.finish()?;
+
+    println!("✅ 已导出 {} 条词条，{} 个章节", count, chapters.len());
+    Ok(count)
+}
+
+const CONTAINER_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>
+"#;
+
+/// 词条的检索键：假名读音和各个汉字表记（多重表记按·拆分），去重
+fn search_keys(entry: &ObunshaDictEntry) -> Vec<String> {
+    let mut keys: Vec<String> = Vec::new();
+    let candidates = entry
+        .kana_reading
+        .iter()
+        .map(|kana| kana.to_string())
+        .chain(
+            entry
+                .kanji_writing
+                .iter()
+                .flat_map(|kanji| kanji.split(['·', '・']).map(|part| part.to_string())),
+        );
+    for key in candidates {
+        let key = key.trim().to_string();
+        if !key.is_empty() && !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+    if keys.is_empty() {
+        keys.push(entry.headword.clone());
+    }
+    keys
+}
+
+/// 词条在章节中的锚点
+fn anchor(entry: &ObunshaDictEntry) -> String {
+    format!("e{}", entry.id.unwrap_or_default())
+}
+
+/// 渲染单个词条
+fn render_article(anchor: &str, entry: &ObunshaDictEntry) -> String {
+    format!(
+        "<article id=\"{}\" epub:type=\"dictentry\"><dfn>{}</dfn>{}</article>",
+        anchor,
+        escape_xml(&entry.headword),
+        sanitize_definition_html(&entry.definition_html)
+    )
+}
+
+/// 将MDX中的HTML整理为合法的XHTML
+/// 去除样式表、脚本和图片，只保留class属性，未知标签改为span，container改为div
+pub fn sanitize_definition_html(html: &str) -> String {
+    let document = Html::parse_fragment(html);
+    let mut xhtml = String::new();
+    write_xhtml_children(document.root_element(), &mut xhtml);
+    xhtml
+}
+
+fn write_xhtml_children(element: ElementRef, xhtml: &mut String) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => xhtml.push_str(&escape_xml(text)),
+            Node::Element(_) => {
+                if let Some(child_element) = ElementRef::wrap(child) {
+                    write_xhtml_element(child_element, xhtml);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn write_xhtml_element(element: ElementRef, xhtml: &mut String) {
+    let name = element.value().name();
+    if DROPPED_TAGS.contains(&name) {
+        return;
+    }
+    if name == "html" || name == "body" {
+        write_xhtml_children(element, xhtml);
+        return;
+    }
+
+    let tag = match name {
+        "container" => "div",
+        _ if ALLOWED_TAGS.contains(&name) => name,
+        _ => "span",
+    };
+    let class = element.value().attr("class").map(escape_xml);
+
+    xhtml.push('<');
+    xhtml.push_str(tag);
+    if let Some(class) = class {
+        xhtml.push_str(&format!(" class=\"{}\"", class));
+    }
+    if tag == "br" {
+        xhtml.push_str("/>");
+        return;
+    }
+    xhtml.push('>');
+    write_xhtml_children(element, xhtml);
+    xhtml.push_str(&format!("</{}>", tag));
+}
+
+/// 转义XML特殊字符
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// 章节XHTML中词条之前的部分
+fn chapter_header(row_name: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops" xml:lang="ja" lang="ja">
+<head><meta charset="UTF-8"/><title>{}</title></head>
+<body>
+<section epub:type="dictionary">
+<h1>{}</h1>
+"#,
+        row_name, row_name
+    )
+}
+
+/// 章节XHTML中词条之后的部分
+const CHAPTER_FOOTER: &str = "</section>\n</body>\n</html>\n";
+
+fn search_key_map(chapters: &BTreeMap<usize, Chapter>) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<search-key-map xmlns=\"http://www.idpf.org/2007/ops\" xml:lang=\"ja\">\n",
+    );
+    for (row, chapter) in chapters {
+        for (anchor, keys) in &chapter.search_keys {
+            xml.push_str(&format!("  <search-key-group href=\"row_{:02}.xhtml#{}\">\n", row, anchor));
+            for key in keys {
+                xml.push_str(&format!("    <match value=\"{}\"/>\n", escape_xml(key)));
+            }
+            xml.push_str("  </search-key-group>\n");
+        }
+    }
+    xml.push_str("</search-key-map>\n");
+    xml
+}
+
+fn nav_xhtml(title: &str, chapter_files: &[(String, &str)]) -> String {
+    let items: Vec<String> = chapter_files
+        .iter()
+        .map(|(file_name, row_name)| format!("<li><a href=\"{}\">{}</a></li>", file_name, row_name))
+        .collect();
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops" xml:lang="ja" lang="ja">
+<head><meta charset="UTF-8"/><title>{}</title></head>
+<body>
+<nav epub:type="toc"><h1>{}</h1><ol>{}</ol></nav>
+</body>
+</html>
+"#,
+        escape_xml(title),
+        escape_xml(title),
+        items.join("")
+    )
+}
+
+/// modified为ISO-8601（UTC）时间，写入dcterms:modified
+fn content_opf(title: &str, modified: &str, chapter_files: &[(String, &str)]) -> String {
+    let manifest: Vec<String> = chapter_files
+        .iter()
+        .enumerate()
+        .map(|(i, (file_name, _))| {
+            format!("    <item id=\"chapter{}\" href=\"{}\" media-type=\"application/xhtml+xml\"/>", i, file_name)
+        })
+        .collect();
+    let spine: Vec<String> = (0..chapter_files.len())
+        .map(|i| format!("    <itemref idref=\"chapter{}\"/>", i))
+        .collect();
+    let links: Vec<String> = chapter_files
+        .iter()
+        .map(|(file_name, _)| format!("    <link href=\"{}\"/>", file_name))
+        .collect();
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="uid" xml:lang="ja">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:identifier id="uid">urn:jp-dict:obunsha-kokugo</dc:identifier>
+    <dc:title>{title}</dc:title>
+    <dc:language>ja</dc:language>
+    <dc:type>dictionary</dc:type>
+    <meta property="dcterms:modified">{modified}</meta>
+    <meta property="source-language">ja</meta>
+    <meta property="target-language">ja</meta>
+  </metadata>
+  <manifest>
+    <item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>
+    <item id="DictionaryData" href="search-key-map.xml" media-type="application/vnd.epub.search-key-map+xml" properties="search-key-map dictionary"/>
+{manifest}
+  </manifest>
+  <spine>
+{spine}
+  </spine>
+  <collection role="dictionary">
+    <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+      <dc:title>{title}</dc:title>
+      <dc:type>dictionary</dc:type>
+      <meta property="source-language">ja</meta>
+      <meta property="target-language">ja</meta>
+    </metadata>
+    <link href="search-key-map.xml"/>
+{links}
+  </collection>
+</package>
+"#,
+        title = escape_xml(title),
+        modified = escape_xml(modified),
+        manifest = manifest.join("\n"),
+        spine = spine.join("\n"),
+        links = links.join("\n"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_definition_html() {
+        let html = r#"<link rel="stylesheet" href="oko.css"><container data-id="1"><div class="mean_normal" onclick="x()">A &amp; B<br><custom>愛</custom></div></container>"#;
+        assert_eq!(
+            sanitize_definition_html(html),
+            r#"<div><div class="mean_normal">A &amp; B<br/><span>愛</span></div></div>"#
+        );
+    }
+
+    #[test]
+    fn test_export_epub() {
+        let db = ObunshaDictDatabase::new(":memory:").unwrap();
+        db.initialize().unwrap();
+        let seeded = db.seed_demo().unwrap();
+        let modified = db.last_modified().unwrap();
+        assert_eq!(modified.len(), "2000-01-01T00:00:00Z".len());
+        assert!(modified.ends_with('Z'));
+
+        let path = std::env::temp_dir().join(format!("jp_dict_epub_test_{}.epub", std::process::id()));
+        let path = path.to_string_lossy().into_owned();
+        let count = export_epub(&db, &path).unwrap();
+        assert_eq!(count, seeded);
+
+        let mut archive = zip::ZipArchive::new(File::open(&path).unwrap()).unwrap();
+        let read = |archive: &mut zip::ZipArchive<File>, name: &str| {
+            let mut content = String::new();
+            std::io::Read::read_to_string(&mut archive.by_name(name).unwrap(), &mut content).unwrap();
+            content
+        };
+        let opf = read(&mut archive, "OEBPS/content.opf");
+        assert!(opf.contains(&format!("<meta property=\"dcterms:modified\">{}</meta>", modified)));
+
+        let mut articles = 0;
+        for name in archive.file_names().map(str::to_string).collect::<Vec<_>>() {
+            if name.starts_with("OEBPS/row_") {
+                let chapter = read(&mut archive, &name);
+                assert!(chapter.trim_end().ends_with("</html>"));
+                articles += chapter.matches("<article ").count();
+            }
+        }
+        assert_eq!(articles, count);
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
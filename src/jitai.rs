@@ -0,0 +1,105 @@
+//! 新字体与旧字体（如 国/國、学/學）的对应，用于 search_type=variant 搜索
+
+/// 新字体与旧字体的对应表（常用汉字中字形变化较大的字）：(新字体, 旧字体)
+/// 新字体本身另有独立用法的字（如 芸/藝、欠/缺、缶/罐、弁/辨・瓣・辯、予/豫、余/餘、台/臺、証/證、灯/燈、虫/蟲、岳/嶽）不收录，避免误匹配
+const SHINJITAI_KYUJITAI: [(char, char); 250] = [
+    ('亜', '亞'), ('悪', '惡'), ('圧', '壓'), ('囲', '圍'), ('医', '醫'), ('為', '爲'), ('壱', '壹'), ('隠', '隱'), ('栄', '榮'), ('営', '營'),
+    ('衛', '衞'), ('駅', '驛'), ('円', '圓'), ('塩', '鹽'), ('縁', '緣'), ('応', '應'), ('欧', '歐'), ('殴', '毆'), ('桜', '櫻'), ('奥', '奧'),
+    ('横', '橫'), ('温', '溫'), ('穏', '穩'), ('仮', '假'), ('価', '價'), ('画', '畫'), ('会', '會'), ('壊', '壞'), ('懐', '懷'), ('絵', '繪'),
+    ('拡', '擴'), ('覚', '覺'), ('学', '學'), ('楽', '樂'), ('勧', '勸'), ('巻', '卷'), ('歓', '歡'), ('観', '觀'), ('関', '關'),
+    ('陥', '陷'), ('気', '氣'), ('帰', '歸'), ('亀', '龜'), ('偽', '僞'), ('戯', '戲'), ('犠', '犧'), ('旧', '舊'), ('拠', '據'), ('挙', '擧'),
+    ('峡', '峽'), ('挟', '挾'), ('狭', '狹'), ('暁', '曉'), ('区', '區'), ('駆', '驅'), ('勲', '勳'), ('径', '徑'), ('茎', '莖'), ('恵', '惠'),
+    ('渓', '溪'), ('経', '經'), ('継', '繼'), ('蛍', '螢'), ('軽', '輕'), ('鶏', '鷄'), ('倹', '儉'), ('剣', '劍'), ('険', '險'), ('圏', '圈'),
+    ('検', '檢'), ('献', '獻'), ('権', '權'), ('顕', '顯'), ('験', '驗'), ('厳', '嚴'), ('広', '廣'), ('効', '效'), ('恒', '恆'), ('鉱', '鑛'),
+    ('号', '號'), ('国', '國'), ('済', '濟'), ('砕', '碎'), ('斎', '齋'), ('剤', '劑'), ('雑', '雜'), ('参', '參'), ('桟', '棧'), ('蚕', '蠶'),
+    ('惨', '慘'), ('賛', '贊'), ('残', '殘'), ('歯', '齒'), ('児', '兒'), ('辞', '辭'), ('湿', '濕'), ('実', '實'), ('写', '寫'), ('釈', '釋'),
+    ('寿', '壽'), ('収', '收'), ('従', '從'), ('渋', '澁'), ('獣', '獸'), ('縦', '縱'), ('粛', '肅'), ('処', '處'), ('叙', '敍'), ('奨', '奬'),
+    ('将', '將'), ('焼', '燒'), ('称', '稱'), ('乗', '乘'), ('浄', '淨'), ('剰', '剩'), ('条', '條'), ('状', '狀'), ('畳', '疊'),
+    ('譲', '讓'), ('醸', '釀'), ('触', '觸'), ('嘱', '囑'), ('真', '眞'), ('寝', '寢'), ('慎', '愼'), ('尽', '盡'), ('図', '圖'), ('粋', '粹'),
+    ('酔', '醉'), ('随', '隨'), ('髄', '髓'), ('数', '數'), ('枢', '樞'), ('声', '聲'), ('静', '靜'), ('斉', '齊'), ('摂', '攝'), ('窃', '竊'),
+    ('専', '專'), ('浅', '淺'), ('戦', '戰'), ('践', '踐'), ('銭', '錢'), ('潜', '潛'), ('繊', '纖'), ('禅', '禪'), ('双', '雙'), ('壮', '壯'),
+    ('争', '爭'), ('荘', '莊'), ('捜', '搜'), ('挿', '插'), ('巣', '巢'), ('装', '裝'), ('総', '總'), ('騒', '騷'), ('増', '增'), ('蔵', '藏'),
+    ('臓', '臟'), ('即', '卽'), ('属', '屬'), ('続', '續'), ('堕', '墮'), ('対', '對'), ('体', '體'), ('帯', '帶'), ('滞', '滯'),
+    ('滝', '瀧'), ('択', '擇'), ('沢', '澤'), ('担', '擔'), ('単', '單'), ('胆', '膽'), ('団', '團'), ('断', '斷'), ('弾', '彈'), ('遅', '遲'),
+    ('昼', '晝'), ('鋳', '鑄'), ('庁', '廳'), ('聴', '聽'), ('鎮', '鎭'), ('逓', '遞'), ('鉄', '鐵'), ('点', '點'), ('転', '轉'),
+    ('伝', '傳'), ('当', '當'), ('党', '黨'), ('盗', '盜'), ('稲', '稻'), ('闘', '鬪'), ('徳', '德'), ('独', '獨'), ('読', '讀'),
+    ('届', '屆'), ('弐', '貳'), ('悩', '惱'), ('脳', '腦'), ('覇', '霸'), ('廃', '廢'), ('拝', '拜'), ('売', '賣'), ('麦', '麥'), ('発', '發'),
+    ('髪', '髮'), ('抜', '拔'), ('蛮', '蠻'), ('秘', '祕'), ('浜', '濱'), ('払', '拂'), ('仏', '佛'), ('並', '竝'), ('変', '變'), ('辺', '邊'),
+    ('舗', '舖'), ('宝', '寶'), ('豊', '豐'), ('没', '沒'), ('翻', '飜'), ('毎', '每'), ('万', '萬'), ('満', '滿'), ('黙', '默'), ('訳', '譯'),
+    ('薬', '藥'), ('与', '與'), ('誉', '譽'), ('揺', '搖'), ('様', '樣'), ('謡', '謠'), ('来', '來'), ('乱', '亂'),
+    ('覧', '覽'), ('竜', '龍'), ('両', '兩'), ('猟', '獵'), ('緑', '綠'), ('涙', '淚'), ('塁', '壘'), ('励', '勵'), ('礼', '禮'), ('霊', '靈'),
+    ('齢', '齡'), ('恋', '戀'), ('炉', '爐'), ('労', '勞'), ('楼', '樓'), ('録', '錄'), ('湾', '灣'),
+];
+
+/// 一个查询最多展开的写法数，防止长查询中每个字都有异体时组合数爆炸
+const MAX_VARIANT_FORMS: usize = 32;
+
+/// 汉字的另一种字体：新字体返回旧字体，旧字体返回新字体，不在对应表中时返回None
+pub fn counterpart(ch: char) -> Option<char> {
+    SHINJITAI_KYUJITAI.iter().find_map(|&(shinjitai, kyujitai)| {
+        if ch == shinjitai {
+            Some(kyujitai)
+        } else if ch == kyujitai {
+            Some(shinjitai)
+        } else {
+            None
+        }
+    })
+}
+
+/// 将文本中的每个字分别替换为新字体或旧字体，返回所有写法（第一个为原文，最多MAX_VARIANT_FORMS个）
+/// 如 "国学" → ["国学", "国學", "國学", "國學"]
+pub fn expand_variants(text: &str) -> Vec<String> {
+    let mut forms = vec![String::new()];
+    for ch in text.chars() {
+        let alternatives: Vec<char> = std::iter::once(ch).chain(counterpart(ch)).collect();
+        forms = forms
+            .iter()
+            .flat_map(|form| {
+                alternatives.iter().map(move |&alternative| {
+                    let mut form = form.clone();
+                    form.push(alternative);
+                    form
+                })
+            })
+            .take(MAX_VARIANT_FORMS)
+            .collect();
+    }
+    forms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counterpart() {
+        assert_eq!(counterpart('国'), Some('國'));
+        assert_eq!(counterpart('國'), Some('国'));
+        assert_eq!(counterpart('学'), Some('學'));
+        assert_eq!(counterpart('學'), Some('学'));
+        assert_eq!(counterpart('愛'), None);
+        // 新字体另有独立用法的字不收录
+        for ch in ['予', '余', '台', '証', '灯', '虫', '岳'] {
+            assert_eq!(counterpart(ch), None, "{}", ch);
+        }
+    }
+
+    #[test]
+    fn test_table_is_one_to_one() {
+        let mut seen = std::collections::HashSet::new();
+        for (shinjitai, kyujitai) in SHINJITAI_KYUJITAI {
+            assert_ne!(shinjitai, kyujitai);
+            assert!(seen.insert(shinjitai), "{}", shinjitai);
+            assert!(seen.insert(kyujitai), "{}", kyujitai);
+        }
+    }
+
+    #[test]
+    fn test_expand_variants() {
+        assert_eq!(expand_variants("国学"), vec!["国学", "国學", "國学", "國學"]);
+        assert_eq!(expand_variants("學校"), vec!["學校", "学校"]);
+        assert_eq!(expand_variants("愛"), vec!["愛"]);
+        assert_eq!(expand_variants("国学会図駅営").len(), MAX_VARIANT_FORMS);
+    }
+}
@@ -0,0 +1,485 @@
+use unicode_normalization::UnicodeNormalization;
+
+/// 罗马字→平假名对照表（Hepburn式与训令式），转换时优先匹配较长的拼写
+const ROMAJI_TABLE: &[(&str, &str)] = &[
+    // 拗音
+    ("kya", "きゃ"), ("kyu", "きゅ"), ("kyo", "きょ"),
+    ("gya", "ぎゃ"), ("gyu", "ぎゅ"), ("gyo", "ぎょ"),
+    ("sha", "しゃ"), ("shu", "しゅ"), ("sho", "しょ"),
+    ("sya", "しゃ"), ("syu", "しゅ"), ("syo", "しょ"),
+    ("jya", "じゃ"), ("jyu", "じゅ"), ("jyo", "じょ"),
+    ("zya", "じゃ"), ("zyu", "じゅ"), ("zyo", "じょ"),
+    ("cha", "ちゃ"), ("chu", "ちゅ"), ("cho", "ちょ"),
+    ("tya", "ちゃ"), ("tyu", "ちゅ"), ("tyo", "ちょ"),
+    ("nya", "にゃ"), ("nyu", "にゅ"), ("nyo", "にょ"),
+    ("hya", "ひゃ"), ("hyu", "ひゅ"), ("hyo", "ひょ"),
+    ("bya", "びゃ"), ("byu", "びゅ"), ("byo", "びょ"),
+    ("pya", "ぴゃ"), ("pyu", "ぴゅ"), ("pyo", "ぴょ"),
+    ("mya", "みゃ"), ("myu", "みゅ"), ("myo", "みょ"),
+    ("rya", "りゃ"), ("ryu", "りゅ"), ("ryo", "りょ"),
+    ("shi", "し"), ("chi", "ち"), ("tsu", "つ"),
+    ("ja", "じゃ"), ("ju", "じゅ"), ("jo", "じょ"),
+    ("fa", "ふぁ"), ("fi", "ふぃ"), ("fe", "ふぇ"), ("fo", "ふぉ"),
+    // 清音・浊音・半浊音
+    ("ka", "か"), ("ki", "き"), ("ku", "く"), ("ke", "け"), ("ko", "こ"),
+    ("ga", "が"), ("gi", "ぎ"), ("gu", "ぐ"), ("ge", "げ"), ("go", "ご"),
+    ("sa", "さ"), ("si", "し"), ("su", "す"), ("se", "せ"), ("so", "そ"),
+    ("za", "ざ"), ("ji", "じ"), ("zi", "じ"), ("zu", "ず"), ("ze", "ぜ"), ("zo", "ぞ"),
+    ("ta", "た"), ("ti", "ち"), ("tu", "つ"), ("te", "て"), ("to", "と"),
+    ("da", "だ"), ("di", "ぢ"), ("du", "づ"), ("de", "で"), ("do", "ど"),
+    ("na", "な"), ("ni", "に"), ("nu", "ぬ"), ("ne", "ね"), ("no", "の"),
+    ("ha", "は"), ("hi", "ひ"), ("fu", "ふ"), ("hu", "ふ"), ("he", "へ"), ("ho", "ほ"),
+    ("ba", "ば"), ("bi", "び"), ("bu", "ぶ"), ("be", "べ"), ("bo", "ぼ"),
+    ("pa", "ぱ"), ("pi", "ぴ"), ("pu", "ぷ"), ("pe", "ぺ"), ("po", "ぽ"),
+    ("ma", "ま"), ("mi", "み"), ("mu", "む"), ("me", "め"), ("mo", "も"),
+    ("ya", "や"), ("yu", "ゆ"), ("yo", "よ"),
+    ("ra", "ら"), ("ri", "り"), ("ru", "る"), ("re", "れ"), ("ro", "ろ"),
+    ("wa", "わ"), ("wo", "を"),
+    ("a", "あ"), ("i", "い"), ("u", "う"), ("e", "え"), ("o", "お"),
+    ("-", "ー"),
+];
+
+/// 五十音表（行×段），用于判断假名在键盘上是否相邻；空位用'　'占位
+const GOJUON_ROWS: [[char; 5]; 10] = [
+    ['あ', 'い', 'う', 'え', 'お'],
+    ['か', 'き', 'く', 'け', 'こ'],
+    ['さ', 'し', 'す', 'せ', 'そ'],
+    ['た', 'ち', 'つ', 'て', 'と'],
+    ['な', 'に', 'ぬ', 'ね', 'の'],
+    ['は', 'ひ', 'ふ', 'へ', 'ほ'],
+    ['ま', 'み', 'む', 'め', 'も'],
+    ['や', '　', 'ゆ', '　', 'よ'],
+    ['ら', 'り', 'る', 'れ', 'ろ'],
+    ['わ', '　', 'を', '　', 'ん'],
+];
+
+/// 浊音・半浊音・小写假名与所在按键上的基本假名
+const KEY_VARIANTS: &[(&str, &str)] = &[
+    ("がぎぐげご", "かきくけこ"),
+    ("ざじずぜぞ", "さしすせそ"),
+    ("だぢづでど", "たちつてと"),
+    ("ばびぶべぼ", "はひふへほ"),
+    ("ぱぴぷぺぽ", "はひふへほ"),
+    ("ぁぃぅぇぉ", "あいうえお"),
+    ("ゃゅょっゎ", "やゆよつわ"),
+];
+
+/// 同一按键上的变体（か/が、つ/っ）替换代价
+const SAME_KEY_COST: f64 = 0.25;
+/// 同行（同辅音）或同段（同元音）假名替换代价
+const ADJACENT_KANA_COST: f64 = 0.5;
+
+/// 补全未输入完整的音节时尝试的后缀
+const SYLLABLE_COMPLETIONS: [&str; 9] = ["a", "i", "u", "e", "o", "ya", "yu", "yo", "'"];
+
+/// 将罗马字转换为平假名
+/// 返回(已转换的假名, 无法转换的剩余部分)，剩余部分通常是尚未输入完整的音节（如"sak"中的"k"）
+pub fn romaji_to_hiragana(romaji: &str) -> (String, String) {
+    let input: Vec<char> = romaji.trim().to_lowercase().chars().collect();
+    let mut kana = String::new();
+    let mut i = 0;
+
+    'outer: while i < input.len() {
+        let c = input[i];
+        let next = input.get(i + 1).copied();
+
+        // 拨音：n后接子音（y除外）、n'、或nn后不接元音
+        if c == 'n' {
+            match next {
+                Some('\'') => {
+                    kana.push('ん');
+                    i += 2;
+                    continue;
+                }
+                Some('n') => {
+                    let after = input.get(i + 2).copied();
+                    kana.push('ん');
+                    i += if after.is_some_and(|a| "aiueoy".contains(a)) { 1 } else { 2 };
+                    continue;
+                }
+                Some(n) if n.is_ascii_alphabetic() && !"aiueoy".contains(n) => {
+                    kana.push('ん');
+                    i += 1;
+                    continue;
+                }
+                _ => {}
+            }
+        }
+
+        // 促音：重复的子音，或tch
+        if c.is_ascii_alphabetic() && !"aiueon".contains(c)
+            && (next == Some(c) || (c == 't' && next == Some('c')))
+        {
+            kana.push('っ');
+            i += 1;
+            continue;
+        }
+
+        for len in (1..=3).rev() {
+            if i + len > input.len() {
+                continue;
+            }
+            let piece: String = input[i..i + len].iter().collect();
+            if let Some((_, hiragana)) = ROMAJI_TABLE.iter().find(|(romaji, _)| *romaji == piece) {
+                kana.push_str(hiragana);
+                i += len;
+                continue 'outer;
+            }
+        }
+
+        break;
+    }
+
+    (kana, input[i..].iter().collect())
+}
+
+/// 罗马字前缀可能对应的假名前缀
+/// 完整的罗马字只对应一个前缀；末尾音节未输入完整时（如"sak"）列出所有可能的补全（さか、さき、さく…）
+pub fn romaji_prefix_candidates(romaji_prefix: &str) -> Vec<String> {
+    let (kana, rest) = romaji_to_hiragana(romaji_prefix);
+    if rest.is_empty() {
+        return if kana.is_empty() { Vec::new() } else { vec![kana] };
+    }
+
+    let mut candidates = Vec::new();
+    for completion in SYLLABLE_COMPLETIONS {
+        let (completed, remaining) = romaji_to_hiragana(&format!("{}{}", romaji_prefix.trim(), completion));
+        if remaining.is_empty() && !candidates.contains(&completed) {
+            candidates.push(completed);
+        }
+    }
+    candidates
+}
+
+/// 平假名转换为片假名，其他字符保持不变
+pub fn hiragana_to_katakana(text: &str) -> String {
+    text.chars()
+        .map(|ch| match ch {
+            '\u{3041}'..='\u{3096}' => char::from_u32(ch as u32 + 0x60).unwrap_or(ch),
+            _ => ch,
+        })
+        .collect()
+}
+
+/// 规范化标题用于检索：NFKC（全角↔半角、兼容字符一并处理）后转为小写
+/// 如"ＡＩ"→"ai"，"㍿"→"株式会社"
+pub fn normalize_headword(text: &str) -> String {
+    text.nfkc().collect::<String>().to_lowercase()
+}
+
+/// 片假名转为平假名，其他字符保持不变
+fn katakana_char_to_hiragana(ch: char) -> char {
+    match ch {
+        '\u{30A1}'..='\u{30F6}' => char::from_u32(ch as u32 - 0x60).unwrap_or(ch),
+        _ => ch,
+    }
+}
+
+/// 平假名所在按键的基本假名（浊音・小写假名归入对应按键）
+fn base_kana(ch: char) -> char {
+    for (variants, bases) in KEY_VARIANTS {
+        if let Some(index) = variants.chars().position(|variant| variant == ch) {
+            return bases.chars().nth(index).unwrap_or(ch);
+        }
+    }
+    ch
+}
+
+/// 假名在五十音表中的位置(行, 段)
+fn gojuon_position(ch: char) -> Option<(usize, usize)> {
+    GOJUON_ROWS.iter().enumerate().find_map(|(row, columns)| {
+        columns.iter().position(|&kana| kana == ch).map(|column| (row, column))
+    })
+}
+
+/// 片假名转换为平假名，其他字符保持不变
+pub fn katakana_to_hiragana(text: &str) -> String {
+    text.chars().map(katakana_char_to_hiragana).collect()
+}
+
+/// 拗音等与前一个假名合为一拍的小写假名（っ单独成拍，不在此列）
+fn is_small_kana(ch: char) -> bool {
+    matches!(
+        ch,
+        'ゃ' | 'ゅ' | 'ょ' | 'ぁ' | 'ぃ' | 'ぅ' | 'ぇ' | 'ぉ' | 'ゎ'
+            | 'ャ' | 'ュ' | 'ョ' | 'ァ' | 'ィ' | 'ゥ' | 'ェ' | 'ォ' | 'ヮ'
+    )
+}
+
+/// 将假名按拍（mora）切分，拗音（きゃ）算一拍，促音（っ）、拨音（ん）、长音（ー）各算一拍
+pub fn split_mora(text: &str) -> Vec<String> {
+    let mut mora: Vec<String> = Vec::new();
+    for ch in text.chars() {
+        if is_small_kana(ch)
+            && let Some(last) = mora.last_mut()
+        {
+            last.push(ch);
+            continue;
+        }
+        mora.push(ch.to_string());
+    }
+    mora
+}
+
+/// 读音是否符合按拍的模式：`_` 匹配任意一拍，`*` 匹配任意多拍（包括零拍），其余假名按拍精确匹配
+/// 平假名与片假名视为相同，如 "か__つ" 匹配 "かたまつ"、"かっぱつ"
+pub fn matches_mora_pattern(reading: &str, pattern: &str) -> bool {
+    let reading = split_mora(&katakana_to_hiragana(reading));
+    let pattern = split_mora(&katakana_to_hiragana(pattern));
+    match_mora(&pattern, &reading)
+}
+
+/// 动态规划匹配，O(模式拍数 × 读音拍数)：从模式末尾逐拍向前，
+/// rest[j] 表示已处理的模式后缀能否匹配 reading[j..]
+fn match_mora(pattern: &[String], reading: &[String]) -> bool {
+    let n = reading.len();
+    let mut rest = vec![false; n + 1];
+    rest[n] = true;
+
+    for mora in pattern.iter().rev() {
+        let mut current = vec![false; n + 1];
+        for j in (0..=n).rev() {
+            current[j] = match mora.as_str() {
+                "*" => rest[j] || (j < n && current[j + 1]),
+                "_" => j < n && rest[j + 1],
+                mora => j < n && reading[j] == mora && rest[j + 1],
+            };
+        }
+        rest = current;
+    }
+    rest[0]
+}
+
+/// 去掉词尾的送假名（平假名），如 "動く" → "動"、"美しい" → "美"
+/// 全部是平假名时（没有汉字词干）原样返回
+pub fn strip_okurigana(text: &str) -> String {
+    let text = text.trim();
+    let stem = text.trim_end_matches(|ch| matches!(ch, '\u{3041}'..='\u{3096}'));
+    if stem.is_empty() { text.to_string() } else { stem.to_string() }
+}
+
+/// 读音是否只由假名和长音符号组成（包括ゝゞヽヾ等叠字符号）
+pub fn is_valid_kana_reading(text: &str) -> bool {
+    !text.is_empty()
+        && text.chars().all(|ch| {
+            matches!(ch, '\u{3041}'..='\u{3096}' | '\u{309D}' | '\u{309E}' | '\u{30A1}'..='\u{30FA}' | 'ー' | '\u{30FD}' | '\u{30FE}')
+        })
+}
+
+/// 排序键中分隔清音部分和浊音・小写标记部分的字符（小于任何假名，短词排在以它开头的长词前）
+const SORT_KEY_SEPARATOR: char = '\u{1}';
+
+/// 小写假名与对应的大写假名
+const SMALL_KANA: [(char, char); 12] = [
+    ('ぁ', 'あ'), ('ぃ', 'い'), ('ぅ', 'う'), ('ぇ', 'え'), ('ぉ', 'お'), ('っ', 'つ'),
+    ('ゃ', 'や'), ('ゅ', 'ゆ'), ('ょ', 'よ'), ('ゎ', 'わ'), ('ゕ', 'か'), ('ゖ', 'け'),
+];
+
+/// 按五十音顺序排序的键，可直接用SQLite内置的BINARY比较
+/// 第一部分为去掉浊点・半浊点、小写转大写、片假名转平假名、长音（ー）换成前一个假名的元音后的清音假名
+/// （清音平假名的码位本身就是五十音顺序），第二部分为每个字的标记：0=清音 1=小写 2=浊音 3=半浊音
+/// 先比较清音再比较标记，因此 か < が < き，かき < がけ
+pub fn sort_key(reading: &str) -> String {
+    let mut primary = String::new();
+    let mut marks = String::new();
+    let mut previous: Option<char> = None;
+
+    for ch in reading.trim().chars() {
+        let ch = katakana_char_to_hiragana(ch);
+        let (base, mark) = if let Some(&(_, large)) = SMALL_KANA.iter().find(|(small, _)| *small == ch) {
+            (large, '1')
+        } else if ch == 'ー' {
+            (previous.and_then(vowel_of).unwrap_or(ch), '0')
+        } else {
+            let mut decomposed = std::iter::once(ch).nfd();
+            let base = decomposed.next().unwrap_or(ch);
+            match decomposed.next() {
+                Some('\u{3099}') => (base, '2'),
+                Some('\u{309A}') => (base, '3'),
+                _ => (ch, '0'),
+            }
+        };
+        primary.push(base);
+        marks.push(mark);
+        previous = Some(base);
+    }
+
+    primary.push(SORT_KEY_SEPARATOR);
+    primary.push_str(&marks);
+    primary
+}
+
+/// 清音平假名的元音（あいうえお），ん等没有元音的假名返回None
+fn vowel_of(kana: char) -> Option<char> {
+    if kana == 'を' {
+        return Some('お');
+    }
+    match gojuon_position(kana) {
+        Some((9, 4)) | None => None,
+        Some((_, column)) => Some(GOJUON_ROWS[0][column]),
+    }
+}
+
+/// 五十音各行的名称，下标与gojuon_row的返回值对应
+pub const GOJUON_ROW_NAMES: [&str; 10] = ["あ行", "か行", "さ行", "た行", "な行", "は行", "ま行", "や行", "ら行", "わ行"];
+
+/// 文本首字所在的五十音行（0=あ行 … 9=わ行），浊音・片假名归入对应的行；首字不是假名时返回None
+pub fn gojuon_row(text: &str) -> Option<usize> {
+    let first = text.chars().next()?;
+    gojuon_position(base_kana(katakana_char_to_hiragana(first))).map(|(row, _)| row)
+}
+
+/// 两个假名之间的替换代价
+fn substitution_cost(a: char, b: char) -> f64 {
+    let (a, b) = (katakana_char_to_hiragana(a), katakana_char_to_hiragana(b));
+    if a == b {
+        return 0.0;
+    }
+    let (base_a, base_b) = (base_kana(a), base_kana(b));
+    if base_a == base_b {
+        return SAME_KEY_COST;
+    }
+    match (gojuon_position(base_a), gojuon_position(base_b)) {
+        (Some((row_a, column_a)), Some((row_b, column_b))) if row_a == row_b || column_a == column_b => {
+            ADJACENT_KANA_COST
+        }
+        _ => 1.0,
+    }
+}
+
+/// 考虑假名键盘布局的编辑距离
+/// 平假名与片假名视为相同；插入・删除代价为1；同一按键上的变体替换代价0.25，同行或同段的假名替换代价0.5，其余替换代价为1
+pub fn kana_distance(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    let mut previous: Vec<f64> = (0..=b.len()).map(|j| j as f64).collect();
+    let mut current = vec![0.0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        current[0] = i as f64;
+        for j in 1..=b.len() {
+            let substitution = previous[j - 1] + substitution_cost(a[i - 1], b[j - 1]);
+            let deletion = previous[j] + 1.0;
+            let insertion = current[j - 1] + 1.0;
+            current[j] = substitution.min(deletion).min(insertion);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sort_key_gojuon_order() {
+        assert!(sort_key("か") < sort_key("が"));
+        assert!(sort_key("が") < sort_key("き"));
+        assert_eq!(sort_key("カ"), sort_key("か"));
+
+        let mut words = vec!["き", "ぱ", "が", "しょう", "かき", "は", "がか", "カード", "か", "ば", "しよう", "かあと"];
+        words.sort_by_key(|word| sort_key(word));
+        assert_eq!(words, vec!["か", "が", "かあと", "カード", "がか", "かき", "き", "しよう", "しょう", "は", "ば", "ぱ"]);
+    }
+
+    #[test]
+    fn test_strip_okurigana() {
+        assert_eq!(strip_okurigana("動く"), "動");
+        assert_eq!(strip_okurigana("美しい"), "美");
+        assert_eq!(strip_okurigana("取り扱う"), "取り扱");
+        assert_eq!(strip_okurigana("動"), "動");
+        assert_eq!(strip_okurigana("うごく"), "うごく");
+    }
+
+    #[test]
+    fn test_romaji_to_hiragana() {
+        assert_eq!(romaji_to_hiragana("sakura"), ("さくら".to_string(), String::new()));
+        assert_eq!(romaji_to_hiragana("kyouto"), ("きょうと".to_string(), String::new()));
+        assert_eq!(romaji_to_hiragana("matcha"), ("まっちゃ".to_string(), String::new()));
+        assert_eq!(romaji_to_hiragana("kan'i"), ("かんい".to_string(), String::new()));
+        assert_eq!(romaji_to_hiragana("sak"), ("さ".to_string(), "k".to_string()));
+    }
+
+    #[test]
+    fn test_romaji_prefix_candidates() {
+        assert_eq!(romaji_prefix_candidates("saku"), vec!["さく"]);
+        let candidates = romaji_prefix_candidates("sak");
+        assert!(candidates.contains(&"さく".to_string()));
+        assert!(candidates.contains(&"さけ".to_string()));
+        assert!(candidates.contains(&"さきゃ".to_string()));
+        assert!(romaji_prefix_candidates("san").contains(&"さん".to_string()));
+    }
+
+    #[test]
+    fn test_kana_distance() {
+        assert_eq!(kana_distance("さくら", "さくら"), 0.0);
+        // 同一按键
+        assert_eq!(kana_distance("かき", "がき"), SAME_KEY_COST);
+        // 同行（さ→す）、同段（か→さ）
+        assert_eq!(kana_distance("さくら", "すくら"), ADJACENT_KANA_COST);
+        assert_eq!(kana_distance("かくら", "さくら"), ADJACENT_KANA_COST);
+        // 无关替换与普通编辑距离相同
+        assert_eq!(kana_distance("さくら", "さくみ"), 1.0);
+        assert_eq!(kana_distance("さくら", "さら"), 1.0);
+        assert_eq!(kana_distance("サクラ", "さくら"), 0.0);
+    }
+
+    #[test]
+    fn test_is_valid_kana_reading() {
+        assert!(is_valid_kana_reading("あいさつ"));
+        assert!(is_valid_kana_reading("アーティスト"));
+        assert!(!is_valid_kana_reading("あい【愛"));
+        assert!(!is_valid_kana_reading("愛"));
+        assert!(!is_valid_kana_reading(""));
+    }
+
+    #[test]
+    fn test_split_mora() {
+        assert_eq!(split_mora("きゃっと"), vec!["きゃ", "っ", "と"]);
+        assert_eq!(split_mora("ジュース"), vec!["ジュ", "ー", "ス"]);
+    }
+
+    #[test]
+    fn test_matches_mora_pattern() {
+        assert!(matches_mora_pattern("かっぱつ", "か__つ"));
+        assert!(matches_mora_pattern("カタマツ", "か__つ"));
+        // 拗音算一拍
+        assert!(matches_mora_pattern("かしゃつ", "か_つ"));
+        assert!(!matches_mora_pattern("かしゃつ", "か__つ"));
+        assert!(!matches_mora_pattern("かつ", "か__つ"));
+        assert!(!matches_mora_pattern("きゃく", "き_"));
+        assert!(matches_mora_pattern("きゃく", "きゃ_"));
+        assert!(matches_mora_pattern("かんがえる", "か*る"));
+        assert!(matches_mora_pattern("かる", "か*る"));
+        assert!(matches_mora_pattern("かる", "**か**る**"));
+        assert!(!matches_mora_pattern("かるい", "か*る"));
+        assert!(matches_mora_pattern("", "*"));
+    }
+
+    #[test]
+    fn test_mora_pattern_is_not_exponential() {
+        // 逐个尝试*跳过的拍数时，这种模式需要指数时间
+        let reading = "あ".repeat(60) + "い";
+        assert!(!matches_mora_pattern(&reading, "*あ*あ*あ*あ*あ*あ*あ*あ*あ*あ*ん"));
+        assert!(matches_mora_pattern(&reading, "*あ*あ*あ*あ*あ*あ*あ*あ*あ*あ*い"));
+    }
+
+    #[test]
+    fn test_gojuon_row() {
+        assert_eq!(gojuon_row("あい"), Some(0));
+        assert_eq!(gojuon_row("がくせい"), Some(1));
+        assert_eq!(gojuon_row("ヨル"), Some(7));
+        assert_eq!(gojuon_row("愛"), None);
+        assert_eq!(gojuon_row(""), None);
+    }
+
+    #[test]
+    fn test_normalize_headword() {
+        assert_eq!(normalize_headword("ＡＩ"), normalize_headword("AI"));
+        assert_eq!(normalize_headword("㍿"), "株式会社");
+        assert_eq!(normalize_headword("ｶﾀｶﾅ"), "カタカナ");
+    }
+}
@@ -0,0 +1,59 @@
+//! 日语词典数据处理库
+//!
+//! 解析核心（`parser`、`obunsha_parser`、`kana`、`data_cleaner`）不依赖SQLite、tokio和axum，
+//! 可以编译到WebAssembly，在浏览器中解析用户自己导出的MDX文本：
+//!
+//! ```text
+//! cargo build --lib --target wasm32-unknown-unknown --no-default-features --features wasm
+//! ```
+//!
+//! 数据库与Web服务模块需要 `server` feature（默认开启）；导出文件的gzip/zstd压缩需要 `compression` feature（默认开启）。
+//!
+//! 作为库使用时，常用类型已在crate根部重新导出：
+//!
+//! ```no_run
+//! use dict::ObunshaDictDatabase;
+//!
+//! let db = ObunshaDictDatabase::open_readonly("obunsha_dict.db")?;
+//! for entry in db.search_by_kana_exact("あい")? {
+//!     println!("{}: {}", entry.headword, entry.definition_text);
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+pub mod conjugation;
+pub mod csv_format;
+pub mod data_cleaner;
+pub mod jitai;
+pub mod kana;
+pub mod obunsha_parser;
+pub mod parser;
+
+#[cfg(feature = "server")]
+pub mod compression;
+#[cfg(feature = "server")]
+pub mod database;
+#[cfg(feature = "server")]
+pub mod download;
+#[cfg(feature = "server")]
+pub mod epub_export;
+#[cfg(feature = "server")]
+pub mod loadtest;
+#[cfg(feature = "server")]
+pub mod merged_export;
+#[cfg(feature = "server")]
+pub mod obunsha_dict;
+#[cfg(feature = "server")]
+pub mod web_server;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+// 常用类型的公开API
+pub use obunsha_parser::{ObunshaDictEntry, ObunshaParser};
+pub use parser::{DictParser, DictionaryEntry, HyoCategory, JouyouFlag, KanjiWriting, ParseSummary, Reading, ReadingKind};
+
+#[cfg(feature = "server")]
+pub use database::Database;
+#[cfg(feature = "server")]
+pub use obunsha_dict::{BooleanQuery, DatabaseDiff, ExportOrder, JsonlExportOptions, ObunshaDictDatabase, RankedEntry, SearchTier, StorageStats};
@@ -0,0 +1,257 @@
+//! 负载测试：用演示数据启动服务器，并发发送 /search 请求，统计吞吐量和延迟分位数
+//!
+//! 服务器的每个请求都会单独打开数据库，因此演示数据写入临时目录中的数据库文件，而不是内存数据库。
+
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::obunsha_dict::ObunshaDictDatabase;
+use crate::web_server::{serve, ServerOptions};
+
+/// 请求按顺序轮流使用的 (查询词, 搜索类型)，查询词均在演示数据中
+const SEARCH_MIX: [(&str, &str); 10] = [
+    ("あい", "exact"),
+    ("愛", "kanji"),
+    ("かぜ", "kana"),
+    ("かん", "prefix"),
+    ("しい", "suffix"),
+    ("心", "definition"),
+    ("かんしよう", "typo"),
+    ("熱 AND 湯", "boolean"),
+    ("はし", "fuzzy"),
+    ("明るい", "stem"),
+];
+
+/// 等待服务器启动的最长时间
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 负载测试的参数
+#[derive(Debug, Clone)]
+pub struct LoadTestOptions {
+    /// 请求总数
+    pub requests: usize,
+    /// 同时进行的请求数
+    pub concurrency: usize,
+    /// 服务器监听的端口，为0时由系统分配空闲端口
+    pub port: u16,
+    /// 传给服务器的 --db-pool-size，未设置时使用服务器的默认值
+    pub db_pool_size: Option<usize>,
+}
+
+impl Default for LoadTestOptions {
+    fn default() -> Self {
+        LoadTestOptions {
+            requests: 1000,
+            concurrency: 16,
+            port: 0,
+            db_pool_size: None,
+        }
+    }
+}
+
+/// 负载测试结果
+#[derive(Debug)]
+pub struct LoadTestReport {
+    /// 从第一个请求发出到最后一个请求完成的时间
+    pub elapsed: Duration,
+    /// 失败（连接错误或非2xx状态）的请求数
+    pub failures: usize,
+    /// 每个搜索类型的请求延迟，已排序
+    pub latencies: BTreeMap<&'static str, Vec<Duration>>,
+}
+
+impl LoadTestReport {
+    /// 所有请求的延迟，已排序
+    pub fn all_latencies(&self) -> Vec<Duration> {
+        let mut all: Vec<Duration> = self.latencies.values().flatten().copied().collect();
+        all.sort();
+        all
+    }
+
+    /// 每秒成功完成的请求数
+    pub fn throughput(&self) -> f64 {
+        let total: usize = self.latencies.values().map(Vec::len).sum();
+        total as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+
+    /// 打印吞吐量、整体和各搜索类型的延迟分位数
+    pub fn print(&self) {
+        let all = self.all_latencies();
+        println!("📊 负载测试结果");
+        println!("   请求数: {}（失败 {}）", all.len(), self.failures);
+        println!("   耗时: {:.2} 秒，吞吐量: {:.1} 请求/秒", self.elapsed.as_secs_f64(), self.throughput());
+        println!(
+            "   延迟: p50 {:.1} ms, p95 {:.1} ms, p99 {:.1} ms",
+            millis(percentile(&all, 50.0)),
+            millis(percentile(&all, 95.0)),
+            millis(percentile(&all, 99.0))
+        );
+        for (search_type, latencies) in &self.latencies {
+            println!(
+                "   {:<10} {:>6} 次  p50 {:>7.1} ms  p95 {:>7.1} ms",
+                search_type,
+                latencies.len(),
+                millis(percentile(latencies, 50.0)),
+                millis(percentile(latencies, 95.0))
+            );
+        }
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// 已排序延迟的分位数（最近秩法），为空时返回0
+pub fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// 写入演示数据，在后台线程启动服务器，然后并发发送请求
+pub fn run_load_test(options: &LoadTestOptions) -> Result<LoadTestReport, Box<dyn std::error::Error>> {
+    let db_path = std::env::temp_dir().join(format!("jp_dict_loadtest_{}.db", std::process::id()));
+    let db_path = db_path.to_string_lossy().into_owned();
+    let _ = std::fs::remove_file(&db_path);
+
+    let db = ObunshaDictDatabase::new(&db_path)?;
+    db.initialize()?;
+    let count = db.seed_demo()?;
+    drop(db);
+    println!("🌱 已写入 {} 条演示词条: {}", count, db_path);
+
+    let report = bind_and_run(&db_path, options);
+    let _ = std::fs::remove_file(&db_path);
+    report
+}
+
+/// 先在本线程绑定端口（端口被占用时直接报错），再在后台线程启动服务器并发送请求
+fn bind_and_run(db_path: &str, options: &LoadTestOptions) -> Result<LoadTestReport, Box<dyn std::error::Error>> {
+    let listener = std::net::TcpListener::bind(("127.0.0.1", options.port))?;
+    listener.set_nonblocking(true)?;
+    let addr = listener.local_addr()?;
+
+    // 服务器使用独立的运行时，避免与发送请求的任务争抢线程；进程结束时随之退出
+    // 服务器退出（启动失败）时通过server_errors通知客户端
+    let (error_sender, server_errors) = mpsc::channel::<String>();
+    let server_db_path = db_path.to_string();
+    let server_options = ServerOptions {
+        db_pool_size: options.db_pool_size,
+        ..ServerOptions::default()
+    };
+    std::thread::spawn(move || {
+        let result = tokio::runtime::Runtime::new().map_err(|e| e.to_string()).and_then(|rt| {
+            rt.block_on(async {
+                let listener = tokio::net::TcpListener::from_std(listener).map_err(|e| e.to_string())?;
+                serve(listener, &server_db_path, server_options).await.map_err(|e| e.to_string())
+            })
+        });
+        let message = match result {
+            Ok(()) => "服务器意外退出".to_string(),
+            Err(e) => e,
+        };
+        let _ = error_sender.send(message);
+    });
+
+    let rt = tokio::runtime::Runtime::new()?;
+    rt.block_on(send_requests(addr, options, &server_errors))
+}
+
+async fn send_requests(
+    addr: SocketAddr,
+    options: &LoadTestOptions,
+    server_errors: &mpsc::Receiver<String>,
+) -> Result<LoadTestReport, Box<dyn std::error::Error>> {
+    let base_url = format!("http://{}", addr);
+    let client = reqwest::Client::new();
+
+    // 等待服务器开始处理请求
+    let startup = Instant::now();
+    while client.get(format!("{}/", base_url)).send().await.is_err() {
+        if let Ok(message) = server_errors.try_recv() {
+            return Err(format!("服务器启动失败: {}", message).into());
+        }
+        if startup.elapsed() > STARTUP_TIMEOUT {
+            return Err(format!("服务器在{}秒内没有启动", STARTUP_TIMEOUT.as_secs()).into());
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    println!("🚦 发送 {} 个请求（并发 {}）...", options.requests, options.concurrency);
+    let next = Arc::new(AtomicUsize::new(0));
+    let started = Instant::now();
+    let mut workers = Vec::new();
+    for _ in 0..options.concurrency.max(1) {
+        let client = client.clone();
+        let url = format!("{}/search", base_url);
+        let next = next.clone();
+        let requests = options.requests;
+        workers.push(tokio::spawn(async move {
+            let mut results = Vec::new();
+            loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                if index >= requests {
+                    break;
+                }
+                let (word, search_type) = SEARCH_MIX[index % SEARCH_MIX.len()];
+                let request_start = Instant::now();
+                let ok = match client.get(&url).query(&[("word", word), ("search_type", search_type)]).send().await {
+                    Ok(response) => response.status().is_success(),
+                    Err(_) => false,
+                };
+                results.push((search_type, request_start.elapsed(), ok));
+            }
+            results
+        }));
+    }
+
+    let mut latencies: BTreeMap<&'static str, Vec<Duration>> = BTreeMap::new();
+    let mut failures = 0;
+    for worker in workers {
+        for (search_type, latency, ok) in worker.await? {
+            if ok {
+                latencies.entry(search_type).or_default().push(latency);
+            } else {
+                failures += 1;
+            }
+        }
+    }
+    let elapsed = started.elapsed();
+    for values in latencies.values_mut() {
+        values.sort();
+    }
+
+    Ok(LoadTestReport { elapsed, failures, latencies })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile() {
+        let sorted: Vec<Duration> = (1..=100).map(Duration::from_millis).collect();
+        assert_eq!(percentile(&sorted, 50.0), Duration::from_millis(50));
+        assert_eq!(percentile(&sorted, 99.0), Duration::from_millis(99));
+        assert_eq!(percentile(&sorted, 100.0), Duration::from_millis(100));
+        assert_eq!(percentile(&sorted[..1], 95.0), Duration::from_millis(1));
+        assert_eq!(percentile(&[], 50.0), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_port_in_use_is_an_error() {
+        let occupied = std::net::TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let options = LoadTestOptions {
+            port: occupied.local_addr().unwrap().port(),
+            ..LoadTestOptions::default()
+        };
+        assert!(bind_and_run("unused.db", &options).is_err());
+    }
+}
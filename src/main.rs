@@ -1,14 +1,12 @@
-mod database;
-mod parser;
-mod obunsha_dict;  // 新增：旺文社国語辞典模块
-mod data_cleaner;  // 新增：数据清理模块
-mod web_server;  
-use database::{Database, DictionaryEntry};
-use parser::DictParser;
-use obunsha_dict::ObunshaDictDatabase;  // 移除未使用的ObunshaDictEntry
-use data_cleaner::DataCleaner;  // 新增：数据清理器导入
+use dict::database::{Database, DictionaryEntry};
+use dict::parser::DictParser;
+use dict::compression::Compression;
+use dict::obunsha_dict::{ExportOrder, JsonlExportOptions, ObunshaDictDatabase, MAX_REDIRECT_DEPTH};
+use dict::data_cleaner::{split_by_kana_row, CleanOptions, DataCleaner};
+use dict::download::{download_file, DEFAULT_DOWNLOAD_TIMEOUT};
+use dict::loadtest::{run_load_test, LoadTestOptions};
 use std::env;
-use web_server::start_server;  // 修正：使用正确的函数名
+use dict::web_server::{load_frequency_list, start_server, ServerOptions};
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("表現読解国語辞典 - 日语词典数据提取工具");
     
@@ -17,7 +15,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     
     match mode {
         "extract" => {
-            extract_dictionary_data()
+            extract_dictionary_data(args.iter().any(|arg| arg == "--jouyou-flags"))
         }
         "test-agaku" => {
             test_agaku_parsing()
@@ -26,26 +24,240 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             init_obunsha_table()
         }
         "clean-data" => {  // 新增：清理导出数据
-            clean_exported_data()
+            let has_flag = |flag: &str| args.iter().any(|arg| arg == flag);
+            let options = CleanOptions {
+                strip_links: has_flag("--strip-links"),
+                keep_data_attrs: !has_flag("--strip-data-attrs"),
+                keep_comments: !has_flag("--strip-comments"),
+            };
+            clean_exported_data(has_flag("--dry-run"), options)
         }
         "analyze-data" => {  // 新增：分析数据结构
-            analyze_exported_data()
+            let fraction = args
+                .iter()
+                .position(|arg| arg == "--boundary-fraction")
+                .and_then(|index| args.get(index + 1));
+            match boundary_fraction(fraction.map(|value| value.as_str())) {
+                Ok(fraction) => analyze_exported_data(fraction),
+                Err(message) => {
+                    println!("❌ {}", message);
+                    Err(message.into())
+                }
+            }
+        }
+        "split-export" => {  // 按五十音行拆分清理后的数据文件
+            split_exported_data(
+                args.get(2).map(|s| s.as_str()).filter(|arg| !arg.starts_with("--")).unwrap_or("exported_dict_cleaned.txt"),
+                args.iter()
+                    .position(|arg| arg == "--output-dir")
+                    .and_then(|index| args.get(index + 1))
+                    .map(|s| s.as_str())
+                    .unwrap_or("split_export"),
+            )
         }
         "import-obunsha" => {  // 新增：导入旺文社数据到数据库
-            import_obunsha_data()
+            let paths: Vec<&str> = args.iter().skip(2).map(|s| s.as_str()).filter(|arg| !arg.starts_with("--")).collect();
+            import_obunsha_data(&paths, args.iter().any(|arg| arg == "--strict"), args.iter().any(|arg| arg == "--resume"))
         }
         "server" => {  // 新增：启动Web服务器
-            start_web_server()
+            let option_value = |flag: &str| {
+                args.iter()
+                    .position(|arg| arg == flag)
+                    .and_then(|index| args.get(index + 1))
+                    .map(|value| value.as_str())
+            };
+            // 慢查询阈值：命令行参数优先，其次是环境变量 SLOW_QUERY_THRESHOLD_MS
+            let slow_query_threshold_ms = option_value("--slow-query-ms")
+                .map(str::to_string)
+                .or_else(|| env::var("SLOW_QUERY_THRESHOLD_MS").ok())
+                .and_then(|value| value.trim().parse::<u64>().ok());
+            let positive = |flag: &str| match positive_flag(flag, option_value(flag)) {
+                Ok(value) => value,
+                Err(message) => {
+                    println!("❌ {}", message);
+                    std::process::exit(1);
+                }
+            };
+            let batch_body_limit = positive("--batch-body-limit");
+            let import_body_limit = positive("--import-body-limit");
+            let db_pool_size = positive("--db-pool-size");
+            let options = ServerOptions {
+                slow_query_threshold_ms,
+                admin_token: option_value("--admin-token")
+                    .map(str::to_string)
+                    .or_else(|| env::var("DICT_ADMIN_TOKEN").ok())
+                    .filter(|token| !token.trim().is_empty()),
+                stats_refresh_interval: option_value("--stats-refresh-secs")
+                    .and_then(|value| value.trim().parse::<u64>().ok())
+                    .filter(|secs| *secs > 0)
+                    .map(std::time::Duration::from_secs),
+                batch_body_limit,
+                import_body_limit,
+                db_pool_size,
+                ..ServerOptions::default()
+            };
+            let download_timeout = match download_timeout(option_value("--download-timeout")) {
+                Ok(timeout) => timeout,
+                Err(message) => {
+                    println!("❌ {}", message);
+                    std::process::exit(1);
+                }
+            };
+            start_web_server(
+                option_value("--frequency-list"),
+                option_value("--db-url"),
+                option_value("--db-sha256"),
+                download_timeout,
+                options,
+            )
+        }
+        "loadtest" => {  // 用演示数据启动服务器并发送并发请求，报告吞吐量和延迟
+            let options = match load_test_options(&args) {
+                Ok(options) => options,
+                Err(message) => {
+                    println!("❌ {}", message);
+                    return Err(message.into());
+                }
+            };
+            match run_load_test(&options) {
+                Ok(report) => {
+                    report.print();
+                    Ok(())
+                }
+                Err(e) => {
+                    println!("❌ 负载测试失败: {}", e);
+                    Err(e)
+                }
+            }
+        }
+        "seed-demo" => {  // 写入内置演示数据
+            seed_demo_database(args.get(2).map(|s| s.as_str()).unwrap_or("obunsha_dict.db"))
+        }
+        "export-epub" => {  // 导出EPUB3词典
+            export_epub_dictionary(args.get(2).map(|s| s.as_str()).unwrap_or("obunsha_dict.epub"))
+        }
+        "export-merged" => {  // 合并两个词典导出为JSON Lines
+            let path = args.get(2).map(|s| s.as_str()).filter(|arg| !arg.starts_with("--")).unwrap_or("merged_dict.jsonl");
+            match export_compression(&args) {
+                Ok(compression) => export_merged_dictionary(path, compression),
+                Err(message) => {
+                    println!("❌ {}", message);
+                    Ok(())
+                }
+            }
+        }
+        "export-json" => {  // 导出为JSON Lines，--since 只导出之后更新的词条（增量发布）
+            let since = args
+                .iter()
+                .position(|arg| arg == "--since")
+                .and_then(|index| args.get(index + 1))
+                .map(|s| s.as_str());
+            let path = args.get(2).map(|s| s.as_str()).filter(|arg| !arg.starts_with("--")).unwrap_or("obunsha_dict.jsonl");
+            match export_order(&args).and_then(|order| export_compression(&args).map(|compression| (order, compression))) {
+                Ok((order, compression)) => {
+                    export_json(path, &JsonlExportOptions { since: since.map(str::to_string), order, compression })
+                }
+                Err(message) => {
+                    println!("❌ {}", message);
+                    Ok(())
+                }
+            }
+        }
+        "export-csv" | "import-csv" => {  // CSV导出/导入（供电子表格编辑）
+            let delimiter = args
+                .iter()
+                .position(|arg| arg == "--delimiter")
+                .and_then(|index| args.get(index + 1))
+                .and_then(|value| value.chars().next())
+                .unwrap_or(',');
+            let path = args.get(2).map(|s| s.as_str()).filter(|arg| !arg.starts_with("--")).unwrap_or("obunsha_dict.csv");
+            match export_order(&args) {
+                Ok(order) => transfer_csv(mode == "import-csv", path, delimiter, order),
+                Err(message) => {
+                    println!("❌ {}", message);
+                    Ok(())
+                }
+            }
+        }
+        "check-redirects" => {  // 检查无法到达词条的重定向
+            check_redirects(
+                args.get(2).map(|s| s.as_str()).filter(|arg| !arg.starts_with("--")).unwrap_or("obunsha_dict.db"),
+                args.iter()
+                    .position(|arg| arg == "--output")
+                    .and_then(|index| args.get(index + 1))
+                    .map(|s| s.as_str())
+                    .unwrap_or("orphan_redirects.tsv"),
+            )
+        }
+        "diff" => {  // 比较两个数据库
+            match (args.get(2), args.get(3)) {
+                (Some(old_db), Some(new_db)) => {
+                    diff_databases(old_db, new_db, args.iter().any(|arg| arg == "--report"))
+                }
+                _ => {
+                    println!("用法: diff <old.db> <new.db> [--report]");
+                    Ok(())
+                }
+            }
+        }
+        "reparse-all" => {  // 用当前解析器重新生成纯文本定义
+            reparse_all_entries()
+        }
+        "reprocess-failures" => {  // 重新解析隔离区中的词条
+            reprocess_parse_failures()
+        }
+        "vacuum" => {  // 压缩数据库并刷新统计信息
+            compact_database(args.get(2).map(|s| s.as_str()).unwrap_or("obunsha_dict.db"))
+        }
+        "reindex" => {  // 重建索引并刷新统计信息
+            reindex_database(args.get(2).map(|s| s.as_str()).unwrap_or("obunsha_dict.db"))
+        }
+        "strip-raw" => {  // 清空原始MDX内容以缩小数据库
+            strip_raw_content(
+                args.get(2).map(|s| s.as_str()).filter(|arg| !arg.starts_with("--")).unwrap_or("obunsha_dict.db"),
+                args.iter().any(|arg| arg == "--yes"),
+            )
+        }
+        "verify" => {  // 校验数据库完整性
+            verify_database(args.get(2).map(|s| s.as_str()).unwrap_or("obunsha_dict.db"))
         }
         _ => {
             println!("使用方法:");
-            println!("  extract      - 提取词典数据");
+            println!("  extract      - 提取词典数据（--jouyou-flags 记录◇△▽标记的表外字）");
             println!("  test-agaku   - 测试あがく词条解析");
             println!("  init-obunsha - 初始化旺文社国语辞典表");
-            println!("  clean-data   - 清理exported_dict_full.txt");
-            println!("  analyze-data - 分析exported_dict_full.txt结构");
-            println!("  import-obunsha - 导入清理后的数据到旺文社数据库");
-            println!("  server       - 启动Web API服务器");
+            println!("  clean-data   - 清理exported_dict_full.txt（--dry-run 只统计不写出）");
+            println!("                 --strip-links / --strip-data-attrs / --strip-comments 去除对应HTML内容");
+            println!("  analyze-data - 分析exported_dict_full.txt结构（--boundary-fraction <0-1> 从总行数的该比例处开始查找分界点，默认0.5）");
+            println!("  split-export [file] [--output-dir dir] - 按五十音行拆分清理后的数据（あ行.txt…，非假名开头的写入misc.txt）");
+            println!("  import-obunsha [file...] - 导入清理后的数据到旺文社数据库，可指定多个文件（--strict 拒绝假名读音含非假名字符的词条并写入隔离区，POST /validate 的其他问题只警告）");
+            println!("                 --resume 从上次中断的位置（<file>.offset）继续导入单个文件，文件在中断后被修改时拒绝继续");
+            println!("  server       - 启动Web API服务器（--frequency-list freq.csv 按词频排序结果）");
+            println!("                 --db-url <url> [--db-sha256 <hex>] 数据库不存在时先从该地址下载");
+            println!("                 --download-timeout <秒> 下载超时（默认600秒，代理读取 HTTP_PROXY/HTTPS_PROXY）");
+            println!("                 --admin-token <token> 管理接口（如POST /import）的Bearer令牌（或设置 DICT_ADMIN_TOKEN）");
+            println!("                 --stats-refresh-secs <秒> /stats 缓存的刷新间隔（默认60秒）");
+            println!("                 --slow-query-ms <ms> 记录超过该耗时的搜索（或设置 SLOW_QUERY_THRESHOLD_MS）");
+            println!("                 --batch-body-limit <字节> / --import-body-limit <字节> POST /entries、/import 的请求体上限（默认1MB / 64MB）");
+            println!("                 --db-pool-size <N> 同时执行的数据库查询数（连接数）上限（默认CPU核心数）");
+            println!("  loadtest     - 用演示数据启动服务器，并发发送混合类型的 /search 请求，报告吞吐量和p50/p95/p99延迟");
+            println!("                 --requests <N>（默认1000） --concurrency <N>（默认16） --port <端口>（默认由系统分配空闲端口） --db-pool-size <N>");
+            println!("  seed-demo [db] - 写入约50条演示词条（无需MDX文件）");
+            println!("  export-epub [file] - 导出EPUB3词典（供电子阅读器使用）");
+            println!("  export-merged [file] - 合并表現読解与旺文社两个词典，导出为JSON Lines");
+            println!("  export-json [file] [--since <时间戳>] - 导出旺文社词条为JSON Lines（--since 只导出之后更新的词条）");
+            println!("  export-csv [file] [--delimiter ;] - 导出旺文社词条为CSV");
+            println!("                 export-json/export-csv 可加 --order-by id|headword|sort_key|data_id（默认id，即插入顺序）");
+            println!("                 export-json/export-merged 可加 --compress gzip|zstd[:级别] 压缩输出（默认不压缩，zstd默认级别3，需要compression feature）");
+            println!("  import-csv [file] [--delimiter ;] - 从CSV导入词条（按data_id插入或更新）");
+            println!("  check-redirects [db] [--output file] - 找出目标词条不存在的重定向，写出为TSV");
+            println!("  diff <old.db> <new.db> [--report] - 比较两个数据库（--report 写出diff_report.json）");
+            println!("  reprocess-failures - 重新解析parse_failures中的词条");
+            println!("  reparse-all  - 从原始MDX内容重新生成纯文本定义（义项以换行分隔）");
+            println!("  vacuum [db]  - 压缩数据库（VACUUM + ANALYZE），回收重复导入产生的空间");
+            println!("  reindex [db] - 重建索引和释义全文索引并刷新统计信息（大批量导入或手工修改后使用）");
+            println!("  strip-raw [db] --yes - 清空raw_mdx_content并压缩数据库（有损，用于发布版）");
+            println!("  verify [db]  - 校验数据库完整性");
             Ok(())
         }
     }
@@ -68,7 +280,7 @@ fn init_obunsha_table() -> Result<(), Box<dyn std::error::Error>> {
 fn test_agaku_parsing() -> Result<(), Box<dyn std::error::Error>> {
     println!("🧪 测试あがく词条解析...");
     
-    let parser = DictParser::new();
+    let parser = DictParser::with_jouyou_flags();
     let test_html = r#"<link rel="stylesheet" href="style.css"><container data-id="236" data-type="2"><div id="id_00000236" class="item item_ippan"><div class="head"><span class="head_kana">あが・く</span><span class="head_hyo_2"><span class="ka_hyo_2">〖</span><span class="mj_sup">◇</span>足<span class="mj_sup">△</span>搔く<span class="ka_hyo_2">〗</span></span></div><div class="mean_normal"><span class="pos"><span class="ka_pos_s">（</span>自五<span class="ka_pos_e">）</span></span><span class="ka_inflec">｛</span><span class="inflec">カ<span class="mj_inflec">（</span>コ<span class="mj_inflec">）</span>・キ<span class="mj_inflec">（</span>イ<span class="mj_inflec">）</span>・<br>ク・ク・ケ・ケ</span><span class="ka_inflec">｝</span></div><div class="mean_lv_2 mean_no_1">❶手足を動かしてもがく。じたばたする。<span class="ex_text">組み敷<span class="mlg mlg_1">し</span>かれて━</span></div><div class="mean_lv_2 mean_no_2">❷悪い状況<span class="mlg mlg_6">じようきよう</span>からぬけ出そうとして、いろいろむだな試みをする。<span class="ex_text">今さら━・いてもむだだ</span></div></div></contaienr></html>"#;
     
     if let Some(entry) = parser.parse_entry(test_html) {
@@ -76,6 +288,8 @@ fn test_agaku_parsing() -> Result<(), Box<dyn std::error::Error>> {
         println!("假名: {}", entry.kana_entry);
         println!("汉字: {:?}", entry.kanji_form);
         println!("发音: {:?}", entry.pronunciation);
+        println!("读音: {:?}", entry.readings);
+        println!("表外字: {:?}", entry.jouyou_flags);
         println!("释义: {}", entry.meaning);
         println!("类型: {}", entry.entry_type);
     } else {
@@ -86,7 +300,7 @@ fn test_agaku_parsing() -> Result<(), Box<dyn std::error::Error>> {
 }
 
 /// 从jpdict.txt提取数据到数据库
-fn extract_dictionary_data() -> Result<(), Box<dyn std::error::Error>> {
+fn extract_dictionary_data(jouyou_flags: bool) -> Result<(), Box<dyn std::error::Error>> {
     println!("🚀 开始从jpdict.txt提取词典数据...");
     
     // 创建数据库连接
@@ -99,8 +313,8 @@ fn extract_dictionary_data() -> Result<(), Box<dyn std::error::Error>> {
     db.clear_all_entries()?;
     println!("✅ 数据库已清空");
     
-    // 创建解析器
-    let parser = DictParser::new();
+    // 创建解析器（--jouyou-flags 时记录◇△▽表外字标记）
+    let parser = if jouyou_flags { DictParser::with_jouyou_flags() } else { DictParser::new() };
     
     // 解析jpdict.txt文件
     let jpdict_path = "data/jpdict.txt";
@@ -140,11 +354,16 @@ fn extract_dictionary_data() -> Result<(), Box<dyn std::error::Error>> {
 }
 
 /// 清理导出的字典数据
-fn clean_exported_data() -> Result<(), Box<dyn std::error::Error>> {
+fn clean_exported_data(dry_run: bool, options: CleanOptions) -> Result<(), Box<dyn std::error::Error>> {
     println!("🧹 清理exported_dict_full.txt数据...");
     
-    let mut cleaner = DataCleaner::new();
-    cleaner.clean_exported_dict("exported_dict_full.txt", "exported_dict_cleaned.txt")?;
+    let mut cleaner = DataCleaner::with_options(options);
+    cleaner.clean_exported_dict("exported_dict_full.txt", "exported_dict_cleaned.txt", dry_run)?;
+    
+    if !dry_run {
+        let redirect_count = cleaner.write_redirects("exported_dict_redirects.tsv")?;
+        println!("🔗 已写出 {} 条重定向到 exported_dict_redirects.tsv", redirect_count);
+    }
     
     let (valid, redirects, mappings) = cleaner.get_stats();
     println!("📈 清理结果:");
@@ -155,74 +374,500 @@ fn clean_exported_data() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-/// 分析导出数据的结构
-fn analyze_exported_data() -> Result<(), Box<dyn std::error::Error>> {
+/// 分析导出数据的结构，boundary_fraction为查找分界点的起始比例（未指定时使用默认值）
+fn analyze_exported_data(boundary_fraction: Option<f64>) -> Result<(), Box<dyn std::error::Error>> {
     println!("🔍 分析exported_dict_full.txt结构...");
     
     let mut cleaner = DataCleaner::new();
+    if let Some(fraction) = boundary_fraction {
+        cleaner.boundary_search_fraction = fraction;
+    }
     cleaner.analyze_file_structure("exported_dict_full.txt")?;
+    if cleaner.boundary_line().is_none() {
+        println!("💡 可以用 --boundary-fraction 调整查找分界点的起始位置");
+    }
     
     Ok(())
 }
 
+/// 按标题首字的五十音行拆分清理后的数据文件（首字不是假名的写入misc.txt）
+fn split_exported_data(input_path: &str, output_dir: &str) -> Result<(), Box<dyn std::error::Error>> {
+    println!("✂️  按五十音行拆分 {} ...", input_path);
+
+    if !std::path::Path::new(input_path).exists() {
+        println!("❌ 错误：数据文件 {} 不存在", input_path);
+        return Ok(());
+    }
+
+    let counts = split_by_kana_row(input_path, output_dir)?;
+    for (file_name, count) in &counts {
+        println!("  - {}: {} 条", file_name, count);
+    }
+    let total: usize = counts.iter().map(|(_, count)| count).sum();
+    println!("✅ 已将 {} 条词条拆分到 {} 个文件（{}）", total, counts.len(), output_dir);
+    Ok(())
+}
+
 /// 导入清理后的数据到旺文社数据库
-fn import_obunsha_data() -> Result<(), Box<dyn std::error::Error>> {
+/// 未指定文件时导入exported_dict_cleaned.txt；指定多个文件时依次导入同一张表
+fn import_obunsha_data(paths: &[&str], strict: bool, resume: bool) -> Result<(), Box<dyn std::error::Error>> {
     println!("🚀 导入清理后的数据到旺文社数据库...");
     
-    let cleaned_data_path = "exported_dict_cleaned.txt";
-    let db = ObunshaDictDatabase::new("obunsha_dict.db")?;
+    let paths = if paths.is_empty() { vec!["exported_dict_cleaned.txt"] } else { paths.to_vec() };
+    for path in &paths {
+        if !std::path::Path::new(path).exists() {
+            println!("❌ 错误：数据文件 {} 不存在", path);
+            return Ok(());
+        }
+    }
+
+    let mut db = ObunshaDictDatabase::new("obunsha_dict.db")?;
+    db.set_strict_kana(strict);
     
     // 确保表已经初始化
     db.initialize()?;
     
-    println!("📖 开始从清理数据导入词条: {}", cleaned_data_path);
-    let imported_count = db.import_from_cleaned_data(cleaned_data_path)?;
+    let imported_count = if let [path] = paths.as_slice() {
+        db.import_from_cleaned_data(path, resume)?
+    } else {
+        if resume {
+            println!("⚠️  --resume 只支持单个文件，将完整导入所有文件");
+        }
+        db.import_from_cleaned_files(&paths)?
+    };
+
+    // 记录词典来源信息，便于确认数据库对应的词典版本
+    db.set_metadata("source_file", &paths.join(", "))?;
+    db.set_metadata("imported_entries", &imported_count.to_string())?;
+
+    let metadata_path = "exported_dict_meta.json";
+    match std::fs::read_to_string(metadata_path) {
+        Ok(content) => {
+            let metadata: serde_json::Map<String, serde_json::Value> = serde_json::from_str(&content)?;
+            for (key, value) in metadata {
+                let value = match value {
+                    serde_json::Value::String(text) => text,
+                    other => other.to_string(),
+                };
+                db.set_metadata(&key, &value)?;
+            }
+            println!("📝 已记录词典元数据: {}", metadata_path);
+        }
+        Err(_) => {
+            println!("⚠️  未找到 {}，跳过MDX来源信息", metadata_path);
+        }
+    }
+    
+    let redirects_path = "exported_dict_redirects.tsv";
+    if std::path::Path::new(redirects_path).exists() {
+        let redirect_count = db.import_redirects(redirects_path)?;
+        println!("🔗 已导入 {} 条重定向", redirect_count);
+    }
     
     let (total_count, unique_headwords) = db.get_stats()?;
     println!("🎉 数据导入完成！");
-    println!("📊 本次导入: {} 条词条", imported_count);
+    println!("📊 已导入: {} 条词条（含 --resume 之前已导入的）", imported_count);
     println!("📊 数据库总计: {} 条词条, {} 个唯一标题", total_count, unique_headwords);
     
     Ok(())
 }
 
-/// 启动Web服务器
-fn start_web_server() -> Result<(), Box<dyn std::error::Error>> {
+/// 写入内置演示数据，便于在没有MDX文件时运行服务器
+fn seed_demo_database(db_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    println!("🌱 写入演示数据: {}", db_path);
+
+    let db = ObunshaDictDatabase::new(db_path)?;
+    db.initialize()?;
+    let count = db.seed_demo()?;
+
+    let (total_count, unique_headwords) = db.get_stats()?;
+    println!("🎉 已写入 {} 条演示词条", count);
+    println!("📊 数据库总计: {} 条词条, {} 个唯一标题", total_count, unique_headwords);
+    println!("💡 运行 'cargo run server' 即可启动服务");
+
+    Ok(())
+}
+
+/// 合并dictionary.db（表現読解）与obunsha_dict.db（旺文社）导出为JSON Lines
+fn export_merged_dictionary(output_path: &str, compression: Compression) -> Result<(), Box<dyn std::error::Error>> {
+    for db_path in ["dictionary.db", "obunsha_dict.db"] {
+        if !std::path::Path::new(db_path).exists() {
+            println!("❌ 错误：数据库文件 {} 不存在", db_path);
+            return Ok(());
+        }
+    }
+
+    let hyougen = Database::new("dictionary.db")?;
+    let obunsha = ObunshaDictDatabase::open_readonly("obunsha_dict.db")?;
+    let count = dict::merged_export::export_merged(&hyougen, &obunsha, output_path, compression)?;
+    println!("🎉 合并词典已导出: {}（{} 条词条）", output_path, count);
+
+    Ok(())
+}
+
+/// 导出EPUB3词典
+fn export_epub_dictionary(output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let db_path = "obunsha_dict.db";
+    if !std::path::Path::new(db_path).exists() {
+        println!("❌ 错误：数据库文件 {} 不存在", db_path);
+        return Ok(());
+    }
+
+    let db = ObunshaDictDatabase::open_readonly(db_path)?;
+    let count = dict::epub_export::export_epub(&db, output_path)?;
+    println!("🎉 EPUB词典已导出: {}（{} 条词条）", output_path, count);
+
+    Ok(())
+}
+
+/// 比较两个数据库的词条差异
+fn diff_databases(old_db_path: &str, new_db_path: &str, write_report: bool) -> Result<(), Box<dyn std::error::Error>> {
+    println!("🔍 比较数据库: {} → {}", old_db_path, new_db_path);
+
+    for path in [old_db_path, new_db_path] {
+        if !std::path::Path::new(path).exists() {
+            println!("❌ 错误：数据库文件 {} 不存在", path);
+            std::process::exit(1);
+        }
+    }
+
+    let old_db = ObunshaDictDatabase::open_readonly(old_db_path)?;
+    let new_db = ObunshaDictDatabase::open_readonly(new_db_path)?;
+    let diff = old_db.diff_against(&new_db)?;
+
+    println!("📊 比较结果:");
+    println!("  - 新增: {}", diff.added.len());
+    println!("  - 删除: {}", diff.removed.len());
+    println!("  - 修改: {}", diff.modified.len());
+    println!("  - 未变化: {}", diff.unchanged);
+
+    for (label, entries) in [("➕", &diff.added), ("➖", &diff.removed), ("✏️ ", &diff.modified)] {
+        for entry in entries.iter().take(5) {
+            println!("  {} {} ({})", label, entry.headword, entry.data_id);
+        }
+    }
+
+    if write_report {
+        let report_path = "diff_report.json";
+        std::fs::write(report_path, serde_json::to_string_pretty(&diff)?)?;
+        println!("📝 详细报告已写入: {}", report_path);
+    }
+
+    Ok(())
+}
+
+/// 重新解析导入时失败的词条
+fn reprocess_parse_failures() -> Result<(), Box<dyn std::error::Error>> {
+    println!("🔁 重新解析隔离区中的词条...");
+
+    let db = ObunshaDictDatabase::new("obunsha_dict.db")?;
+    db.initialize()?;
+
+    let (promoted, remaining) = db.reprocess_failures()?;
+    println!("✅ {} 条词条已转入主表", promoted);
+    println!("📊 仍有 {} 条词条解析失败", remaining);
+
+    Ok(())
+}
+
+/// 从原始MDX内容重新生成所有词条的纯文本定义
+fn reparse_all_entries() -> Result<(), Box<dyn std::error::Error>> {
+    println!("🔁 重新解析所有词条的释义...");
+
+    let db = ObunshaDictDatabase::new("obunsha_dict.db")?;
+    db.initialize()?;
+
+    let updated = db.reparse_all()?;
+    println!("✅ 已更新 {} 条词条的纯文本定义", updated);
+
+    Ok(())
+}
+
+/// 检查重定向的目标是否存在，将无法到达词条的重定向写出为TSV（源\t目标）
+fn check_redirects(db_path: &str, output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    println!("🔗 检查重定向: {}", db_path);
+
+    if !std::path::Path::new(db_path).exists() {
+        println!("❌ 错误：数据库文件 {} 不存在", db_path);
+        return Ok(());
+    }
+
+    let db = ObunshaDictDatabase::open_readonly(db_path)?;
+    let orphans = db.find_orphan_redirects(MAX_REDIRECT_DEPTH)?;
+    if orphans.is_empty() {
+        println!("✅ 所有重定向都能到达词条");
+        return Ok(());
+    }
+
+    let content: String = orphans
+        .iter()
+        .map(|(source, target)| format!("{}\t{}\n", source, target))
+        .collect();
+    std::fs::write(output_path, content)?;
+
+    for (source, target) in orphans.iter().take(10) {
+        println!("  ⚠️  {} -> {}", source, target);
+    }
+    println!("⚠️  {} 条重定向无法到达词条，已写入 {}", orphans.len(), output_path);
+    Ok(())
+}
+
+/// 校验数据库完整性
+fn verify_database(db_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    println!("🔍 校验数据库: {}", db_path);
+
+    if !std::path::Path::new(db_path).exists() {
+        println!("❌ 错误：数据库文件 {} 不存在", db_path);
+        std::process::exit(1);
+    }
+
+    let db = ObunshaDictDatabase::new(db_path)?;
+    if db.integrity_check()? {
+        println!("🎉 校验通过：数据库可以正常使用");
+        Ok(())
+    } else {
+        println!("❌ 校验失败：数据库可能已损坏或导入不完整");
+        std::process::exit(1);
+    }
+}
+
+/// 压缩数据库并打印压缩前后的文件大小
+fn compact_database(db_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    println!("🧹 压缩数据库: {}", db_path);
+
+    if !std::path::Path::new(db_path).exists() {
+        println!("❌ 错误：数据库文件 {} 不存在", db_path);
+        return Ok(());
+    }
+
+    let before = std::fs::metadata(db_path)?.len();
+    let db = ObunshaDictDatabase::new(db_path)?;
+    db.compact()?;
+    drop(db);
+    let after = std::fs::metadata(db_path)?.len();
+
+    println!("📦 压缩前: {:.2} MB", before as f64 / 1024.0 / 1024.0);
+    println!("📦 压缩后: {:.2} MB", after as f64 / 1024.0 / 1024.0);
+    println!("✅ 压缩完成，回收 {:.2} MB", before.saturating_sub(after) as f64 / 1024.0 / 1024.0);
+    Ok(())
+}
+
+/// 重建数据库索引（REINDEX + 全文索引rebuild + ANALYZE）
+fn reindex_database(db_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    println!("🔧 重建索引: {}", db_path);
+
+    if !std::path::Path::new(db_path).exists() {
+        println!("❌ 错误：数据库文件 {} 不存在", db_path);
+        return Ok(());
+    }
+
+    let start = std::time::Instant::now();
+    let db = ObunshaDictDatabase::new(db_path)?;
+    db.rebuild_indexes()?;
+    println!("✅ 索引重建完成，用时 {:.1} 秒", start.elapsed().as_secs_f64());
+    Ok(())
+}
+
+/// 导出命令的 --order-by 参数（id/headword/sort_key/data_id），未指定时按插入顺序
+fn export_order(args: &[String]) -> Result<ExportOrder, String> {
+    match args.iter().position(|arg| arg == "--order-by").and_then(|index| args.get(index + 1)) {
+        Some(value) => value.parse(),
+        None => Ok(ExportOrder::Insertion),
+    }
+}
+
+/// server命令的 --download-timeout 参数（秒），未指定时为默认值
+fn download_timeout(value: Option<&str>) -> Result<std::time::Duration, String> {
+    match value {
+        Some(value) => value
+            .trim()
+            .parse::<u64>()
+            .ok()
+            .filter(|secs| *secs > 0)
+            .map(std::time::Duration::from_secs)
+            .ok_or_else(|| format!("--download-timeout 必须是正整数（秒）: {}", value)),
+        None => Ok(DEFAULT_DOWNLOAD_TIMEOUT),
+    }
+}
+
+/// analyze-data命令的 --boundary-fraction 参数，必须是0到1之间的小数
+fn boundary_fraction(value: Option<&str>) -> Result<Option<f64>, String> {
+    value
+        .map(|value| {
+            value
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|fraction| (0.0..=1.0).contains(fraction))
+                .ok_or_else(|| format!("--boundary-fraction 必须是0到1之间的小数: {}", value))
+        })
+        .transpose()
+}
+
+/// loadtest命令的参数，未指定的使用默认值；无法解析、为0或端口超出范围时返回错误
+fn load_test_options(args: &[String]) -> Result<LoadTestOptions, String> {
+    let option_value = |flag: &str| {
+        args.iter()
+            .position(|arg| arg == flag)
+            .and_then(|index| args.get(index + 1))
+            .map(|value| value.as_str())
+    };
+    let defaults = LoadTestOptions::default();
+    let port = match positive_flag("--port", option_value("--port"))? {
+        Some(port) => u16::try_from(port).map_err(|_| format!("--port 必须是1到65535之间的端口号: {}", port))?,
+        None => defaults.port,
+    };
+    Ok(LoadTestOptions {
+        requests: positive_flag("--requests", option_value("--requests"))?.unwrap_or(defaults.requests),
+        concurrency: positive_flag("--concurrency", option_value("--concurrency"))?.unwrap_or(defaults.concurrency),
+        port,
+        db_pool_size: positive_flag("--db-pool-size", option_value("--db-pool-size"))?,
+    })
+}
+
+/// 值为正整数的可选参数，未指定时为None，无法解析或为0时返回错误
+fn positive_flag(flag: &str, value: Option<&str>) -> Result<Option<usize>, String> {
+    value
+        .map(|value| {
+            value
+                .trim()
+                .parse::<usize>()
+                .ok()
+                .filter(|number| *number > 0)
+                .ok_or_else(|| format!("{} 必须是正整数: {}", flag, value))
+        })
+        .transpose()
+}
+
+/// 导出命令的 --compress 参数（gzip、zstd 或 zstd:<级别>），未指定时不压缩
+fn export_compression(args: &[String]) -> Result<Compression, String> {
+    match args.iter().position(|arg| arg == "--compress").and_then(|index| args.get(index + 1)) {
+        Some(value) => value.parse(),
+        None => Ok(Compression::None),
+    }
+}
+
+/// 在旺文社数据库和CSV文件之间导入/导出词条，导出时按order排列
+fn transfer_csv(import: bool, path: &str, delimiter: char, order: ExportOrder) -> Result<(), Box<dyn std::error::Error>> {
+    let db = ObunshaDictDatabase::new("obunsha_dict.db")?;
+    db.initialize()?;
+
+    if import {
+        if !std::path::Path::new(path).exists() {
+            println!("❌ 错误：CSV文件 {} 不存在", path);
+            return Ok(());
+        }
+        db.import_from_csv(path, delimiter)?;
+    } else {
+        db.export_to_csv(path, delimiter, order)?;
+    }
+    Ok(())
+}
+
+/// 导出旺文社词条为JSON Lines，指定since时只导出之后更新的词条
+fn export_json(path: &str, options: &JsonlExportOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let db_path = "obunsha_dict.db";
+    if !std::path::Path::new(db_path).exists() {
+        println!("❌ 错误：数据库文件 {} 不存在", db_path);
+        return Ok(());
+    }
+
+    let db = ObunshaDictDatabase::open_readonly(db_path)?;
+    db.export_to_jsonl(path, options)?;
+    Ok(())
+}
+
+/// 清空原始MDX内容并压缩数据库（有损，需要 --yes 确认）
+fn strip_raw_content(db_path: &str, confirmed: bool) -> Result<(), Box<dyn std::error::Error>> {
+    println!("✂️  清空原始MDX内容: {}", db_path);
+
+    if !std::path::Path::new(db_path).exists() {
+        println!("❌ 错误：数据库文件 {} 不存在", db_path);
+        return Ok(());
+    }
+
+    println!("⚠️  这是有损操作：清空后无法再用 reparse-all 重新解析，只能重新导入恢复");
+    if !confirmed {
+        println!("💡 确认后请加上 --yes 重新运行: strip-raw {} --yes", db_path);
+        return Ok(());
+    }
+
+    let before = std::fs::metadata(db_path)?.len();
+    let db = ObunshaDictDatabase::new(db_path)?;
+    let stripped = db.strip_raw()?;
+    println!("✅ 已清空 {} 条词条的原始内容，正在压缩...", stripped);
+    db.compact()?;
+    drop(db);
+    let after = std::fs::metadata(db_path)?.len();
+
+    println!("📦 处理前: {:.2} MB", before as f64 / 1024.0 / 1024.0);
+    println!("📦 处理后: {:.2} MB", after as f64 / 1024.0 / 1024.0);
+    Ok(())
+}
+
+/// 启动Web服务器，options中的词频表由 frequency_list 加载
+fn start_web_server(
+    frequency_list: Option<&str>,
+    db_url: Option<&str>,
+    db_sha256: Option<&str>,
+    download_timeout: std::time::Duration,
+    mut options: ServerOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
     println!("🌐 启动旺文社词典Web服务器...");
     
-    // 检查数据库文件是否存在
+    // 检查数据库文件是否存在，不存在且指定了 --db-url 时先下载
     let db_path = "obunsha_dict.db";
+    if !std::path::Path::new(db_path).exists()
+        && let Some(url) = db_url
+        && let Err(e) = download_file(url, db_path, db_sha256, download_timeout)
+    {
+        println!("❌ 数据库下载失败: {}", e);
+        return Err(e);
+    }
     if !std::path::Path::new(db_path).exists() {
         println!("❌ 错误：数据库文件 {} 不存在", db_path);
         println!("💡 请先运行 'cargo run import-obunsha' 创建数据库");
-        return Ok(());
+        return Err(format!("数据库文件 {} 不存在", db_path).into());
     }
     
-    // 验证数据库连接
-    match ObunshaDictDatabase::new(db_path) {
+    // 验证数据库连接（服务只读打开数据库）
+    match ObunshaDictDatabase::open_readonly(db_path) {
         Ok(db) => {
             let (count, _) = db.get_stats().unwrap_or((0, 0));
             if count == 0 {
-                println!("⚠️  警告：数据库为空，请先导入数据");
-                return Ok(());
+                println!("❌ 错误：数据库为空，请先导入数据");
+                return Err(format!("数据库 {} 中没有词条", db_path).into());
             }
             println!("📚 数据库连接成功，共有 {} 个词条", count);
         }
         Err(e) => {
             println!("❌ 数据库连接失败: {}", e);
-            return Ok(());
+            return Err(e.into());
         }
     }
     
+    options.frequencies = match frequency_list {
+        Some(path) => {
+            let frequencies = load_frequency_list(path)?;
+            println!("📈 已加载词频表 {}（{} 个词）", path, frequencies.len());
+            frequencies
+        }
+        None => std::collections::HashMap::new(),
+    };
+
+    if let Some(threshold) = options.slow_query_threshold_ms {
+        println!("🐢 慢查询阈值: {} ms", threshold);
+    }
+
     // 使用tokio运行时启动服务器
     let rt = tokio::runtime::Runtime::new()?;
     rt.block_on(async {
-        if let Err(e) = start_server(db_path, 3000).await {
+        let result = start_server(db_path, 3000, options).await;
+        if let Err(e) = &result {
             println!("❌ 服务器启动失败: {}", e);
         }
-    });
-    
-    Ok(())
+        result
+    })
 }
 
 /// 测试数据库结构
@@ -243,8 +888,11 @@ fn test_database_structure() -> Result<(), Box<dyn std::error::Error>> {
         kanji_form: Some("愛".to_string()),
         meaning: "❶かわいがりいつくしむ気持ち。❷こいしたう気持ち。❸たいせつに思う気持ち。".to_string(),
         pronunciation: Some("アイ".to_string()),
+        readings: Vec::new(),
         entry_type: "item_kiso".to_string(),
         raw_html: r#"<div class="item item_kiso"><div class="head"><span class="head_kana">あい</span><span class="head_hyo_1">【愛】</span></div></div>"#.to_string(),
+        jouyou_flags: Vec::new(),
+        kanji_writings: Vec::new(),
     };
     
     let entry_id = db.insert_entry(&test_entry)?;
@@ -275,4 +923,47 @@ fn test_database_structure() -> Result<(), Box<dyn std::error::Error>> {
     println!("💡 运行 'cargo run extract' 开始提取jpdict.txt数据");
     
     Ok(())
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_download_timeout() {
+        assert_eq!(download_timeout(None), Ok(DEFAULT_DOWNLOAD_TIMEOUT));
+        assert_eq!(download_timeout(Some(" 30 ")), Ok(std::time::Duration::from_secs(30)));
+        assert!(download_timeout(Some("0")).is_err());
+        assert!(download_timeout(Some("abc")).is_err());
+    }
+
+    #[test]
+    fn test_boundary_fraction() {
+        assert_eq!(boundary_fraction(None), Ok(None));
+        assert_eq!(boundary_fraction(Some("0.25")), Ok(Some(0.25)));
+        assert!(boundary_fraction(Some("1.5")).is_err());
+        assert!(boundary_fraction(Some("abc")).is_err());
+    }
+
+    #[test]
+    fn test_load_test_options() {
+        let args = |extra: &[&str]| {
+            ["dict", "loadtest"].iter().chain(extra).map(|arg| arg.to_string()).collect::<Vec<_>>()
+        };
+
+        let options = load_test_options(&args(&["--requests", "50", "--port", "3901"])).unwrap();
+        assert_eq!(options.requests, 50);
+        assert_eq!(options.port, 3901);
+        assert_eq!(options.concurrency, LoadTestOptions::default().concurrency);
+        assert!(load_test_options(&args(&["--concurrency", "abc"])).unwrap_err().contains("--concurrency"));
+        assert!(load_test_options(&args(&["--requests", "0"])).is_err());
+        assert!(load_test_options(&args(&["--port", "70000"])).is_err());
+    }
+
+    #[test]
+    fn test_positive_flag() {
+        assert_eq!(positive_flag("--db-pool-size", None), Ok(None));
+        assert_eq!(positive_flag("--db-pool-size", Some(" 8 ")), Ok(Some(8)));
+        assert!(positive_flag("--db-pool-size", Some("0")).is_err());
+        assert!(positive_flag("--db-pool-size", Some("abc")).unwrap_err().contains("--db-pool-size"));
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use serde::Serialize;
+
+use crate::compression::{CompressedWriter, Compression};
+use crate::database::Database;
+use crate::kana;
+use crate::obunsha_dict::ObunshaDictDatabase;
+
+/// 表現読解国語辞典的来源名
+const HYOUGEN_SOURCE: &str = "hyougen";
+/// 旺文社国語辞典的来源名
+const OBUNSHA_SOURCE: &str = "obunsha";
+
+/// 一个来源词典中的释义
+#[derive(Debug, Serialize, PartialEq)]
+pub struct MergedSource {
+    pub name: String,
+    pub definition: String,
+}
+
+/// 合并后的词条：同一假名+汉字的各词典释义归为一组
+#[derive(Debug, Serialize)]
+pub struct MergedEntry {
+    pub headword: String,
+    pub kana: String,
+    pub kanji: Option<String>,
+    pub sources: Vec<MergedSource>,
+}
+
+/// 合并键：规范化后的假名（片假名转平假名）和汉字表记
+fn merge_key(kana_text: &str, kanji: Option<&str>) -> (String, String) {
+    let kana_key = kana::normalize_headword(&kana::katakana_to_hiragana(kana_text.trim()));
+    let kanji_key = kanji.map(|kanji| kana::normalize_headword(kanji.trim())).unwrap_or_default();
+    (kana_key, kanji_key)
+}
+
+/// 按假名+汉字合并两个词典
+/// 旺文社词条使用原标题，只出现在表現読解国語辞典中的词条标题为"かな【漢字】"
+pub fn merge_dictionaries(
+    hyougen: &Database,
+    obunsha: &ObunshaDictDatabase,
+) -> Result<Vec<MergedEntry>, Box<dyn std::error::Error>> {
+    let mut merged: BTreeMap<(String, String), MergedEntry> = BTreeMap::new();
+
+    obunsha.for_each_entry(|entry| {
+        let kana_text = entry.kana_reading.clone().unwrap_or_else(|| entry.headword.clone());
+        let kanji = entry.kanji_writing.clone().filter(|kanji| !kanji.is_empty());
+        let key = merge_key(&kana_text, kanji.as_deref());
+        merged
+            .entry(key)
+            .or_insert_with(|| MergedEntry {
+                headword: entry.headword.clone(),
+                kana: kana_text,
+                kanji,
+                sources: Vec::new(),
+            })
+            .sources
+            .push(MergedSource {
+                name: OBUNSHA_SOURCE.to_string(),
+                definition: entry.definition_text,
+            });
+    })?;
+
+    for entry in hyougen.all_entries()? {
+        let kanji = entry.kanji_form.clone().filter(|kanji| !kanji.is_empty());
+        let key = merge_key(&entry.kana_entry, kanji.as_deref());
+        merged
+            .entry(key)
+            .or_insert_with(|| MergedEntry {
+                headword: match &kanji {
+                    Some(kanji) => format!("{}【{}】", entry.kana_entry, kanji),
+                    None => entry.kana_entry.clone(),
+                },
+                kana: entry.kana_entry.clone(),
+                kanji,
+                sources: Vec::new(),
+            })
+            .sources
+            .push(MergedSource {
+                name: HYOUGEN_SOURCE.to_string(),
+                definition: entry.meaning,
+            });
+    }
+
+    Ok(merged.into_values().collect())
+}
+
+/// 将两个词典合并导出为JSON Lines（每行一个MergedEntry），按compression压缩，返回导出的词条数
+pub fn export_merged(
+    hyougen: &Database,
+    obunsha: &ObunshaDictDatabase,
+    output_path: &str,
+    compression: Compression,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    println!("📚 开始合并导出: {}", output_path);
+
+    let entries = merge_dictionaries(hyougen, obunsha)?;
+    let mut writer = CompressedWriter::create(output_path, compression)?;
+    for entry in &entries {
+        serde_json::to_writer(&mut writer, entry)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.finish()?;
+
+    let both = entries.iter().filter(|entry| entry.sources.len() > 1).count();
+    println!("📊 共 {} 条，其中 {} 条同时出现在两个词典中", entries.len(), both);
+    Ok(entries.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::DictionaryEntry;
+    use crate::obunsha_dict::ObunshaDictEntry;
+
+    #[test]
+    fn test_merge_dictionaries() {
+        let hyougen = Database::new(":memory:").unwrap();
+        hyougen.initialize().unwrap();
+        let obunsha = ObunshaDictDatabase::new(":memory:").unwrap();
+        obunsha.initialize().unwrap();
+
+        for (kana_entry, kanji_form) in [("アイ", Some("愛")), ("こい", None)] {
+            hyougen.insert_entry(&DictionaryEntry {
+                id: None,
+                kana_entry: kana_entry.to_string(),
+                kanji_form: kanji_form.map(str::to_string),
+                meaning: format!("{}の意味", kana_entry),
+                pronunciation: None,
+                readings: Vec::new(),
+                entry_type: "item_ippan".to_string(),
+                raw_html: String::new(),
+                jouyou_flags: Vec::new(),
+                kanji_writings: Vec::new(),
+            }).unwrap();
+        }
+        for (data_id, kana_reading, kanji_writing) in [("1", "あい", "愛"), ("2", "あお", "青")] {
+            obunsha.insert_entry(&ObunshaDictEntry {
+                id: None,
+                data_id: data_id.to_string(),
+                data_type: "1".to_string(),
+                headword: format!("{}【{}】", kana_reading, kanji_writing),
+                kana_reading: Some(kana_reading.to_string()),
+                kanji_writing: Some(kanji_writing.to_string()),
+                kanji_variants: Vec::new(),
+                expansion: None,
+                part_of_speech: None,
+                conjugation: None,
+                definition_html: String::new(),
+                definition_text: format!("{}の釈義", kana_reading),
+                raw_mdx_content: String::new(),
+            }).unwrap();
+        }
+
+        let merged = merge_dictionaries(&hyougen, &obunsha).unwrap();
+        let headwords: Vec<&str> = merged.iter().map(|entry| entry.headword.as_str()).collect();
+        assert_eq!(headwords, vec!["あい【愛】", "あお【青】", "こい"]);
+
+        // 片假名读音与平假名读音合并为同一词条
+        let names: Vec<&str> = merged[0].sources.iter().map(|source| source.name.as_str()).collect();
+        assert_eq!(names, vec!["obunsha", "hyougen"]);
+        assert_eq!(merged[1].sources.len(), 1);
+        assert_eq!(merged[2].sources[0].name, "hyougen");
+    }
+}
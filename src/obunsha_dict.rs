@@ -1,49 +1,541 @@
-use rusqlite::{Connection, Result, params};
-use serde::{Deserialize, Serialize};
-use scraper::Html;
-
-/// 旺文社国語辞典词条结构 (Obunsha Kokugo Dictionary Entry)
-/// 基于MDX格式的专业日语词典数据
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ObunshaDictEntry {
-    pub id: Option<i64>,
-    /// 词条ID - 来源于MDX的data-id
+use rusqlite::{Connection, OpenFlags, OptionalExtension, Result, params};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::cell::Cell;
+use std::collections::BTreeMap;
+
+use crate::compression::{CompressedWriter, Compression};
+use crate::csv_format;
+use crate::data_cleaner::strip_invisible;
+use crate::jitai;
+use crate::kana;
+use crate::obunsha_parser::{
+    count_examples, expansion_from_html, extract_english_glosses, is_pure_redirect_text, split_kanji_variants, ObunshaParser, Sense,
+    PURE_REDIRECT_MAX_CHARS,
+};
+pub use crate::obunsha_parser::ObunshaDictEntry;
+
+/// 带相关度得分的词条
+/// 用于释义搜索等需要排序的查询，得分越大越相关
+#[derive(Debug, Clone, Serialize)]
+pub struct RankedEntry {
+    #[serde(flatten)]
+    pub entry: ObunshaDictEntry,
+    /// 相关度得分 - 不排序的查询为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rank: Option<f64>,
+    /// Markdown格式的释义 - 仅在请求format=markdown时填充
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub definition_markdown: Option<String>,
+    /// 义项树 - 仅在请求structured=tree时填充
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub senses: Option<Vec<Sense>>,
+}
+
+impl RankedEntry {
+    /// 创建带得分的词条
+    pub fn with_rank(entry: ObunshaDictEntry, rank: f64) -> Self {
+        RankedEntry { entry, rank: Some(rank), definition_markdown: None, senses: None }
+    }
+}
+
+impl From<ObunshaDictEntry> for RankedEntry {
+    fn from(entry: ObunshaDictEntry) -> Self {
+        RankedEntry { entry, rank: None, definition_markdown: None, senses: None }
+    }
+}
+
+/// 数据库结构版本（写入PRAGMA user_version），表结构变化时递增
+pub const SCHEMA_VERSION: i64 = 8;
+
+/// 跟随重定向链的最大深度（Web搜索和孤立重定向检查共用）
+pub const MAX_REDIRECT_DEPTH: usize = 5;
+
+/// suggestions_for返回的候选数上限
+const DID_YOU_MEAN_LIMIT: usize = 5;
+
+/// 导入时纯文本定义中各义项之间的分隔符（保留义项边界，definition_html不受影响）
+const STORED_SENSE_SEPARATOR: &str = "\n";
+
+/// 释义全文搜索先按bm25取limit的多少倍，乘以第一义项加权后再排序截断
+const DEFINITION_RERANK_FACTOR: usize = 5;
+
+/// 单条SQL语句中IN列表的最大参数数（SQLite旧版本的参数上限为999）
+const MAX_SQL_PARAMS: usize = 999;
+
+/// 内置演示数据（清理后的数据格式）
+const DEMO_ENTRIES: &str = include_str!("../fixtures/demo_entries.txt");
+/// 内置演示重定向（源\t目标）
+const DEMO_REDIRECTS: &str = include_str!("../fixtures/demo_redirects.tsv");
+
+/// 按读音顺序排列的某个词条及其前后词条（翻页浏览用）
+#[derive(Debug, Clone, Serialize)]
+pub struct EntryWindow {
+    /// 目标之前的词条（按读音顺序，最近的在最后）
+    pub before: Vec<ObunshaDictEntry>,
+    /// 读音与查询完全一致的词条，没有时为空（before/after为插入位置前后的词条）
+    pub target: Option<ObunshaDictEntry>,
+    /// 目标之后的词条
+    pub after: Vec<ObunshaDictEntry>,
+}
+
+/// 统计存储占用的文本列
+const STORAGE_COLUMNS: [&str; 10] = [
+    "headword", "kana_reading", "kanji_writing", "part_of_speech", "conjugation",
+    "definition_html", "definition_text", "raw_mdx_content", "english_gloss", "expansion",
+];
+
+/// 单个列的存储占用
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ColumnStorage {
+    pub column: String,
+    /// 该列所有值的UTF-8字节数之和（不含SQLite的页和索引开销）
+    pub bytes: i64,
+}
+
+/// 数据库存储占用统计
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageStats {
+    /// 各文本列的占用，按字节数从大到小排列
+    pub columns: Vec<ColumnStorage>,
+    /// 数据库文件大小（page_count × page_size），包含索引和全文索引
+    pub database_bytes: i64,
+}
+
+/// 遍历和导出词条的顺序（iter_entries及基于它的export_to_csv / export_to_jsonl，只允许这几种）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExportOrder {
+    /// 插入顺序（按id，最快）
+    #[default]
+    Insertion,
+    /// 按标题
+    Headword,
+    /// 按五十音顺序（见kana::sort_key）
+    SortKey,
+    /// 按data_id
+    DataId,
+}
+
+impl ExportOrder {
+    /// 排序键的SQL表达式，相同时再按id排列保证结果稳定；键不为NULL，才能用于按键分页
+    fn sort_column(self) -> &'static str {
+        match self {
+            ExportOrder::Insertion => "id",
+            ExportOrder::Headword => "headword",
+            ExportOrder::SortKey => "IFNULL(sort_key, '')",
+            ExportOrder::DataId => "data_id",
+        }
+    }
+}
+
+impl std::str::FromStr for ExportOrder {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value {
+            "id" | "insertion" => Ok(ExportOrder::Insertion),
+            "headword" => Ok(ExportOrder::Headword),
+            "sort_key" => Ok(ExportOrder::SortKey),
+            "data_id" => Ok(ExportOrder::DataId),
+            _ => Err(format!("不支持的排序方式 {:?}，支持: id, headword, sort_key, data_id", value)),
+        }
+    }
+}
+
+/// export_to_jsonl的参数
+#[derive(Debug, Clone, Default)]
+pub struct JsonlExportOptions {
+    /// 只导出updated_at晚于该时间（ISO-8601时间戳）的词条，用于发布增量数据
+    pub since: Option<String>,
+    /// 导出顺序
+    pub order: ExportOrder,
+    /// 输出文件的压缩方式
+    pub compression: Compression,
+}
+
+/// 逐级回退搜索（search_best）的一级
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchTier {
+    /// 假名精确匹配（search_by_kana_exact）
+    Kana,
+    /// 汉字智能匹配（search_by_kanji_smart）
+    Kanji,
+    /// 规范化标题匹配（find_by_headword）
+    Headword,
+}
+
+impl SearchTier {
+    pub const ALL: [SearchTier; 3] = [SearchTier::Kana, SearchTier::Kanji, SearchTier::Headword];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SearchTier::Kana => "kana",
+            SearchTier::Kanji => "kanji",
+            SearchTier::Headword => "headword",
+        }
+    }
+}
+
+impl std::str::FromStr for SearchTier {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|tier| tier.as_str() == value)
+            .ok_or_else(|| format!("未知的回退搜索方式 {:?}，支持: kana, kanji, headword", value))
+    }
+}
+
+/// search_anything 在没有检索形式表时的默认回退顺序
+pub const DEFAULT_SEARCH_CHAIN: [SearchTier; 3] = SearchTier::ALL;
+
+/// CSV导出的列（导入时表头必须是这些列的子集，且包含data_id和headword）
+pub const CSV_COLUMNS: [&str; 11] = [
+    "data_id", "data_type", "headword", "kana_reading", "kanji_writing", "part_of_speech",
+    "conjugation", "expansion", "definition_html", "definition_text", "raw_mdx_content",
+];
+
+/// 紧凑格式（compact_entries）每行的列，依次对应CompactEntry的各项
+pub const COMPACT_COLUMNS: [&str; 6] = ["data_id", "headword", "kana_reading", "kanji_writing", "part_of_speech", "definition_text"];
+
+/// 紧凑格式的词条：(data_id, headword, kana_reading, kanji_writing, part_of_speech, definition_text)
+/// 序列化为JSON数组，省去每个词条重复的字段名，用于整库传输
+pub type CompactEntry = (String, String, Option<String>, Option<String>, Option<String>, String);
+
+/// 导入进度文件的后缀（与数据文件同目录，如 exported_dict_cleaned.txt.offset）
+const IMPORT_OFFSET_SUFFIX: &str = ".offset";
+
+/// 导入进度文件的内容，一行制表符分隔的字段
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ImportProgress {
+    /// 下一批词条在文件中的起始字节位置
+    offset: u64,
+    /// 已导入的词条数
+    entries: usize,
+    /// 已读取的行数，继续导入时报告的行号由此接续
+    lines: usize,
+    /// 数据文件的字节数
+    file_len: u64,
+    /// 数据文件的修改时间（UNIX纪元以来的纳秒），与file_len一起判断文件是否被更换
+    modified: u128,
+}
+
+impl ImportProgress {
+    fn to_line(self) -> String {
+        format!("{}\t{}\t{}\t{}\t{}\n", self.offset, self.entries, self.lines, self.file_len, self.modified)
+    }
+
+    fn parse(content: &str) -> Option<Self> {
+        let mut fields = content.trim().split('\t');
+        let progress = ImportProgress {
+            offset: fields.next()?.parse().ok()?,
+            entries: fields.next()?.parse().ok()?,
+            lines: fields.next()?.parse().ok()?,
+            file_len: fields.next()?.parse().ok()?,
+            modified: fields.next()?.parse().ok()?,
+        };
+        fields.next().is_none().then_some(progress)
+    }
+}
+
+/// 数据文件的（字节数, 修改时间纳秒），用于确认继续导入的是同一个文件
+fn file_fingerprint(metadata: &std::fs::Metadata) -> std::io::Result<(u64, u128)> {
+    let modified = metadata
+        .modified()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or(0);
+    Ok((metadata.len(), modified))
+}
+
+/// CSV导入时表头必须包含的列
+const CSV_REQUIRED_COLUMNS: [&str; 2] = ["data_id", "headword"];
+
+/// 两个数据库之间差异中的一个词条
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct DiffEntry {
     pub data_id: String,
-    /// 词条类型 - 来源于MDX的data-type  
-    pub data_type: String,
-    /// 词条标题 - 从MDX关键词提取的标题
     pub headword: String,
-    /// 假名读音 - 提取的假名部分
-    pub kana_reading: Option<String>,
-    /// 汉字表记 - 提取的汉字部分
-    pub kanji_writing: Option<String>,
-    /// 词性信息 - 如"自五"等语法信息
-    pub part_of_speech: Option<String>,
-    /// 活用形 - 动词、形容词的变化形式
-    pub conjugation: Option<String>,
-    /// 词条定义 - 完整的HTML定义内容
-    pub definition_html: String,
-    /// 纯文本定义 - 去除HTML标签的纯文本版本
-    pub definition_text: String,
-    /// 原始MDX内容 - 保留完整的原始数据
-    pub raw_mdx_content: String,
+}
+
+/// 两个数据库按data_id比较的结果
+#[derive(Debug, Default, Serialize)]
+pub struct DatabaseDiff {
+    /// 新数据库中新增的词条
+    pub added: Vec<DiffEntry>,
+    /// 新数据库中删除的词条
+    pub removed: Vec<DiffEntry>,
+    /// definition_html发生变化的词条（headword为新数据库中的标题）
+    pub modified: Vec<DiffEntry>,
+    /// 两边完全相同的词条数
+    pub unchanged: usize,
+}
+
+/// iter_entries每次从数据库读取的词条数
+const ITER_PAGE_SIZE: usize = 500;
+
+/// 全部词条的惰性迭代器
+/// 按（排序键, id）分页读取（WHERE (键, id) > 上一页最后的(键, id)），内存中最多保留一页词条
+pub struct EntryIter<'a> {
+    db: &'a ObunshaDictDatabase,
+    order: ExportOrder,
+    /// 只遍历updated_at晚于该时间（SQLite datetime格式）的词条
+    updated_after: Option<String>,
+    buffer: std::collections::VecDeque<ObunshaDictEntry>,
+    /// 上一页最后一个词条的排序键和id，读取第一页前为None
+    last_key: Option<(rusqlite::types::Value, i64)>,
+    finished: bool,
+}
+
+impl Iterator for EntryIter<'_> {
+    type Item = Result<ObunshaDictEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buffer.is_empty()
+            && !self.finished
+            && let Err(e) = self.fetch_page()
+        {
+            self.finished = true;
+            return Some(Err(e));
+        }
+        self.buffer.pop_front().map(Ok)
+    }
+}
+
+impl EntryIter<'_> {
+    /// 读取下一页词条
+    fn fetch_page(&mut self) -> Result<()> {
+        let column = self.order.sort_column();
+        // 按插入顺序时只比较id，可以直接按rowid定位
+        let after = match self.order {
+            ExportOrder::Insertion => "id > ?3".to_string(),
+            _ => format!("?2 IS NULL OR ({}, id) > (?2, ?3)", column),
+        };
+        let mut stmt = self.db.conn.prepare_cached(&format!(
+            r#"
+            SELECT *, {column} AS iter_key FROM obunsha_kokugo_dict
+            WHERE (?1 IS NULL OR updated_at > ?1) AND ({after})
+            ORDER BY {column}, id
+            LIMIT ?4
+            "#,
+        ))?;
+        let (last_key, last_id) = self.last_key.clone().unwrap_or((rusqlite::types::Value::Null, 0));
+        let entry_iter = stmt.query_map(
+            params![self.updated_after, last_key, last_id, ITER_PAGE_SIZE as i64],
+            |row| Ok((ObunshaDictDatabase::entry_from_row(row)?, row.get::<_, rusqlite::types::Value>("iter_key")?)),
+        )?;
+        for entry in entry_iter {
+            let (entry, key) = entry?;
+            self.last_key = Some((key, entry.id.unwrap_or(last_id)));
+            self.buffer.push_back(entry);
+        }
+        if self.buffer.len() < ITER_PAGE_SIZE {
+            self.finished = true;
+        }
+        Ok(())
+    }
+}
+
+/// 布尔查询：按OR分为若干组，组内各查询词之间为AND
+/// "あつい OR あつ" → [["あつい"], ["あつ"]]；"熱 AND 湯" 与 "熱 湯" 等价 → [["熱", "湯"]]
+#[derive(Debug, Clone, PartialEq)]
+pub struct BooleanQuery {
+    pub groups: Vec<Vec<String>>,
+}
+
+impl BooleanQuery {
+    /// 解析查询字符串，查询词超过max_terms个或运算符缺少操作数时返回错误信息
+    pub fn parse(query: &str, max_terms: usize) -> std::result::Result<Self, String> {
+        let mut groups: Vec<Vec<String>> = vec![Vec::new()];
+        let mut expecting_term = true;
+        let mut term_count = 0;
+
+        for token in query.split_whitespace() {
+            match token {
+                "OR" | "AND" => {
+                    if expecting_term {
+                        return Err(format!("运算符 {} 前缺少查询词", token));
+                    }
+                    if token == "OR" {
+                        groups.push(Vec::new());
+                    }
+                    expecting_term = true;
+                }
+                term => {
+                    term_count += 1;
+                    if term_count > max_terms {
+                        return Err(format!("查询词过多（最多 {} 个）", max_terms));
+                    }
+                    if let Some(group) = groups.last_mut() {
+                        group.push(term.to_string());
+                    }
+                    expecting_term = false;
+                }
+            }
+        }
+
+        if term_count == 0 {
+            return Err("查询词不能为空".to_string());
+        }
+        if expecting_term {
+            return Err("运算符后缺少查询词".to_string());
+        }
+
+        Ok(BooleanQuery { groups })
+    }
 }
 
 /// 旺文社国語辞典数据库管理
 pub struct ObunshaDictDatabase {
     conn: Connection,
+    /// 是否以只读模式打开（Web服务使用）
+    read_only: bool,
+    /// 严格模式：插入时拒绝假名读音异常的词条；否则只对假名读音异常记录警告
+    strict_kana: bool,
+    /// 插入时发现的假名读音异常数
+    kana_violations: Cell<usize>,
+    /// 严格模式下发现的读音以外的校验问题数（只警告，不拒绝）
+    content_warnings: Cell<usize>,
+    /// SQLite是否支持FTS5（首次检查后缓存），不支持时释义搜索退回LIKE扫描
+    fts5_supported: Cell<Option<bool>>,
 }
 
 impl ObunshaDictDatabase {
     /// 创建新的数据库连接
     pub fn new(db_path: &str) -> Result<Self> {
         let conn = Connection::open(db_path)?;
-        Ok(ObunshaDictDatabase { conn })
+        Ok(ObunshaDictDatabase {
+            conn,
+            read_only: false,
+            strict_kana: false,
+            kana_violations: Cell::new(0),
+            content_warnings: Cell::new(0),
+            fts5_supported: Cell::new(None),
+        })
+    }
+
+    /// 以只读模式打开数据库
+    /// 所有写入方法都会返回SQLITE_READONLY错误，防止服务进程意外修改数据库
+    pub fn open_readonly(db_path: &str) -> Result<Self> {
+        let conn = Connection::open_with_flags(
+            db_path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_URI | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        Ok(ObunshaDictDatabase {
+            conn,
+            read_only: true,
+            strict_kana: false,
+            kana_violations: Cell::new(0),
+            content_warnings: Cell::new(0),
+            fts5_supported: Cell::new(None),
+        })
+    }
+
+    /// 以读写模式打开已初始化的数据库，文件不存在时返回错误
+    /// 不执行initialize中的迁移，供服务进程处理写入请求（迁移已在启动服务前完成）
+    pub fn open_for_write(db_path: &str) -> Result<Self> {
+        let conn = Connection::open_with_flags(
+            db_path,
+            OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_URI | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        Ok(ObunshaDictDatabase {
+            conn,
+            read_only: false,
+            strict_kana: false,
+            kana_violations: Cell::new(0),
+            content_warnings: Cell::new(0),
+            fts5_supported: Cell::new(None),
+        })
+    }
+
+    /// 设置插入时的校验模式（默认宽松）
+    /// 严格模式下读音含非假名字符的词条会被拒绝，批量插入时写入隔离区；validate的其他问题（字段为空、HTML标签不配对）只打印警告；
+    /// 宽松模式只对读音含非假名字符的词条打印警告并计数
+    pub fn set_strict_kana(&mut self, strict: bool) {
+        self.strict_kana = strict;
+    }
+
+    /// 插入时发现的假名读音异常数（宽松模式下这些词条仍会写入）
+    pub fn kana_violation_count(&self) -> usize {
+        self.kana_violations.get()
+    }
+
+    /// 校验待插入的词条，返回严格模式下拒绝写入的原因，宽松模式总是返回None
+    /// 严格模式只因假名读音拒绝词条，validate的其他问题只打印警告；宽松模式只检查假名读音
+    fn check_entry(&self, entry: &ObunshaDictEntry) -> Option<String> {
+        const MAX_WARNINGS: usize = 20;
+
+        if self.strict_kana {
+            if let Some(problem) = entry.kana_reading_problem() {
+                return Some(format!("严格模式校验未通过: {}", problem));
+            }
+            let problems = entry.content_problems();
+            if !problems.is_empty() {
+                let count = self.content_warnings.get() + 1;
+                self.content_warnings.set(count);
+                if count <= MAX_WARNINGS {
+                    eprintln!("⚠️  词条 {} ({}) 未通过校验: {}", entry.data_id, entry.headword, problems.join("; "));
+                }
+            }
+            return None;
+        }
+
+        let kana = entry.kana_reading.as_deref()?;
+        if kana::is_valid_kana_reading(kana) {
+            return None;
+        }
+
+        let message = format!("词条 {} ({}) 的假名读音含非假名字符: {:?}", entry.data_id, entry.headword, kana);
+        let count = self.kana_violations.get() + 1;
+        self.kana_violations.set(count);
+        if count <= MAX_WARNINGS {
+            eprintln!("⚠️  {}", message);
+        }
+        None
+    }
+
+    /// 严格模式拒绝写入时返回的错误（SQLITE_CONSTRAINT_CHECK，与违反CHECK约束相同）
+    fn rejected_entry_error(entry: &ObunshaDictEntry, reason: &str) -> rusqlite::Error {
+        rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CONSTRAINT_CHECK),
+            Some(format!("词条 {} ({}) {}", entry.data_id, entry.headword, reason)),
+        )
+    }
+
+    /// 检查当前SQLite是否支持FTS5：尝试创建一个临时FTS5表，失败说明编译时未包含FTS5
+    pub fn fts5_available(&self) -> bool {
+        if let Some(supported) = self.fts5_supported.get() {
+            return supported;
+        }
+        let supported = self
+            .conn
+            .execute_batch("CREATE VIRTUAL TABLE temp.fts5_probe USING fts5(x); DROP TABLE temp.fts5_probe;")
+            .is_ok();
+        self.fts5_supported.set(Some(supported));
+        supported
+    }
+
+    /// 使用已知的FTS5探测结果，跳过fts5_available中的探测（服务进程启动时探测一次，每个连接共用）
+    pub fn set_fts5_available(&self, supported: bool) {
+        self.fts5_supported.set(Some(supported));
+    }
+
+    /// 写入前检查连接是否可写
+    fn ensure_writable(&self) -> Result<()> {
+        if self.read_only {
+            return Err(rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_READONLY),
+                Some("数据库以只读模式打开，不允许写入".to_string()),
+            ));
+        }
+        Ok(())
     }
 
     /// 初始化旺文社国語辞典表
     /// 表名: obunsha_kokugo_dict (旺文社国語辞典)
     pub fn initialize(&self) -> Result<()> {
+        self.ensure_writable()?;
         self.conn.execute(
             r#"
             CREATE TABLE IF NOT EXISTS obunsha_kokugo_dict (
@@ -51,6 +543,11 @@ impl ObunshaDictDatabase {
                 data_id TEXT NOT NULL UNIQUE,               -- MDX词条ID
                 data_type TEXT NOT NULL,                    -- MDX词条类型
                 headword TEXT NOT NULL,                     -- 词条标题
+                headword_normalized TEXT,                   -- NFKC规范化后的标题（用于检索）
+                english_gloss TEXT,                         -- 释义括号中的英文对应词（|分隔，小写）
+                expansion TEXT,                             -- 英文缩写的全称
+                example_count INTEGER,                      -- 释义中的例句数
+                sort_key TEXT,                              -- 五十音顺序排序键（见kana::sort_key）
                 kana_reading TEXT,                          -- 假名读音
                 kanji_writing TEXT,                         -- 汉字表记
                 part_of_speech TEXT,                        -- 词性信息
@@ -71,508 +568,3741 @@ impl ObunshaDictDatabase {
             [],
         )?;
 
+        // 旧数据库补充规范化标题列，索引须在列存在后创建
+        self.ensure_headword_normalized()?;
+        self.conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_headword_normalized ON obunsha_kokugo_dict(headword_normalized)",
+            [],
+        )?;
+
+        // 旧数据库补充英文对应词列和缩写全称列（全称在版本8的迁移中回填）
+        self.ensure_english_gloss()?;
+        self.ensure_column("expansion", "TEXT")?;
+
+        // 旧数据库补充例句数列并回填
+        self.ensure_example_count()?;
+
+        // 旧数据库补充五十音排序键并回填
+        self.ensure_sort_key()?;
+        self.conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_sort_key ON obunsha_kokugo_dict(sort_key)",
+            [],
+        )?;
+
         self.conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_kana_reading ON obunsha_kokugo_dict(kana_reading)",
             [],
         )?;
 
+        self.conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_kanji_writing ON obunsha_kokugo_dict(kanji_writing)",
+            [],
+        )?;
+
         self.conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_data_id ON obunsha_kokugo_dict(data_id)",
             [],
         )?;
 
-        println!("✅ 旺文社国語辞典表已初始化");
-        Ok(())
-    }
+        self.conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_updated_at ON obunsha_kokugo_dict(updated_at)",
+            [],
+        )?;
 
-    /// 插入单个词条
-    pub fn insert_entry(&self, entry: &ObunshaDictEntry) -> Result<i64> {
-        let mut stmt = self.conn.prepare(
+        self.conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_created_at ON obunsha_kokugo_dict(created_at)",
+            [],
+        )?;
+
+        // 释义全文索引（外部内容表，导入后通过rebuild同步）
+        // 使用trigram分词器，日语文本无需分词即可进行子串匹配
+        // SQLite不支持FTS5时不创建，释义搜索退回LIKE扫描
+        if self.fts5_available() {
+            self.conn.execute(
+                r#"
+                CREATE VIRTUAL TABLE IF NOT EXISTS obunsha_definitions_fts USING fts5(
+                    definition_text,
+                    content='obunsha_kokugo_dict',
+                    content_rowid='id',
+                    tokenize='trigram'
+                )
+                "#,
+                [],
+            )?;
+        } else {
+            println!("⚠️  当前SQLite不支持FTS5，不创建释义全文索引，释义搜索将使用较慢的LIKE扫描");
+        }
+
+        // 检索形式表：标题、读音、各汉字表记的规范化形式，exact搜索一次查询即可命中任一形式
+        self.conn.execute(
             r#"
-            INSERT INTO obunsha_kokugo_dict (
-                data_id, data_type, headword, kana_reading, kanji_writing,
-                part_of_speech, conjugation, definition_html, definition_text, raw_mdx_content
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+            CREATE TABLE IF NOT EXISTS search_all (
+                form TEXT NOT NULL,                         -- 规范化检索形式（NFKC、小写、平假名）
+                entry_id INTEGER NOT NULL,                  -- 对应词条id
+                PRIMARY KEY (form, entry_id)
+            ) WITHOUT ROWID
             "#,
+            [],
+        )?;
+        self.conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_search_all_entry ON search_all(entry_id)",
+            [],
         )?;
 
-        let row_id = stmt.insert(params![
-            entry.data_id,
-            entry.data_type,
-            entry.headword,
-            entry.kana_reading,
-            entry.kanji_writing,
-            entry.part_of_speech,
-            entry.conjugation,
-            entry.definition_html,
-            entry.definition_text,
-            entry.raw_mdx_content,
-        ])?;
+        // 词典元数据表（来源MDX文件、版本等）
+        self.conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS dict_metadata (
+                key TEXT PRIMARY KEY,                       -- 元数据键
+                value TEXT NOT NULL,                        -- 元数据值
+                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+            [],
+        )?;
 
-        Ok(row_id)
-    }
+        // 重定向表（MDX中 @@@LINK= 形式的跳转）
+        self.conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS redirects (
+                source TEXT PRIMARY KEY,                    -- 重定向源（MDX关键词）
+                target TEXT NOT NULL                        -- 目标词条标题
+            )
+            "#,
+            [],
+        )?;
 
-    /// 批量插入词条
-    pub fn insert_entries_batch(&self, entries: &[ObunshaDictEntry]) -> Result<usize> {
-        let tx = self.conn.unchecked_transaction()?;
-        
-        {
-            let mut stmt = tx.prepare(
-                r#"
-                INSERT OR REPLACE INTO obunsha_kokugo_dict (
-                    data_id, data_type, headword, kana_reading, kanji_writing,
-                    part_of_speech, conjugation, definition_html, definition_text, raw_mdx_content
-                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
-                "#,
-            )?;
+        // 解析失败的词条（隔离区），改进解析器后可用reprocess_failures重新处理
+        self.conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS parse_failures (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                title TEXT NOT NULL,                        -- 词条标题行
+                raw_html TEXT NOT NULL,                     -- 原始HTML行
+                reason TEXT NOT NULL,                       -- 失败原因
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                UNIQUE(title, raw_html)
+            )
+            "#,
+            [],
+        )?;
 
-            for entry in entries {
-                stmt.execute(params![
-                    entry.data_id,
-                    entry.data_type,
-                    entry.headword,
-                    entry.kana_reading,
-                    entry.kanji_writing,
-                    entry.part_of_speech,
-                    entry.conjugation,
-                    entry.definition_html,
-                    entry.definition_text,
-                    entry.raw_mdx_content,
-                ])?;
+        // 版本2：清理旧数据中的零宽字符和控制字符，释义有变化，需要重建全文索引
+        if self.schema_version()? < 2 {
+            let cleaned = self.strip_invisible_from_rows()?;
+            if cleaned > 0 {
+                self.rebuild_definition_index()?;
+                println!("✅ 已清理 {} 条词条中的不可见字符", cleaned);
             }
         }
 
-        tx.commit()?;
-        println!("✅ 成功插入 {} 条词条", entries.len());
-        Ok(entries.len())
-    }
+        // 版本5：统一早期导入的汉字表记中的中点和空白
+        if self.schema_version()? < 5 {
+            let normalized = self.normalize_kanji_rows()?;
+            if normalized > 0 {
+                println!("✅ 已规范化 {} 条词条的汉字表记", normalized);
+            }
+        }
 
-    /// 根据标题查询词条（模糊匹配，保留原有功能）
-    pub fn search_by_headword(&self, headword: &str) -> Result<Vec<ObunshaDictEntry>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT * FROM obunsha_kokugo_dict WHERE headword LIKE ?1 ORDER BY headword"
-        )?;
-
-        let entry_iter = stmt.query_map([format!("%{}%", headword)], |row| {
-            Ok(ObunshaDictEntry {
-                id: Some(row.get(0)?),
-                data_id: row.get(1)?,
-                data_type: row.get(2)?,
-                headword: row.get(3)?,
-                kana_reading: row.get(4)?,
-                kanji_writing: row.get(5)?,
-                part_of_speech: row.get(6)?,
-                conjugation: row.get(7)?,
-                definition_html: row.get(8)?,
-                definition_text: row.get(9)?,
-                raw_mdx_content: row.get(10)?,
-            })
-        })?;
+        // 版本6：为已有词条填充检索形式表（版本2、5的迁移也会修改这些字段，须在其后执行）
+        if self.schema_version()? < 6 {
+            let indexed = self.rebuild_search_all()?;
+            if indexed > 0 {
+                println!("✅ 已为 {} 条词条建立检索形式", indexed);
+            }
+        }
 
-        let mut entries = Vec::new();
-        for entry in entry_iter {
-            entries.push(entry?);
+        // 版本7：去掉早期导入的读音中表示活用分界的中点（あが・く → あがく），读音变化后重建检索形式
+        if self.schema_version()? < 7 {
+            let stripped = self.strip_reading_dots_from_rows()?;
+            if stripped > 0 {
+                self.rebuild_search_all()?;
+                println!("✅ 已去掉 {} 条词条读音中的中点", stripped);
+            }
         }
 
-        Ok(entries)
+        // 版本8：为添加expansion列之前导入的缩写词条回填全称（非缩写词条的全称本就为NULL，只回填一次）
+        if self.schema_version()? < 8 {
+            let filled = self.backfill_expansion()?;
+            if filled > 0 {
+                println!("✅ 已回填 {} 条缩写全称", filled);
+            }
+        }
+
+        self.conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+
+        println!("✅ 旺文社国語辞典表已初始化");
+        Ok(())
     }
 
-    /// 根据假名精确搜索（全等匹配）
-    pub fn search_by_kana_exact(&self, kana: &str) -> Result<Vec<ObunshaDictEntry>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT * FROM obunsha_kokugo_dict WHERE kana_reading = ?1 ORDER BY headword"
+    /// 迁移：添加headword_normalized列，并为尚未填充的词条回填
+    fn ensure_headword_normalized(&self) -> Result<()> {
+        let exists: bool = self.conn.query_row(
+            "SELECT COUNT(*) FROM pragma_table_info('obunsha_kokugo_dict') WHERE name = 'headword_normalized'",
+            [],
+            |row| row.get::<_, i64>(0).map(|count| count > 0)
         )?;
+        if !exists {
+            self.conn.execute("ALTER TABLE obunsha_kokugo_dict ADD COLUMN headword_normalized TEXT", [])?;
+        }
 
-        let entry_iter = stmt.query_map([kana], |row| {
-            Ok(ObunshaDictEntry {
-                id: Some(row.get(0)?),
-                data_id: row.get(1)?,
-                data_type: row.get(2)?,
-                headword: row.get(3)?,
-                kana_reading: row.get(4)?,
-                kanji_writing: row.get(5)?,
-                part_of_speech: row.get(6)?,
-                conjugation: row.get(7)?,
-                definition_html: row.get(8)?,
-                definition_text: row.get(9)?,
-                raw_mdx_content: row.get(10)?,
-            })
-        })?;
+        let pending: Vec<(i64, String)> = {
+            let mut stmt = self.conn.prepare(
+                "SELECT id, headword FROM obunsha_kokugo_dict WHERE headword_normalized IS NULL"
+            )?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<Result<Vec<_>>>()?
+        };
+        if pending.is_empty() {
+            return Ok(());
+        }
 
-        let mut entries = Vec::new();
-        for entry in entry_iter {
-            entries.push(entry?);
+        let tx = self.conn.unchecked_transaction()?;
+        {
+            let mut stmt = tx.prepare("UPDATE obunsha_kokugo_dict SET headword_normalized = ?1 WHERE id = ?2")?;
+            for (id, headword) in &pending {
+                stmt.execute(params![kana::normalize_headword(headword), id])?;
+            }
         }
+        tx.commit()?;
 
-        Ok(entries)
+        println!("✅ 已回填 {} 条规范化标题", pending.len());
+        Ok(())
     }
 
-    /// 根据汉字智能搜索（同时进行精确匹配和包含匹配）
-    pub fn search_by_kanji_smart(&self, kanji: &str) -> Result<Vec<ObunshaDictEntry>> {
-        let mut entries = Vec::new();
-        let mut seen_ids = std::collections::HashSet::new();
-
-        // 首先进行精确匹配
-        let mut stmt = self.conn.prepare(
-            "SELECT * FROM obunsha_kokugo_dict WHERE kanji_writing = ?1 ORDER BY headword"
-        )?;
-
-        let entry_iter = stmt.query_map([kanji], |row| {
-            Ok(ObunshaDictEntry {
-                id: Some(row.get(0)?),
-                data_id: row.get(1)?,
-                data_type: row.get(2)?,
-                headword: row.get(3)?,
-                kana_reading: row.get(4)?,
-                kanji_writing: row.get(5)?,
-                part_of_speech: row.get(6)?,
-                conjugation: row.get(7)?,
-                definition_html: row.get(8)?,
-                definition_text: row.get(9)?,
-                raw_mdx_content: row.get(10)?,
-            })
-        })?;
-
-        for entry in entry_iter {
-            let entry = entry?;
-            seen_ids.insert(entry.data_id.clone());
-            entries.push(entry);
+    /// 用clean_kanji_text重新清理所有kanji_writing（统一中点、去掉空白和标记符号），返回修改的行数
+    /// 清理后为空的表记存为NULL；有修改时重建检索形式表
+    pub fn normalize_kanji_column(&self) -> Result<usize> {
+        let normalized = self.normalize_kanji_rows()?;
+        if normalized > 0 {
+            self.rebuild_search_all()?;
         }
+        Ok(normalized)
+    }
 
-        // 然后进行LIKE搜索（查找带点号的多重表记）
-        let mut stmt = self.conn.prepare(
-            "SELECT * FROM obunsha_kokugo_dict WHERE kanji_writing LIKE ?1 ORDER BY headword"
-        )?;
-
-        let entry_iter = stmt.query_map([format!("%{}%", kanji)], |row| {
-            Ok(ObunshaDictEntry {
-                id: Some(row.get(0)?),
-                data_id: row.get(1)?,
-                data_type: row.get(2)?,
-                headword: row.get(3)?,
-                kana_reading: row.get(4)?,
-                kanji_writing: row.get(5)?,
-                part_of_speech: row.get(6)?,
-                conjugation: row.get(7)?,
-                definition_html: row.get(8)?,
-                definition_text: row.get(9)?,
-                raw_mdx_content: row.get(10)?,
-            })
-        })?;
+    /// normalize_kanji_column的清理部分，不重建检索形式表（迁移中由版本6统一重建）
+    fn normalize_kanji_rows(&self) -> Result<usize> {
+        self.ensure_writable()?;
+        let rows: Vec<(i64, String)> = {
+            let mut stmt = self.conn.prepare(
+                "SELECT id, kanji_writing FROM obunsha_kokugo_dict WHERE kanji_writing IS NOT NULL"
+            )?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<Result<Vec<_>>>()?
+        };
 
-        for entry_result in entry_iter {
-            let entry = entry_result?;
-            // 避免重复添加已经在精确匹配中找到的词条
-            if !seen_ids.contains(&entry.data_id) {
-                // 应用层过滤：检查是否真的匹配（支持点号分割的多重表记）
-                if let Some(ref kanji_writing) = entry.kanji_writing {
-                    if kanji_writing.split('·').any(|part| part == kanji) {
-                        entries.push(entry);
-                    }
+        let parser = ObunshaParser::new();
+        let tx = self.conn.unchecked_transaction()?;
+        let mut normalized = 0;
+        {
+            let mut stmt = tx.prepare(
+                "UPDATE obunsha_kokugo_dict SET kanji_writing = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2"
+            )?;
+            for (id, kanji_writing) in &rows {
+                let cleaned = parser.clean_kanji_text(kanji_writing);
+                if cleaned != *kanji_writing {
+                    let cleaned = if cleaned.is_empty() { None } else { Some(cleaned) };
+                    stmt.execute(params![cleaned, id])?;
+                    normalized += 1;
                 }
             }
         }
-
-        Ok(entries)
+        tx.commit()?;
+        Ok(normalized)
     }
 
-    /// 获取表的统计信息
-    pub fn get_stats(&self) -> Result<(i64, i64)> {
-        let count: i64 = self.conn.query_row(
-            "SELECT COUNT(*) FROM obunsha_kokugo_dict",
-            [],
-            |row| row.get(0)
-        )?;
-
-        let unique_headwords: i64 = self.conn.query_row(
-            "SELECT COUNT(DISTINCT headword) FROM obunsha_kokugo_dict",
-            [],
-            |row| row.get(0)
-        )?;
+    /// 从definition_html中的缩写（.headword_ryaku）和纯文本释义计算尚未填写的全称，返回回填的词条数
+    fn backfill_expansion(&self) -> Result<usize> {
+        let rows: Vec<(i64, String, String)> = {
+            let mut stmt = self.conn.prepare(
+                "SELECT id, definition_html, definition_text FROM obunsha_kokugo_dict
+                 WHERE expansion IS NULL AND definition_html LIKE '%headword_ryaku%'"
+            )?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+            rows.collect::<Result<Vec<_>>>()?
+        };
 
-        Ok((count, unique_headwords))
+        let mut filled = 0;
+        let tx = self.conn.unchecked_transaction()?;
+        {
+            let mut stmt = tx.prepare("UPDATE obunsha_kokugo_dict SET expansion = ?1 WHERE id = ?2")?;
+            for (id, definition_html, definition_text) in &rows {
+                if let Some(expansion) = expansion_from_html(definition_html, definition_text) {
+                    stmt.execute(params![expansion, id])?;
+                    filled += 1;
+                }
+            }
+        }
+        tx.commit()?;
+        Ok(filled)
     }
 
-    /// 从清理后的数据文件解析并导入所有词条
-    pub fn import_from_cleaned_data(&self, cleaned_data_path: &str) -> Result<usize, Box<dyn std::error::Error>> {
-        use std::fs::File;
-        use std::io::{BufRead, BufReader};
-
-        println!("🚀 开始从清理数据导入词条: {}", cleaned_data_path);
+    /// 去掉已有词条kana_reading中的中点，同步更新排序键，返回修改的词条数
+    /// 去掉后为空的读音存为NULL；不重建检索形式表，由调用方重建
+    fn strip_reading_dots_from_rows(&self) -> Result<usize> {
+        let rows: Vec<(i64, String, String)> = {
+            let mut stmt = self.conn.prepare(
+                "SELECT id, headword, kana_reading FROM obunsha_kokugo_dict WHERE kana_reading LIKE '%・%'"
+            )?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+            rows.collect::<Result<Vec<_>>>()?
+        };
 
-        let file = File::open(cleaned_data_path)?;
-        let reader = BufReader::new(file);
-        let mut lines = reader.lines();
+        let tx = self.conn.unchecked_transaction()?;
+        {
+            let mut stmt = tx.prepare(
+                "UPDATE obunsha_kokugo_dict SET kana_reading = ?1, sort_key = ?2, updated_at = CURRENT_TIMESTAMP WHERE id = ?3"
+            )?;
+            for (id, headword, kana_reading) in &rows {
+                let stripped = kana_reading.replace('・', "");
+                let stripped = if stripped.is_empty() { None } else { Some(stripped) };
+                stmt.execute(params![stripped, Self::sort_key_column(headword, stripped.as_deref()), id])?;
+            }
+        }
+        tx.commit()?;
+        Ok(rows.len())
+    }
 
-        let mut entries = Vec::new();
-        let mut current_title: Option<String> = None;
-        let mut processed_count = 0;
+    /// 去除已有词条文本字段中的不可见字符（见strip_invisible），同步更新规范化标题，返回修改的词条数
+    fn strip_invisible_from_rows(&self) -> Result<usize> {
+        type TextRow = (i64, String, Option<String>, Option<String>, Option<String>, Option<String>, String);
 
-        while let Some(line_result) = lines.next() {
-            let line = line_result?;
-            
-            if line.trim().is_empty() {
-                // 空行表示词条结束，重置状态
-                current_title = None;
-                continue;
-            }
+        let rows: Vec<TextRow> = {
+            let mut stmt = self.conn.prepare(
+                "SELECT id, headword, kana_reading, kanji_writing, part_of_speech, conjugation, definition_text FROM obunsha_kokugo_dict"
+            )?;
+            let rows = stmt.query_map([], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?, row.get(6)?))
+            })?;
+            rows.collect::<Result<Vec<_>>>()?
+        };
 
-            if line.contains("<link rel=\"stylesheet\"") {
-                // 这是HTML内容行
-                if let Some(title) = current_title.take() {
-                    // 解析这个词条
-                    if let Some(entry) = self.parse_entry_from_html(&title, &line) {
-                        entries.push(entry);
-                        processed_count += 1;
+        let strip = |value: &Option<String>| value.as_deref().map(strip_invisible);
+        let tx = self.conn.unchecked_transaction()?;
+        let mut cleaned = 0;
+        {
+            let mut stmt = tx.prepare(
+                r#"
+                UPDATE obunsha_kokugo_dict SET
+                    headword = ?1, headword_normalized = ?2, kana_reading = ?3, kanji_writing = ?4,
+                    part_of_speech = ?5, conjugation = ?6, definition_text = ?7, english_gloss = ?8,
+                    updated_at = CURRENT_TIMESTAMP
+                WHERE id = ?9
+                "#,
+            )?;
+            for (id, headword, kana_reading, kanji_writing, part_of_speech, conjugation, definition_text) in &rows {
+                let new_headword = strip_invisible(headword);
+                let new_kana = strip(kana_reading);
+                let new_kanji = strip(kanji_writing);
+                let new_pos = strip(part_of_speech);
+                let new_conjugation = strip(conjugation);
+                let new_definition = strip_invisible(definition_text);
 
-                        // 每1000条批量插入一次
-                        if entries.len() >= 1000 {
-                            self.insert_entries_batch(&entries)?;
-                            entries.clear();
-                            println!("✅ 已导入 {} 条词条", processed_count);
-                        }
-                    }
+                let changed = new_headword != *headword
+                    || new_kana != *kana_reading
+                    || new_kanji != *kanji_writing
+                    || new_pos != *part_of_speech
+                    || new_conjugation != *conjugation
+                    || new_definition != *definition_text;
+                if changed {
+                    stmt.execute(params![
+                        new_headword,
+                        kana::normalize_headword(&new_headword),
+                        new_kana,
+                        new_kanji,
+                        new_pos,
+                        new_conjugation,
+                        new_definition,
+                        Self::english_gloss_column(&new_definition),
+                        id
+                    ])?;
+                    cleaned += 1;
                 }
-            } else {
-                // 这是标题行
-                current_title = Some(line);
             }
         }
+        tx.commit()?;
 
-        // 插入剩余的词条
-        if !entries.is_empty() {
-            self.insert_entries_batch(&entries)?;
-        }
+        Ok(cleaned)
+    }
 
-        println!("🎉 导入完成！共处理 {} 条词条", processed_count);
-        Ok(processed_count)
+    /// 为旧数据库补充缺失的列
+    fn ensure_column(&self, column: &str, definition: &str) -> Result<()> {
+        let exists = self.conn
+            .prepare("SELECT 1 FROM pragma_table_info('obunsha_kokugo_dict') WHERE name = ?1")?
+            .exists([column])?;
+        if !exists {
+            self.conn.execute(
+                &format!("ALTER TABLE obunsha_kokugo_dict ADD COLUMN {} {}", column, definition),
+                [],
+            )?;
+        }
+        Ok(())
     }
 
-    /// 从HTML解析单个词条
-    fn parse_entry_from_html(&self, title: &str, html: &str) -> Option<ObunshaDictEntry> {
-        use scraper::{Html, Selector};
+    /// 为旧数据库添加english_gloss列，并从纯文本定义回填
+    fn ensure_english_gloss(&self) -> Result<()> {
+        self.ensure_column("english_gloss", "TEXT")?;
 
-        let document = Html::parse_fragment(html);
-        
-        // 提取data-id
-        let container_selector = Selector::parse("container").ok()?;
-        let container = document.select(&container_selector).next()?;
-        let data_id = container.value().attr("data-id")?.to_string();
-        let data_type = container.value().attr("data-type").unwrap_or("unknown").to_string();
-
-        // CSS选择器
-        let kana_selector = Selector::parse(".headword_kana").ok()?;
-        let kanji_selector = Selector::parse(".headword_hyouki").ok()?;
-        let ryaku_selector = Selector::parse(".headword_ryaku").ok()?;
-        let pos_selector = Selector::parse(".pos_s").ok()?;
-        let katsuyo_selector = Selector::parse(".katsuyo").ok()?;
-
-        let mut kana_reading: Option<String> = None;
-        let mut kanji_writing: Option<String> = None;
-        let mut part_of_speech: Option<String> = None;
-        let mut conjugation: Option<String> = None;
-
-        // 优先从headline（title）解析假名和汉字
-        if let Some((kana, kanji)) = self.parse_headline(title) {
-            kana_reading = Some(kana);
-            kanji_writing = Some(kanji);
-        } else {
+        let pending: Vec<(i64, String)> = {
+            let mut stmt = self.conn.prepare(
+                "SELECT id, definition_text FROM obunsha_kokugo_dict WHERE english_gloss IS NULL"
+            )?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<Result<Vec<_>>>()?
+        };
+        if pending.is_empty() {
+            return Ok(());
         }
 
-        // 如果从headline解析失败，再从HTML中选择器提取
-        if kana_reading.is_none() {
-            if let Some(kana_element) = document.select(&kana_selector).next() {
-                let kana_text = kana_element.text().collect::<String>();
-                let cleaned_kana = self.clean_kana_text(&kana_text);
-                if !cleaned_kana.is_empty() {
-                    kana_reading = Some(cleaned_kana);
-                }
+        let tx = self.conn.unchecked_transaction()?;
+        {
+            let mut stmt = tx.prepare("UPDATE obunsha_kokugo_dict SET english_gloss = ?1 WHERE id = ?2")?;
+            for (id, definition_text) in &pending {
+                stmt.execute(params![Self::english_gloss_column(definition_text), id])?;
             }
         }
+        tx.commit()?;
 
-        if kanji_writing.is_none() {
-            if let Some(kanji_element) = document.select(&kanji_selector).next() {
-                let kanji_text = kanji_element.text().collect::<String>();
-                let cleaned_kanji = self.clean_kanji_text(&kanji_text);
-                if !cleaned_kanji.is_empty() {
-                    kanji_writing = Some(cleaned_kanji);
-                }
-            }
+        println!("✅ 已回填 {} 条英文对应词", pending.len());
+        Ok(())
+    }
+
+    /// 迁移：添加example_count列，并为尚未统计的词条回填
+    fn ensure_example_count(&self) -> Result<()> {
+        self.ensure_column("example_count", "INTEGER")?;
+
+        let pending: Vec<(i64, String)> = {
+            let mut stmt = self.conn.prepare(
+                "SELECT id, definition_html FROM obunsha_kokugo_dict WHERE example_count IS NULL"
+            )?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<Result<Vec<_>>>()?
+        };
+        if pending.is_empty() {
+            return Ok(());
         }
 
-        // 对于英文缩写词条，提取ryaku作为假名读音
-        if kana_reading.is_none() {
-            if let Some(ryaku_element) = document.select(&ryaku_selector).next() {
-                let ryaku_text = ryaku_element.text().collect::<String>();
-                let cleaned_ryaku = self.clean_kana_text(&ryaku_text);
-                if !cleaned_ryaku.is_empty() {
-                    kana_reading = Some(cleaned_ryaku);
-                }
+        let tx = self.conn.unchecked_transaction()?;
+        {
+            let mut stmt = tx.prepare("UPDATE obunsha_kokugo_dict SET example_count = ?1 WHERE id = ?2")?;
+            for (id, definition_html) in &pending {
+                stmt.execute(params![count_examples(definition_html) as i64, id])?;
             }
         }
+        tx.commit()?;
 
-        // 提取词性信息
-        if let Some(pos_element) = document.select(&pos_selector).next() {
-            let pos_text = pos_element.text().collect::<String>().trim().to_string();
-            if !pos_text.is_empty() {
-                part_of_speech = Some(pos_text);
-            }
+        println!("✅ 已回填 {} 条词条的例句数", pending.len());
+        Ok(())
+    }
+
+    /// 迁移：添加sort_key列，并为尚未计算的词条回填
+    fn ensure_sort_key(&self) -> Result<()> {
+        self.ensure_column("sort_key", "TEXT")?;
+
+        let pending: Vec<(i64, String, Option<String>)> = {
+            let mut stmt = self.conn.prepare(
+                "SELECT id, headword, kana_reading FROM obunsha_kokugo_dict WHERE sort_key IS NULL"
+            )?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+            rows.collect::<Result<Vec<_>>>()?
+        };
+        if pending.is_empty() {
+            return Ok(());
         }
 
-        // 提取活用形
-        if let Some(katsuyo_element) = document.select(&katsuyo_selector).next() {
-            let katsuyo_text = katsuyo_element.text().collect::<String>().trim().to_string();
-            if !katsuyo_text.is_empty() {
-                conjugation = Some(katsuyo_text);
+        let tx = self.conn.unchecked_transaction()?;
+        {
+            let mut stmt = tx.prepare("UPDATE obunsha_kokugo_dict SET sort_key = ?1 WHERE id = ?2")?;
+            for (id, headword, kana_reading) in &pending {
+                stmt.execute(params![Self::sort_key_column(headword, kana_reading.as_deref()), id])?;
             }
         }
+        tx.commit()?;
 
-        // 提取纯文本定义
-        let definition_text = self.extract_definition_text(&document);
+        println!("✅ 已回填 {} 条词条的排序键", pending.len());
+        Ok(())
+    }
 
-        Some(ObunshaDictEntry {
-            id: None,
-            data_id,
-            data_type,
-            headword: title.to_string(),
-            kana_reading,
-            kanji_writing,
-            part_of_speech,
-            conjugation,
-            definition_html: html.to_string(),
-            definition_text,
-            raw_mdx_content: format!("{}\n{}", title, html),
-        })
+    /// sort_key列的值：按假名读音计算，没有读音时按标题计算
+    fn sort_key_column(headword: &str, kana_reading: Option<&str>) -> String {
+        kana::sort_key(kana_reading.unwrap_or(headword))
     }
 
-    /// 从headline解析假名和汉字
-    fn parse_headline(&self, headline: &str) -> Option<(String, String)> {
-        let headline = headline.trim();
-        
-        // 检查是否包含【】括号格式：假名【汉字】
-        if let Some(start) = headline.find('【') {
-            if let Some(end) = headline.find('】') {
-                if start < end {
-                    // 使用chars()迭代器来正确处理中文字符
-                    let chars: Vec<char> = headline.chars().collect();
-                    
-                    // 将字节索引转换为字符索引
-                    let start_char = headline[..start].chars().count();
-                    let end_char = headline[..end].chars().count();
-                    
-                    if start_char < end_char && start_char < chars.len() && end_char < chars.len() {
-                        let kana_part: String = chars[..start_char].iter().collect();
-                        let kanji_part: String = chars[start_char + 1..end_char].iter().collect();
-                        
-                        // 假名部分不能为空，汉字部分可以为空（如：ば【】）
-                        if !kana_part.is_empty() {
-                            return Some((kana_part, kanji_part));
-                        }
-                    }
-                }
-            }
+    /// english_gloss列的值：各英文对应词用|分隔并在两端加|，便于按整词匹配；没有时为空字符串
+    fn english_gloss_column(definition_text: &str) -> String {
+        let glosses = extract_english_glosses(definition_text);
+        if glosses.is_empty() {
+            String::new()
+        } else {
+            format!("|{}|", glosses.join("|"))
         }
-        
-        // 如果没有括号，检查是否只有假名
-        if !headline.is_empty() {
-            // 检查是否包含汉字
-            let has_kanji = headline.chars().any(|c| {
-                c >= '\u{4e00}' && c <= '\u{9fff}' // CJK统一汉字
-            });
-            
-            if !has_kanji {
-                // 只有假名的情况
-                return Some((headline.to_string(), String::new()));
-            }
+    }
+
+    /// 词条的全部检索形式：规范化标题、平假名读音和各个汉字表记，去重
+    fn search_forms(headword: &str, kana_reading: Option<&str>, kanji_writing: Option<&str>) -> Vec<String> {
+        let mut forms = vec![Self::search_form(headword)];
+        if let Some(kana_reading) = kana_reading {
+            forms.push(Self::search_form(kana_reading));
         }
-        
-        None
+        if let Some(kanji_writing) = kanji_writing {
+            forms.extend(split_kanji_variants(kanji_writing).iter().map(|variant| Self::search_form(variant)));
+        }
+        forms.retain(|form| !form.is_empty());
+        forms.sort();
+        forms.dedup();
+        forms
     }
 
-    /// 清理假名文本，去除特殊符号和HTML标签
-    fn clean_kana_text(&self, text: &str) -> String {
-        let mut result = String::new();
-        
-        for ch in text.chars() {
-            match ch {
-                // 保留平假名
-                '\u{3040}'..='\u{309f}' => result.push(ch),
-                // 保留片假名
-                '\u{30a0}'..='\u{30ff}' => result.push(ch),
-                // 保留片假名长音符号
-                'ー' => result.push(ch),
-                // 保留英文和数字（用于英文缩写词条）
-                _ if ch.is_ascii_alphanumeric() => result.push(ch),
-                // 对于英文词条，保留连字符和下划线
-                '-' | '_' if text.chars().any(|c| c.is_ascii_alphabetic()) => result.push(ch),
-                // 过滤掉所有其他符号，包括日语词条中的ASCII连字符
-                _ => {}
-            }
+    /// 检索形式：NFKC规范化、小写，片假名转为平假名
+    fn search_form(text: &str) -> String {
+        kana::katakana_to_hiragana(&kana::normalize_headword(text.trim()))
+    }
+
+    /// 用词条当前的标题、读音和表记替换其检索形式
+    fn write_search_forms(conn: &Connection, id: i64, headword: &str, kana_reading: Option<&str>, kanji_writing: Option<&str>) -> Result<()> {
+        conn.execute("DELETE FROM search_all WHERE entry_id = ?1", [id])?;
+        let mut stmt = conn.prepare_cached("INSERT OR IGNORE INTO search_all (form, entry_id) VALUES (?1, ?2)")?;
+        for form in Self::search_forms(headword, kana_reading, kanji_writing) {
+            stmt.execute(params![form, id])?;
         }
-        
-        result.trim().to_string()
+        Ok(())
     }
 
-    /// 清理汉字文本，去除标记符号
-    fn clean_kanji_text(&self, text: &str) -> String {
-        let mut result = String::new();
-        
-        for ch in text.chars() {
-            match ch {
-                // 保留汉字 (CJK统一汉字)
-                '\u{4e00}'..='\u{9fff}' => result.push(ch),
-                // 保留平假名
-                '\u{3040}'..='\u{309f}' => result.push(ch),
-                // 保留片假名
-                '\u{30a0}'..='\u{30ff}' => result.push(ch),
-                // 保留一些基本符号
-                '・' | '‧' | '·' | '-' | 'ー' => result.push(ch),
-                // 过滤掉标记符号
-                '【' | '】' | '◇' | '△' | '▽' | '▲' | '▼' | '○' | '●' | '◯' | 
-                '□' | '■' | '▢' | '▣' | '◆' | '※' | '＊' | '☆' | '★' => {
-                    // 跳过这些标记符号
-                },
-                // 保留其他可能有用的字符（如英文、数字）
-                _ if ch.is_alphanumeric() => result.push(ch),
-                _ => {} // 跳过其他特殊符号
-            }
+    /// 根据主表重建检索形式表，返回建立了检索形式的词条数
+    pub fn rebuild_search_all(&self) -> Result<usize> {
+        self.ensure_writable()?;
+        type FormRow = (i64, String, Option<String>, Option<String>);
+        let rows: Vec<FormRow> = {
+            let mut stmt = self.conn.prepare(
+                "SELECT id, headword, kana_reading, kanji_writing FROM obunsha_kokugo_dict"
+            )?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))?;
+            rows.collect::<Result<Vec<_>>>()?
+        };
+
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM search_all", [])?;
+        for (id, headword, kana_reading, kanji_writing) in &rows {
+            Self::write_search_forms(&tx, *id, headword, kana_reading.as_deref(), kanji_writing.as_deref())?;
         }
-        
-        result.trim().to_string()
+        tx.commit()?;
+
+        Ok(rows.len())
     }
 
-    /// 提取定义的纯文本内容
-    fn extract_definition_text(&self, document: &Html) -> String {
-        use scraper::Selector;
+    /// 插入单个词条，严格模式下未通过校验的词条返回SQLITE_CONSTRAINT_CHECK错误
+    pub fn insert_entry(&self, entry: &ObunshaDictEntry) -> Result<i64> {
+        self.ensure_writable()?;
+        if let Some(reason) = self.check_entry(entry) {
+            return Err(Self::rejected_entry_error(entry, &reason));
+        }
+        let mut stmt = self.conn.prepare(
+            r#"
+            INSERT INTO obunsha_kokugo_dict (
+                data_id, data_type, headword, kana_reading, kanji_writing,
+                part_of_speech, conjugation, definition_html, definition_text, raw_mdx_content,
+                headword_normalized, english_gloss, expansion, example_count, sort_key
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)
+            "#,
+        )?;
 
-        let meaning_selectors = [
-            ".mean_normal",
-            ".mean_lv_2", 
-            ".mean_lv_1",
-            ".mean_no_1",
-            ".mean_no_2", 
-            ".mean_no_3",
-        ];
-        
-        let mut meanings = Vec::new();
-        
-        for selector_str in &meaning_selectors {
-            if let Ok(selector) = Selector::parse(selector_str) {
-                for element in document.select(&selector) {
-                    let text = element.text().collect::<Vec<_>>().join("");
-                    let cleaned_text = text.trim();
-                    if !cleaned_text.is_empty() {
-                        meanings.push(cleaned_text.to_string());
-                    }
+        let row_id = stmt.insert(params![
+            entry.data_id,
+            entry.data_type,
+            entry.headword,
+            entry.kana_reading,
+            entry.kanji_writing,
+            entry.part_of_speech,
+            entry.conjugation,
+            entry.definition_html,
+            entry.definition_text,
+            entry.raw_mdx_content,
+            kana::normalize_headword(&entry.headword),
+            Self::english_gloss_column(&entry.definition_text),
+            entry.expansion,
+            count_examples(&entry.definition_html) as i64,
+            Self::sort_key_column(&entry.headword, entry.kana_reading.as_deref()),
+        ])?;
+        Self::write_search_forms(&self.conn, row_id, &entry.headword, entry.kana_reading.as_deref(), entry.kanji_writing.as_deref())?;
+
+        Ok(row_id)
+    }
+
+    /// 批量插入词条，返回写入的词条数
+    /// 按data_id更新已有词条，只有内容确实变化时才刷新updated_at，重复导入不会改变未修改的词条
+    /// 严格模式下未通过校验的词条写入parse_failures隔离区并跳过，其余词条照常写入
+    pub fn insert_entries_batch(&self, entries: &[ObunshaDictEntry]) -> Result<usize> {
+        self.ensure_writable()?;
+        let mut accepted = Vec::with_capacity(entries.len());
+        for entry in entries {
+            match self.check_entry(entry) {
+                Some(reason) => {
+                    let (title, html) = entry.raw_mdx_content
+                        .split_once('\n')
+                        .unwrap_or((&entry.headword, &entry.definition_html));
+                    self.record_parse_failure(title, html, &reason)?;
                 }
+                None => accepted.push(entry),
             }
         }
+        if accepted.len() < entries.len() {
+            println!("⚠️  {} 条词条未通过严格模式校验，已写入隔离区", entries.len() - accepted.len());
+        }
+        let tx = self.conn.unchecked_transaction()?;
         
-        if meanings.is_empty() {
-            // 如果没有找到特定的释义元素，提取所有文本
-            document.root_element().text().collect::<String>().trim().to_string()
-        } else {
-            meanings.join(" ")
+        {
+            let mut stmt = tx.prepare(
+                r#"
+                INSERT INTO obunsha_kokugo_dict (
+                    data_id, data_type, headword, kana_reading, kanji_writing,
+                    part_of_speech, conjugation, definition_html, definition_text, raw_mdx_content,
+                    headword_normalized, english_gloss, expansion, example_count, sort_key
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)
+                ON CONFLICT(data_id) DO UPDATE SET
+                    data_type = excluded.data_type,
+                    headword = excluded.headword,
+                    headword_normalized = excluded.headword_normalized,
+                    kana_reading = excluded.kana_reading,
+                    kanji_writing = excluded.kanji_writing,
+                    part_of_speech = excluded.part_of_speech,
+                    conjugation = excluded.conjugation,
+                    definition_html = excluded.definition_html,
+                    definition_text = excluded.definition_text,
+                    english_gloss = excluded.english_gloss,
+                    expansion = excluded.expansion,
+                    example_count = excluded.example_count,
+                    sort_key = excluded.sort_key,
+                    raw_mdx_content = excluded.raw_mdx_content,
+                    updated_at = CURRENT_TIMESTAMP
+                WHERE data_type IS NOT excluded.data_type
+                   OR headword IS NOT excluded.headword
+                   OR kana_reading IS NOT excluded.kana_reading
+                   OR kanji_writing IS NOT excluded.kanji_writing
+                   OR part_of_speech IS NOT excluded.part_of_speech
+                   OR conjugation IS NOT excluded.conjugation
+                   OR definition_html IS NOT excluded.definition_html
+                   OR definition_text IS NOT excluded.definition_text
+                   OR raw_mdx_content IS NOT excluded.raw_mdx_content
+                   OR expansion IS NOT excluded.expansion
+                "#,
+            )?;
+
+            let mut id_stmt = tx.prepare("SELECT id FROM obunsha_kokugo_dict WHERE data_id = ?1")?;
+
+            for entry in &accepted {
+                stmt.execute(params![
+                    entry.data_id,
+                    entry.data_type,
+                    entry.headword,
+                    entry.kana_reading,
+                    entry.kanji_writing,
+                    entry.part_of_speech,
+                    entry.conjugation,
+                    entry.definition_html,
+                    entry.definition_text,
+                    entry.raw_mdx_content,
+                    kana::normalize_headword(&entry.headword),
+                    Self::english_gloss_column(&entry.definition_text),
+                    entry.expansion,
+                    count_examples(&entry.definition_html) as i64,
+                    Self::sort_key_column(&entry.headword, entry.kana_reading.as_deref()),
+                ])?;
+                let id: i64 = id_stmt.query_row([&entry.data_id], |row| row.get(0))?;
+                Self::write_search_forms(&tx, id, &entry.headword, entry.kana_reading.as_deref(), entry.kanji_writing.as_deref())?;
+            }
         }
+
+        tx.commit()?;
+        println!("✅ 成功插入 {} 条词条", accepted.len());
+        Ok(accepted.len())
     }
-} 
\ No newline at end of file
+
+    /// 根据标题查询词条（模糊匹配，保留原有功能）
+    /// 与规范化标题比较，全角/半角写法均可命中；同时匹配英文缩写的全称（如用"nucleic"找到DNA）
+    pub fn search_by_headword(&self, headword: &str) -> Result<Vec<ObunshaDictEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT * FROM obunsha_kokugo_dict WHERE headword_normalized LIKE ?1 OR lower(expansion) LIKE ?1 ORDER BY headword"
+        )?;
+
+        let entry_iter = stmt.query_map([format!("%{}%", kana::normalize_headword(headword))], Self::entry_from_row)?;
+
+        let mut entries = Vec::new();
+        for entry in entry_iter {
+            entries.push(entry?);
+        }
+
+        Ok(entries)
+    }
+
+    /// 在检索形式表中一次查询匹配标题、读音或任一汉字表记
+    /// 查询同样规范化（全角/半角、片假名均可），罗马字整体可转换为假名时也一并匹配
+    /// 旧数据库没有检索形式表时，依次退回假名精确、汉字智能和规范化标题搜索
+    pub fn search_anything(&self, q: &str) -> Result<Vec<ObunshaDictEntry>> {
+        if !self.table_exists("search_all")? {
+            return Ok(self.search_best(q, &DEFAULT_SEARCH_CHAIN)?.0);
+        }
+
+        let mut forms = vec![Self::search_form(q)];
+        let (hiragana, rest) = kana::romaji_to_hiragana(q);
+        if rest.is_empty() && !hiragana.is_empty() {
+            forms.push(hiragana);
+        }
+        forms.retain(|form| !form.is_empty());
+        forms.dedup();
+        if forms.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let placeholders = vec!["?"; forms.len()].join(", ");
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT * FROM obunsha_kokugo_dict WHERE id IN (SELECT entry_id FROM search_all WHERE form IN ({})) ORDER BY headword",
+            placeholders
+        ))?;
+        let entry_iter = stmt.query_map(rusqlite::params_from_iter(forms.iter()), Self::entry_from_row)?;
+
+        let mut entries = Vec::new();
+        for entry in entry_iter {
+            entries.push(entry?);
+        }
+
+        Ok(entries)
+    }
+
+    /// 按chain的顺序逐级搜索，返回第一个有结果的一级的结果及该级；全部无结果时返回空列表和None
+    pub fn search_best(&self, q: &str, chain: &[SearchTier]) -> Result<(Vec<ObunshaDictEntry>, Option<SearchTier>)> {
+        for &tier in chain {
+            let results = match tier {
+                SearchTier::Kana => self.search_by_kana_exact(q)?,
+                SearchTier::Kanji => self.search_by_kanji_smart(q)?,
+                SearchTier::Headword => self.find_by_headword(q)?,
+            };
+            if !results.is_empty() {
+                return Ok((results, Some(tier)));
+            }
+        }
+        Ok((Vec::new(), None))
+    }
+
+    /// 根据假名精确搜索（全等匹配）
+    pub fn search_by_kana_exact(&self, kana: &str) -> Result<Vec<ObunshaDictEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT * FROM obunsha_kokugo_dict WHERE kana_reading = ?1 ORDER BY headword"
+        )?;
+
+        let entry_iter = stmt.query_map([kana], Self::entry_from_row)?;
+
+        let mut entries = Vec::new();
+        for entry in entry_iter {
+            entries.push(entry?);
+        }
+
+        Ok(entries)
+    }
+
+    /// 根据汉字智能搜索（同时进行精确匹配和包含匹配）
+    pub fn search_by_kanji_smart(&self, kanji: &str) -> Result<Vec<ObunshaDictEntry>> {
+        let mut entries = Vec::new();
+        let mut seen_ids = std::collections::HashSet::new();
+
+        // 首先进行精确匹配
+        let mut stmt = self.conn.prepare(
+            "SELECT * FROM obunsha_kokugo_dict WHERE kanji_writing = ?1 ORDER BY headword"
+        )?;
+
+        let entry_iter = stmt.query_map([kanji], Self::entry_from_row)?;
+
+        for entry in entry_iter {
+            let entry = entry?;
+            seen_ids.insert(entry.data_id.clone());
+            entries.push(entry);
+        }
+
+        // 然后进行LIKE搜索（查找带点号的多重表记）
+        let mut stmt = self.conn.prepare(
+            "SELECT * FROM obunsha_kokugo_dict WHERE kanji_writing LIKE ?1 ORDER BY headword"
+        )?;
+
+        let entry_iter = stmt.query_map([format!("%{}%", kanji)], Self::entry_from_row)?;
+
+        for entry_result in entry_iter {
+            let entry = entry_result?;
+            // 避免重复添加已经在精确匹配中找到的词条
+            if !seen_ids.contains(&entry.data_id) {
+                // 应用层过滤：检查是否真的匹配（支持点号分割的多重表记）
+                if let Some(ref kanji_writing) = entry.kanji_writing
+                    && split_kanji_variants(kanji_writing).iter().any(|part| part == kanji)
+                {
+                    entries.push(entry);
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// 汉字表记的新字体・旧字体搜索：把查询词展开为各字的新旧字体组合（见jitai::expand_variants），
+    /// 用一条查询做与search_by_kanji_smart相同的匹配，如"国学"也找到写作"國學"的词条
+    /// 候选词条通过检索形式表（search_all）的索引查找，尚未建立检索形式表的旧数据库退回到LIKE扫描
+    /// 结果按匹配的写法排列（原文在前），同一写法中完全一致的表记在前
+    pub fn search_by_kanji_variants(&self, kanji: &str) -> Result<Vec<ObunshaDictEntry>> {
+        let forms = jitai::expand_variants(kanji.trim());
+        let (condition, values): (String, Vec<String>) = if self.table_exists("search_all")? {
+            let values = forms.iter().map(|form| Self::search_form(form)).collect::<Vec<_>>();
+            let placeholders = vec!["?"; values.len()].join(", ");
+            (format!("id IN (SELECT entry_id FROM search_all WHERE form IN ({}))", placeholders), values)
+        } else {
+            let conditions = (1..=forms.len())
+                .map(|index| format!("kanji_writing LIKE ?{} ESCAPE '\\'", index))
+                .collect::<Vec<_>>()
+                .join(" OR ");
+            (conditions, forms.iter().map(|form| format!("%{}%", Self::escape_like(form))).collect())
+        };
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT * FROM obunsha_kokugo_dict WHERE {} ORDER BY headword",
+            condition
+        ))?;
+        let entry_iter = stmt.query_map(rusqlite::params_from_iter(values), Self::entry_from_row)?;
+
+        // (写法序号, 是否为多重表记中的一个, 词条)
+        let mut matched = Vec::new();
+        for entry in entry_iter {
+            let entry = entry?;
+            let Some(kanji_writing) = entry.kanji_writing.as_deref() else { continue };
+            let variants = split_kanji_variants(kanji_writing);
+            let position = forms.iter().enumerate().find_map(|(index, form)| {
+                if kanji_writing == form {
+                    Some((index, false))
+                } else if variants.iter().any(|variant| variant == form) {
+                    Some((index, true))
+                } else {
+                    None
+                }
+            });
+            if let Some((index, dotted)) = position {
+                matched.push((index, dotted, entry));
+            }
+        }
+        matched.sort_by_key(|&(index, dotted, _)| (index, dotted));
+        Ok(matched.into_iter().map(|(_, _, entry)| entry).collect())
+    }
+
+    /// 同形异读词的读音选择：按kana_reading对search_by_kanji_smart的结果分组，每个读音取第一个词条作代表
+    /// 如"生"返回 せい、なま 等各一个词条；没有假名读音的词条不计入，读音按首次出现的顺序排列
+    pub fn disambiguate(&self, kanji: &str) -> Result<Vec<(String, ObunshaDictEntry)>> {
+        let mut readings: Vec<(String, ObunshaDictEntry)> = Vec::new();
+        for entry in self.search_by_kanji_smart(kanji.trim())? {
+            let Some(reading) = entry.kana_reading.clone().filter(|reading| !reading.is_empty()) else {
+                continue;
+            };
+            if !readings.iter().any(|(existing, _)| *existing == reading) {
+                readings.push((reading, entry));
+            }
+        }
+        Ok(readings)
+    }
+
+    /// 布尔查询
+    /// 每个查询词匹配假名读音（全等）、汉字表记（包含）或规范化标题（全等），组内取交集，组间取并集
+    pub fn search_boolean(&self, query: &BooleanQuery, limit: usize) -> Result<Vec<ObunshaDictEntry>> {
+        let mut values: Vec<String> = Vec::new();
+        let mut group_clauses = Vec::new();
+
+        for group in &query.groups {
+            let mut term_clauses = Vec::new();
+            for term in group {
+                let base = values.len();
+                term_clauses.push(format!(
+                    "(kana_reading = ?{} OR kanji_writing LIKE ?{} ESCAPE '\\' OR headword_normalized = ?{})",
+                    base + 1, base + 2, base + 3
+                ));
+                values.push(term.clone());
+                values.push(format!("%{}%", Self::escape_like(term)));
+                values.push(kana::normalize_headword(term));
+            }
+            group_clauses.push(format!("({})", term_clauses.join(" AND ")));
+        }
+
+        let sql = format!(
+            "SELECT * FROM obunsha_kokugo_dict WHERE {} ORDER BY headword LIMIT {}",
+            group_clauses.join(" OR "),
+            limit
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
+        let entry_iter = stmt.query_map(rusqlite::params_from_iter(values.iter()), Self::entry_from_row)?;
+
+        let mut entries = Vec::new();
+        for entry in entry_iter {
+            entries.push(entry?);
+        }
+
+        Ok(entries)
+    }
+
+    /// 假名容错搜索（纠正输入时按错相邻假名的情况）
+    /// 只比较长度相差不超过max_distance的读音，按kana_distance由近到远排序；
+    /// rank为 1/(1+距离)，完全一致时为1
+    /// 先只读取id和读音计算距离，再加载排在前limit个的完整词条
+    pub fn search_by_kana_typo(&self, kana: &str, max_distance: f64, limit: usize) -> Result<Vec<RankedEntry>> {
+        let length = kana.chars().count() as i64;
+        let slack = max_distance.ceil() as i64;
+
+        let mut candidates = Vec::new();
+        {
+            let mut stmt = self.conn.prepare(
+                r#"
+                SELECT id, kana_reading FROM obunsha_kokugo_dict
+                WHERE kana_reading IS NOT NULL
+                  AND length(kana_reading) BETWEEN ?1 AND ?2
+                "#,
+            )?;
+            let rows = stmt.query_map(params![length - slack, length + slack], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+            })?;
+            for row in rows {
+                let (id, reading) = row?;
+                let distance = kana::kana_distance(kana, &reading);
+                if distance <= max_distance {
+                    candidates.push((id, distance));
+                }
+            }
+        }
+
+        // 稳定排序：距离相同的词条保持id顺序
+        candidates.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+        candidates.truncate(limit);
+
+        let mut found = std::collections::HashMap::new();
+        for chunk in candidates.chunks(MAX_SQL_PARAMS) {
+            let placeholders = vec!["?"; chunk.len()].join(", ");
+            let sql = format!("SELECT * FROM obunsha_kokugo_dict WHERE id IN ({})", placeholders);
+            let mut stmt = self.conn.prepare(&sql)?;
+            let entry_iter = stmt.query_map(rusqlite::params_from_iter(chunk.iter().map(|(id, _)| id)), Self::entry_from_row)?;
+            for entry in entry_iter {
+                let entry = entry?;
+                found.insert(entry.id, entry);
+            }
+        }
+
+        Ok(candidates
+            .into_iter()
+            .filter_map(|(id, distance)| {
+                found.remove(&Some(id)).map(|entry| RankedEntry::with_rank(entry, 1.0 / (1.0 + distance)))
+            })
+            .collect())
+    }
+
+    /// 无结果时的"您是不是要找"候选：先取假名读音编辑距离≤1的词条（按距离排序），再补充前缀匹配的词条
+    /// 返回去重后的标题，最多DID_YOU_MEAN_LIMIT个
+    pub fn suggestions_for(&self, q: &str) -> Result<Vec<String>> {
+        let q = q.trim();
+        if q.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let fuzzy = self.search_by_kana_typo(q, 1.0, DID_YOU_MEAN_LIMIT)?
+            .into_iter()
+            .map(|ranked| ranked.entry.headword);
+        let prefixed = self.suggest(q, DID_YOU_MEAN_LIMIT)?
+            .into_iter()
+            .map(|entry| entry.headword);
+
+        let mut suggestions: Vec<String> = Vec::new();
+        for headword in fuzzy.chain(prefixed) {
+            if !suggestions.contains(&headword) {
+                suggestions.push(headword);
+            }
+        }
+        suggestions.truncate(DID_YOU_MEAN_LIMIT);
+        Ok(suggestions)
+    }
+
+    /// 输入提示（自动补全）：假名读音、汉字表记或标题以prefix开头的词条
+    /// 三个前缀范围查询取UNION去重，完全一致的排在最前，其余按标题长度排序
+    pub fn suggest(&self, prefix: &str, limit: usize) -> Result<Vec<ObunshaDictEntry>> {
+        let normalized = kana::normalize_headword(prefix);
+        let (upper_bound, normalized_upper_bound) =
+            match (Self::prefix_upper_bound(prefix), Self::prefix_upper_bound(&normalized)) {
+                (Some(upper_bound), Some(normalized_upper_bound)) => (upper_bound, normalized_upper_bound),
+                _ => return Ok(Vec::new()),
+            };
+
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT * FROM (
+                SELECT * FROM obunsha_kokugo_dict WHERE kana_reading >= ?1 AND kana_reading < ?2
+                UNION
+                SELECT * FROM obunsha_kokugo_dict WHERE kanji_writing >= ?1 AND kanji_writing < ?2
+                UNION
+                SELECT * FROM obunsha_kokugo_dict WHERE headword_normalized >= ?3 AND headword_normalized < ?4
+            )
+            ORDER BY (kana_reading = ?1 OR kanji_writing = ?1 OR headword_normalized = ?3) DESC,
+                     length(headword), headword
+            LIMIT ?5
+            "#,
+        )?;
+
+        let entry_iter = stmt.query_map(
+            params![prefix, upper_bound, normalized, normalized_upper_bound, limit as i64],
+            Self::entry_from_row,
+        )?;
+
+        let mut entries = Vec::new();
+        for entry in entry_iter {
+            entries.push(entry?);
+        }
+
+        Ok(entries)
+    }
+
+    /// 根据罗马字前缀提示词条（自动补全）
+    /// 罗马字先转换为假名前缀，末尾未输入完整的音节（如"sak"）会展开为所有可能的假名（さか、さき、さく…）
+    /// 同时匹配平假名和片假名读音，结果按读音长度排序
+    pub fn suggest_by_romaji_prefix(&self, romaji_prefix: &str, limit: usize) -> Result<Vec<ObunshaDictEntry>> {
+        let mut entries: Vec<ObunshaDictEntry> = Vec::new();
+        let mut seen_ids = std::collections::HashSet::new();
+
+        for hiragana in kana::romaji_prefix_candidates(romaji_prefix) {
+            for prefix in [kana::hiragana_to_katakana(&hiragana), hiragana] {
+                for entry in self.search_by_kana_prefix(&prefix, limit)? {
+                    if seen_ids.insert(entry.data_id.clone()) {
+                        entries.push(entry);
+                    }
+                }
+            }
+        }
+
+        entries.sort_by(|a, b| {
+            let a_kana = a.kana_reading.as_deref().unwrap_or("");
+            let b_kana = b.kana_reading.as_deref().unwrap_or("");
+            a_kana.chars().count().cmp(&b_kana.chars().count()).then_with(|| a_kana.cmp(b_kana))
+        });
+        entries.truncate(limit);
+        Ok(entries)
+    }
+
+    /// 根据假名前缀搜索（使用范围查询，可利用kana_reading索引）
+    pub fn search_by_kana_prefix(&self, prefix: &str, limit: usize) -> Result<Vec<ObunshaDictEntry>> {
+        let upper_bound = match Self::prefix_upper_bound(prefix) {
+            Some(upper_bound) => upper_bound,
+            None => return Ok(Vec::new()),
+        };
+
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT * FROM obunsha_kokugo_dict
+            WHERE kana_reading >= ?1 AND kana_reading < ?2
+            ORDER BY kana_reading
+            LIMIT ?3
+            "#,
+        )?;
+
+        let entry_iter = stmt.query_map(params![prefix, upper_bound, limit as i64], Self::entry_from_row)?;
+
+        let mut entries = Vec::new();
+        for entry in entry_iter {
+            entries.push(entry?);
+        }
+
+        Ok(entries)
+    }
+
+    /// 按英文对应词搜索（只匹配释义括号中的英文，如"（love）"），不区分大小写
+    /// 先用english_gloss列LIKE筛选，再按整词匹配，"love"不会命中"lovely"
+    pub fn search_by_english_gloss(&self, en: &str, limit: usize) -> Result<Vec<ObunshaDictEntry>> {
+        let term = en.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+        if term.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut stmt = self.conn.prepare(
+            "SELECT * FROM obunsha_kokugo_dict WHERE english_gloss LIKE ?1 ESCAPE '\\' ORDER BY length(headword), headword"
+        )?;
+        let entry_iter = stmt.query_map([format!("%{}%", Self::escape_like(&term))], Self::entry_from_row)?;
+
+        let padded_term = format!(" {} ", term);
+        let mut entries = Vec::new();
+        for entry in entry_iter {
+            let entry = entry?;
+            let matched = extract_english_glosses(&entry.definition_text)
+                .iter()
+                .any(|gloss| format!(" {} ", gloss).contains(&padded_term));
+            if matched {
+                entries.push(entry);
+                if entries.len() >= limit {
+                    break;
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// 汇总某个汉字在整个词典中的读音：汉字表记含该汉字的词条的kana_reading，去重后按读音排序
+    /// 如"生"返回 いきる、せい、なま 等（读音是整个词的读音，不是单个汉字的读音）
+    pub fn readings_for_kanji(&self, kanji: char) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT kana_reading FROM obunsha_kokugo_dict
+             WHERE kanji_writing LIKE ?1 ESCAPE '\\' AND kana_reading IS NOT NULL AND kana_reading != ''
+             ORDER BY kana_reading"
+        )?;
+        let rows = stmt.query_map([format!("%{}%", Self::escape_like(&kanji.to_string()))], |row| row.get::<_, String>(0))?;
+        rows.collect()
+    }
+
+    /// 查找释义中使用了某个汉字的词条（用于收集该字的用例），按id分页
+    /// LIKE '%字%' 无法使用索引，每次查询都要扫描全表，调用方应限制limit
+    pub fn entries_using_kanji_in_definition(&self, kanji: char, limit: usize, offset: usize) -> Result<Vec<ObunshaDictEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT * FROM obunsha_kokugo_dict WHERE definition_text LIKE ?1 ESCAPE '\\' ORDER BY id LIMIT ?2 OFFSET ?3"
+        )?;
+        let pattern = format!("%{}%", Self::escape_like(&kanji.to_string()));
+        let rows = stmt.query_map(params![pattern, limit as i64, offset as i64], Self::entry_from_row)?;
+        rows.collect()
+    }
+
+    /// 每日一词：将日期（如2024-05-01）的SHA-256对词条数取模，按id顺序取该位置的词条
+    /// 每个词条被选中的概率相同，不受id空缺影响；同一日期且数据不变时总是返回同一个词条；数据库为空时返回None
+    pub fn entry_of_the_day(&self, date: &str) -> Result<Option<ObunshaDictEntry>> {
+        let count: i64 = self.conn.query_row("SELECT COUNT(*) FROM obunsha_kokugo_dict", [], |row| row.get(0))?;
+        if count == 0 {
+            return Ok(None);
+        }
+
+        let digest = Sha256::digest(date.trim().as_bytes());
+        let mut prefix = [0u8; 8];
+        prefix.copy_from_slice(&digest[..8]);
+        let offset = (u64::from_be_bytes(prefix) % count as u64) as i64;
+
+        self.conn
+            .query_row(
+                "SELECT * FROM obunsha_kokugo_dict ORDER BY id LIMIT 1 OFFSET ?1",
+                [offset],
+                Self::entry_from_row,
+            )
+            .optional()
+    }
+
+    /// 获取某个读音前后的词条，按五十音顺序（sort_key、id）排列，浊音排在对应清音之后
+    /// 查询读音同样换算为排序键，平假名与片假名等价
+    /// 词典开头或结尾附近返回的词条少于before/after
+    pub fn entries_window(&self, headword: &str, before: usize, after: usize) -> Result<EntryWindow> {
+        let at = headword.trim();
+        let key = kana::sort_key(at);
+
+        let mut before_entries = {
+            let mut stmt = self.conn.prepare(
+                "SELECT * FROM obunsha_kokugo_dict WHERE sort_key < ?1 ORDER BY sort_key DESC, id DESC LIMIT ?2"
+            )?;
+            let rows = stmt.query_map(params![key, before as i64], Self::entry_from_row)?;
+            rows.collect::<Result<Vec<_>>>()?
+        };
+        before_entries.reverse();
+
+        let mut after_entries = {
+            let mut stmt = self.conn.prepare(
+                "SELECT * FROM obunsha_kokugo_dict WHERE sort_key >= ?1 ORDER BY sort_key, id LIMIT ?2"
+            )?;
+            let rows = stmt.query_map(params![key, after as i64 + 1], Self::entry_from_row)?;
+            rows.collect::<Result<Vec<_>>>()?
+        };
+
+        let is_target = |entry: &ObunshaDictEntry| {
+            Self::sort_key_column(&entry.headword, entry.kana_reading.as_deref()) == key
+        };
+        let target = if after_entries.first().is_some_and(is_target) {
+            Some(after_entries.remove(0))
+        } else {
+            None
+        };
+        after_entries.truncate(after);
+
+        Ok(EntryWindow {
+            before: before_entries,
+            target,
+            after: after_entries,
+        })
+    }
+
+    /// 按汉字词干搜索：查询和汉字表记都去掉词尾送假名后比较，"動く"能找到"動"，"美"能找到"美しい"
+    /// 多重表记（動く・働く）中任一表记的词干相同即匹配，结果按标题长度排序
+    pub fn search_by_kanji_stem(&self, query: &str, limit: usize) -> Result<Vec<ObunshaDictEntry>> {
+        let stem = kana::strip_okurigana(query);
+        if stem.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut stmt = self.conn.prepare(
+            "SELECT * FROM obunsha_kokugo_dict WHERE kanji_writing LIKE ?1 ESCAPE '\\' ORDER BY length(headword), headword"
+        )?;
+        let entry_iter = stmt.query_map([format!("%{}%", Self::escape_like(&stem))], Self::entry_from_row)?;
+
+        let mut entries = Vec::new();
+        for entry in entry_iter {
+            let entry = entry?;
+            let matched = entry
+                .kanji_writing
+                .as_deref()
+                .is_some_and(|writing| split_kanji_variants(writing).iter().any(|part| kana::strip_okurigana(part) == stem));
+            if matched {
+                entries.push(entry);
+                if entries.len() >= limit {
+                    break;
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// 按拍模式搜索读音：`_` 匹配任意一拍，`*` 匹配任意多拍，拗音（きゃ）算一拍
+    /// 模式开头的固定假名用范围查询缩小候选（平假名、片假名各查一次），
+    /// 不含 `*` 时再按字数过滤（n拍的读音有n到2n个字），最后在应用层按拍匹配
+    pub fn search_by_kana_pattern(&self, pattern: &str, limit: usize) -> Result<Vec<ObunshaDictEntry>> {
+        let pattern = kana::katakana_to_hiragana(pattern.trim());
+        let fixed_prefix: String = pattern.chars().take_while(|ch| *ch != '_' && *ch != '*').collect();
+        let (min_len, max_len) = if pattern.contains('*') {
+            (0, i64::MAX)
+        } else {
+            let mora_count = kana::split_mora(&pattern).len() as i64;
+            (mora_count, mora_count * 2)
+        };
+
+        let mut conditions = vec!["length(kana_reading) BETWEEN ?1 AND ?2".to_string()];
+        let mut values: Vec<rusqlite::types::Value> = vec![min_len.into(), max_len.into()];
+        if let Some(upper_bound) = Self::prefix_upper_bound(&fixed_prefix) {
+            let katakana_prefix = kana::hiragana_to_katakana(&fixed_prefix);
+            let katakana_upper_bound = Self::prefix_upper_bound(&katakana_prefix).unwrap_or_default();
+            conditions.push(
+                "((kana_reading >= ?3 AND kana_reading < ?4) OR (kana_reading >= ?5 AND kana_reading < ?6))".to_string(),
+            );
+            values.extend([fixed_prefix.into(), upper_bound.into(), katakana_prefix.into(), katakana_upper_bound.into()]);
+        }
+
+        let sql = format!(
+            "SELECT * FROM obunsha_kokugo_dict WHERE {} ORDER BY kana_reading, id",
+            conditions.join(" AND ")
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
+        let entry_iter = stmt.query_map(rusqlite::params_from_iter(values), Self::entry_from_row)?;
+
+        let mut entries = Vec::new();
+        for entry in entry_iter {
+            let entry = entry?;
+            let matched = entry
+                .kana_reading
+                .as_deref()
+                .is_some_and(|reading| kana::matches_mora_pattern(reading, &pattern));
+            if matched {
+                entries.push(entry);
+                if entries.len() >= limit {
+                    break;
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// 根据标题前缀搜索（与规范化标题比较，等价于 LIKE 'q%'，但使用范围查询以利用索引）
+    /// 标题以假名开头，因此假名前缀同样可以命中
+    pub fn search_headword_prefix(&self, prefix: &str, limit: usize) -> Result<Vec<ObunshaDictEntry>> {
+        let prefix = kana::normalize_headword(prefix);
+        let upper_bound = match Self::prefix_upper_bound(&prefix) {
+            Some(upper_bound) => upper_bound,
+            None => return Ok(Vec::new()),
+        };
+
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT * FROM obunsha_kokugo_dict
+            WHERE headword_normalized >= ?1 AND headword_normalized < ?2
+            ORDER BY headword_normalized
+            LIMIT ?3
+            "#,
+        )?;
+
+        let entry_iter = stmt.query_map(params![prefix, upper_bound, limit as i64], Self::entry_from_row)?;
+
+        let mut entries = Vec::new();
+        for entry in entry_iter {
+            entries.push(entry?);
+        }
+
+        Ok(entries)
+    }
+
+    /// 根据后缀搜索（LIKE '%q'，如所有以"的"结尾的词）
+    /// 标题形如"かがくてき【科学的】"，因此同时比较标题、汉字表记和假名读音；无法利用索引，需要全表扫描
+    pub fn search_headword_suffix(&self, suffix: &str, limit: usize) -> Result<Vec<ObunshaDictEntry>> {
+        let suffix = kana::normalize_headword(suffix);
+        if suffix.is_empty() {
+            return Ok(Vec::new());
+        }
+        let pattern = format!("%{}", Self::escape_like(&suffix));
+
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT * FROM obunsha_kokugo_dict
+            WHERE headword_normalized LIKE ?1 ESCAPE '\'
+               OR kanji_writing LIKE ?1 ESCAPE '\'
+               OR kana_reading LIKE ?1 ESCAPE '\'
+            ORDER BY headword
+            LIMIT ?2
+            "#,
+        )?;
+
+        let entry_iter = stmt.query_map(params![pattern, limit as i64], Self::entry_from_row)?;
+
+        let mut entries = Vec::new();
+        for entry in entry_iter {
+            entries.push(entry?);
+        }
+
+        Ok(entries)
+    }
+
+    /// 转义LIKE模式中的通配符
+    fn escape_like(text: &str) -> String {
+        let mut escaped = String::with_capacity(text.len());
+        for ch in text.chars() {
+            if matches!(ch, '\\' | '%' | '_') {
+                escaped.push('\\');
+            }
+            escaped.push(ch);
+        }
+        escaped
+    }
+
+    /// 前缀范围查询的上界：将最后一个字符加一
+    fn prefix_upper_bound(prefix: &str) -> Option<String> {
+        let mut chars: Vec<char> = prefix.chars().collect();
+        let last = chars.pop()?;
+        chars.push(char::from_u32(last as u32 + 1)?);
+        Some(chars.into_iter().collect())
+    }
+
+    /// 根据标题精确查询词条（与规范化标题比较）
+    pub fn find_by_headword(&self, headword: &str) -> Result<Vec<ObunshaDictEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT * FROM obunsha_kokugo_dict WHERE headword_normalized = ?1 ORDER BY id"
+        )?;
+
+        let entry_iter = stmt.query_map([kana::normalize_headword(headword)], Self::entry_from_row)?;
+
+        let mut entries = Vec::new();
+        for entry in entry_iter {
+            entries.push(entry?);
+        }
+
+        Ok(entries)
+    }
+
+    /// 按data_id列表批量查询词条
+    /// 按请求的顺序返回，不存在的data_id被忽略；id较多时分批查询以避免超出参数上限
+    pub fn get_by_data_ids(&self, ids: &[String]) -> Result<Vec<ObunshaDictEntry>> {
+        let mut found: std::collections::HashMap<String, ObunshaDictEntry> = std::collections::HashMap::new();
+
+        for chunk in ids.chunks(MAX_SQL_PARAMS) {
+            let placeholders = vec!["?"; chunk.len()].join(", ");
+            let sql = format!("SELECT * FROM obunsha_kokugo_dict WHERE data_id IN ({})", placeholders);
+            let mut stmt = self.conn.prepare(&sql)?;
+            let entry_iter = stmt.query_map(rusqlite::params_from_iter(chunk.iter()), Self::entry_from_row)?;
+            for entry in entry_iter {
+                let entry = entry?;
+                found.insert(entry.data_id.clone(), entry);
+            }
+        }
+
+        let mut entries = Vec::new();
+        for id in ids {
+            // 重复的id只返回一次
+            if let Some(entry) = found.remove(id) {
+                entries.push(entry);
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// 返回给定id中释义含例句（example_count > 0）的id；id较多时分批查询
+    pub fn ids_with_examples(&self, ids: &[i64]) -> Result<std::collections::HashSet<i64>> {
+        let mut found = std::collections::HashSet::new();
+        for chunk in ids.chunks(MAX_SQL_PARAMS) {
+            let placeholders = vec!["?"; chunk.len()].join(", ");
+            let sql = format!(
+                "SELECT id FROM obunsha_kokugo_dict WHERE example_count > 0 AND id IN ({})",
+                placeholders
+            );
+            let mut stmt = self.conn.prepare(&sql)?;
+            let rows = stmt.query_map(rusqlite::params_from_iter(chunk.iter()), |row| row.get(0))?;
+            for id in rows {
+                found.insert(id?);
+            }
+        }
+        Ok(found)
+    }
+
+    /// 批量写入重定向（已存在的源会被覆盖）
+    pub fn insert_redirects(&self, redirects: &[(String, String)]) -> Result<usize> {
+        self.ensure_writable()?;
+        let tx = self.conn.unchecked_transaction()?;
+
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO redirects (source, target) VALUES (?1, ?2)"
+            )?;
+
+            for (source, target) in redirects {
+                stmt.execute(params![source, target])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(redirects.len())
+    }
+
+    /// 从清理时写出的TSV文件导入重定向（每行：重定向源\t目标词条）
+    pub fn import_redirects(&self, redirects_path: &str) -> Result<usize, Box<dyn std::error::Error>> {
+        self.ensure_writable()?;
+        let content = std::fs::read_to_string(redirects_path)?;
+        Ok(self.insert_redirects(&Self::parse_redirects_tsv(&content))?)
+    }
+
+    /// 解析"源\t目标"格式的重定向列表
+    fn parse_redirects_tsv(content: &str) -> Vec<(String, String)> {
+        content
+            .lines()
+            .filter_map(|line| line.split_once('\t'))
+            .map(|(source, target)| (source.to_string(), target.to_string()))
+            .collect()
+    }
+
+    /// 沿重定向链查找最终目标词条
+    /// 最多跟随 `max_depth` 次；词条没有重定向时返回None，遇到循环（如A→B→A）时返回None，
+    /// 超过深度限制时返回已到达的最后一个目标
+    pub fn resolve_redirect_chain(&self, headword: &str, max_depth: usize) -> Result<Option<String>> {
+        if !self.table_exists("redirects")? {
+            return Ok(None);
+        }
+
+        let mut stmt = self.conn.prepare("SELECT target FROM redirects WHERE source = ?1")?;
+        let mut visited = std::collections::HashSet::new();
+        visited.insert(headword.to_string());
+
+        let mut current = headword.to_string();
+        let mut resolved = None;
+        for _ in 0..max_depth {
+            let next: Option<String> = stmt
+                .query_row([current.as_str()], |row| row.get(0))
+                .optional()?;
+
+            match next {
+                Some(target) => {
+                    if !visited.insert(target.clone()) {
+                        println!("⚠️  检测到重定向循环: {} -> {}", current, target);
+                        return Ok(None);
+                    }
+                    resolved = Some(target.clone());
+                    current = target;
+                }
+                None => break,
+            }
+        }
+
+        Ok(resolved)
+    }
+
+    /// 查找无法到达词条的重定向：沿重定向链（最多max_depth次，与搜索时相同）得到的目标标题没有对应词条，
+    /// 或重定向链成环。返回(源, 直接目标)，按源排序
+    pub fn find_orphan_redirects(&self, max_depth: usize) -> Result<Vec<(String, String)>> {
+        if !self.table_exists("redirects")? {
+            return Ok(Vec::new());
+        }
+
+        let redirects: Vec<(String, String)> = {
+            let mut stmt = self.conn.prepare("SELECT source, target FROM redirects ORDER BY source")?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<Result<Vec<_>>>()?
+        };
+
+        let mut exists = self.conn.prepare("SELECT 1 FROM obunsha_kokugo_dict WHERE headword_normalized = ?1")?;
+        let mut orphans = Vec::new();
+        for (source, target) in redirects {
+            let reachable = match self.resolve_redirect_chain(&source, max_depth)? {
+                Some(resolved) => exists.exists([kana::normalize_headword(&resolved)])?,
+                None => false,
+            };
+            if !reachable {
+                orphans.push((source, target));
+            }
+        }
+        Ok(orphans)
+    }
+
+    /// 按id顺序读取id大于after_id的紧凑格式词条（列见COMPACT_COLUMNS），用于整库传输
+    /// 返回(id, 词条)，下一页从最后一个id继续；按主键定位，翻到后面的页也不需要扫描前面的行
+    pub fn compact_entries(&self, limit: usize, after_id: i64) -> Result<Vec<(i64, CompactEntry)>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT id, data_id, headword, kana_reading, kanji_writing, part_of_speech, definition_text
+            FROM obunsha_kokugo_dict
+            WHERE id > ?2
+            ORDER BY id
+            LIMIT ?1
+            "#,
+        )?;
+        let rows = stmt.query_map(params![limit as i64, after_id], |row| {
+            Ok((row.get(0)?, (row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?, row.get(6)?)))
+        })?;
+        rows.collect()
+    }
+
+    /// 获取指定时间之后修改过的词条，按updated_at排序（用于增量同步）
+    /// 时间戳为ISO-8601格式，如 2024-01-01T00:00:00Z
+    pub fn entries_modified_since(&self, iso_timestamp: &str, limit: usize, offset: usize) -> Result<Vec<ObunshaDictEntry>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT * FROM obunsha_kokugo_dict
+            WHERE updated_at > datetime(?1)
+            ORDER BY updated_at, id
+            LIMIT ?2 OFFSET ?3
+            "#,
+        )?;
+
+        let entry_iter = stmt.query_map(
+            params![iso_timestamp, limit as i64, offset as i64],
+            Self::entry_from_row,
+        )?;
+
+        let mut entries = Vec::new();
+        for entry in entry_iter {
+            entries.push(entry?);
+        }
+
+        Ok(entries)
+    }
+
+    /// 获取在指定时间段内创建的词条（包含两端），按created_at排序，用于核对某次导入写入的词条
+    /// 时间戳为ISO-8601格式，按SQLite的datetime比较
+    pub fn entries_created_between(&self, from: &str, to: &str, limit: usize, offset: usize) -> Result<Vec<ObunshaDictEntry>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT * FROM obunsha_kokugo_dict
+            WHERE created_at BETWEEN datetime(?1) AND datetime(?2)
+            ORDER BY created_at, id
+            LIMIT ?3 OFFSET ?4
+            "#,
+        )?;
+
+        let entry_iter = stmt.query_map(
+            params![from, to, limit as i64, offset as i64],
+            Self::entry_from_row,
+        )?;
+
+        let mut entries = Vec::new();
+        for entry in entry_iter {
+            entries.push(entry?);
+        }
+
+        Ok(entries)
+    }
+
+    /// 将ISO-8601时间戳转换为SQLite的datetime格式，无法解析时返回None
+    pub fn normalize_timestamp(&self, iso_timestamp: &str) -> Result<Option<String>> {
+        self.conn.query_row("SELECT datetime(?1)", [iso_timestamp], |row| row.get(0))
+    }
+
+    /// 在释义中搜索关键词，按相关度排序
+    /// 使用FTS5的bm25()计算相关度，关键词出现在第一义项（❶）中的词条会被加权提升
+    pub fn search_in_definitions(&self, keyword: &str, limit: usize) -> Result<Vec<RankedEntry>> {
+        let keyword = keyword.trim();
+
+        // trigram分词器无法匹配少于3个字符的查询，此时退回LIKE扫描
+        if keyword.chars().count() < 3 || !self.has_definition_index()? {
+            return self.search_in_definitions_like(keyword, limit);
+        }
+
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT d.*, bm25(obunsha_definitions_fts) AS score
+            FROM obunsha_definitions_fts
+            JOIN obunsha_kokugo_dict d ON d.id = obunsha_definitions_fts.rowid
+            WHERE obunsha_definitions_fts MATCH ?1
+            ORDER BY score
+            LIMIT ?2
+            "#,
+        )?;
+
+        // 作为短语查询，避免关键词中的符号被解释为FTS语法
+        // 多取一些结果，使bm25稍低但关键词在第一义项中的词条能在加权后进入前limit个
+        let fts_query = format!("\"{}\"", keyword.replace('"', "\"\""));
+        let window = limit.saturating_mul(DEFINITION_RERANK_FACTOR) as i64;
+        let entry_iter = stmt.query_map(params![fts_query, window], |row| {
+            let entry = Self::entry_from_row(row)?;
+            let score: f64 = row.get("score")?;
+            Ok((entry, score))
+        })?;
+
+        let mut ranked = Vec::new();
+        for item in entry_iter {
+            let (entry, score) = item?;
+            // bm25()越小越相关，取反后作为得分
+            let rank = -score * Self::sense_boost(&entry.definition_text, keyword);
+            ranked.push(RankedEntry::with_rank(entry, rank));
+        }
+
+        Self::sort_by_rank(&mut ranked);
+        ranked.truncate(limit);
+        Ok(ranked)
+    }
+
+    /// 统计某种搜索的匹配总数（不受limit限制），用于告知客户端结果是否被截断
+    /// 只支持能直接用COUNT(*)统计的类型（definition、prefix、suffix），需要在应用层过滤的类型返回None
+    pub fn count_search_results(&self, search_type: &str, query: &str) -> Result<Option<i64>> {
+        let query = query.trim();
+        match search_type {
+            "definition" => {
+                if query.chars().count() >= 3 && self.has_definition_index()? {
+                    let fts_query = format!("\"{}\"", query.replace('"', "\"\""));
+                    self.conn
+                        .query_row(
+                            "SELECT COUNT(*) FROM obunsha_definitions_fts WHERE obunsha_definitions_fts MATCH ?1",
+                            [fts_query],
+                            |row| row.get(0),
+                        )
+                        .map(Some)
+                } else {
+                    self.conn
+                        .query_row(
+                            "SELECT COUNT(*) FROM obunsha_kokugo_dict WHERE definition_text LIKE ?1 ESCAPE '\\'",
+                            [format!("%{}%", Self::escape_like(query))],
+                            |row| row.get(0),
+                        )
+                        .map(Some)
+                }
+            }
+            "prefix" => {
+                let prefix = kana::normalize_headword(query);
+                let Some(upper_bound) = Self::prefix_upper_bound(&prefix) else {
+                    return Ok(Some(0));
+                };
+                self.conn
+                    .query_row(
+                        "SELECT COUNT(*) FROM obunsha_kokugo_dict WHERE headword_normalized >= ?1 AND headword_normalized < ?2",
+                        params![prefix, upper_bound],
+                        |row| row.get(0),
+                    )
+                    .map(Some)
+            }
+            "suffix" => {
+                let suffix = kana::normalize_headword(query);
+                if suffix.is_empty() {
+                    return Ok(Some(0));
+                }
+                self.conn
+                    .query_row(
+                        r#"
+                        SELECT COUNT(*) FROM obunsha_kokugo_dict
+                        WHERE headword_normalized LIKE ?1 ESCAPE '\'
+                           OR kanji_writing LIKE ?1 ESCAPE '\'
+                           OR kana_reading LIKE ?1 ESCAPE '\'
+                        "#,
+                        [format!("%{}", Self::escape_like(&suffix))],
+                        |row| row.get(0),
+                    )
+                    .map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// 释义搜索的LIKE实现，按关键词出现次数计算得分
+    fn search_in_definitions_like(&self, keyword: &str, limit: usize) -> Result<Vec<RankedEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT * FROM obunsha_kokugo_dict WHERE definition_text LIKE ?1 ESCAPE '\\'"
+        )?;
+
+        let entry_iter = stmt.query_map([format!("%{}%", Self::escape_like(keyword))], Self::entry_from_row)?;
+
+        let lowered_keyword = keyword.to_lowercase();
+        let mut ranked = Vec::new();
+        for entry in entry_iter {
+            let entry = entry?;
+            let occurrences = entry.definition_text.to_lowercase().matches(&lowered_keyword).count().max(1);
+            let rank = occurrences as f64 * Self::sense_boost(&entry.definition_text, keyword);
+            ranked.push(RankedEntry::with_rank(entry, rank));
+        }
+
+        Self::sort_by_rank(&mut ranked);
+        ranked.truncate(limit);
+        Ok(ranked)
+    }
+
+    /// 关键词出现在第一义项（❷之前的部分）时的加权系数
+    fn sense_boost(definition_text: &str, keyword: &str) -> f64 {
+        let first_sense = definition_text.split('❷').next().unwrap_or("");
+        if first_sense.contains(keyword) {
+            1.5
+        } else {
+            1.0
+        }
+    }
+
+    /// 按得分从高到低排序
+    fn sort_by_rank(ranked: &mut [RankedEntry]) {
+        ranked.sort_by(|a, b| {
+            b.rank.partial_cmp(&a.rank).unwrap_or(std::cmp::Ordering::Equal)
+        });
+    }
+
+    /// 检查释义全文索引是否可用（旧数据库可能没有；SQLite不支持FTS5时即使表存在也无法查询）
+    fn has_definition_index(&self) -> Result<bool> {
+        Ok(self.table_exists("obunsha_definitions_fts")? && self.fts5_available())
+    }
+
+    /// 检查表是否存在
+    fn table_exists(&self, table_name: &str) -> Result<bool> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1",
+            [table_name],
+            |row| row.get(0)
+        )?;
+        Ok(count > 0)
+    }
+
+    /// 根据主表重建释义全文索引，没有可用的全文索引时不做任何事
+    pub fn rebuild_definition_index(&self) -> Result<()> {
+        self.ensure_writable()?;
+        if !self.has_definition_index()? {
+            return Ok(());
+        }
+        self.conn.execute(
+            "INSERT INTO obunsha_definitions_fts(obunsha_definitions_fts) VALUES('rebuild')",
+            [],
+        )?;
+        Ok(())
+    }
+
+    /// 将查询结果行映射为词条（按列名读取，不依赖列顺序）
+    fn entry_from_row(row: &rusqlite::Row) -> Result<ObunshaDictEntry> {
+        let kanji_writing: Option<String> = row.get("kanji_writing")?;
+        Ok(ObunshaDictEntry {
+            id: Some(row.get("id")?),
+            data_id: row.get("data_id")?,
+            data_type: row.get("data_type")?,
+            headword: row.get("headword")?,
+            kana_reading: row.get("kana_reading")?,
+            kanji_writing: kanji_writing.clone(),
+            kanji_variants: kanji_writing.as_deref().map(split_kanji_variants).unwrap_or_default(),
+            expansion: row.get("expansion")?,
+            part_of_speech: row.get("part_of_speech")?,
+            conjugation: row.get("conjugation")?,
+            definition_html: row.get("definition_html")?,
+            definition_text: row.get("definition_text")?,
+            raw_mdx_content: row.get("raw_mdx_content")?,
+        })
+    }
+
+    /// 按order顺序惰性遍历全部词条，不会把整张表读入内存
+    /// 供导出、统计等需要全表扫描的功能共用
+    pub fn iter_entries(&self, order: ExportOrder) -> Result<impl Iterator<Item = Result<ObunshaDictEntry>> + '_> {
+        Ok(self.entries_updated_after(order, None))
+    }
+
+    /// 同iter_entries；updated_after不为None时只遍历该时间（SQLite datetime格式）之后更新的词条
+    fn entries_updated_after(&self, order: ExportOrder, updated_after: Option<String>) -> EntryIter<'_> {
+        EntryIter {
+            db: self,
+            order,
+            updated_after,
+            buffer: std::collections::VecDeque::new(),
+            last_key: None,
+            finished: false,
+        }
+    }
+
+    /// 按假名读音顺序逐条处理全部词条（导出用，不把整张表读入内存）
+    pub fn for_each_entry<F>(&self, mut f: F) -> Result<()>
+    where
+        F: FnMut(ObunshaDictEntry),
+    {
+        let mut stmt = self.conn.prepare(
+            "SELECT * FROM obunsha_kokugo_dict ORDER BY kana_reading, id"
+        )?;
+        let entry_iter = stmt.query_map([], Self::entry_from_row)?;
+        for entry in entry_iter {
+            f(entry?);
+        }
+        Ok(())
+    }
+
+    /// 数据库结构版本（PRAGMA user_version），未初始化的旧数据库为0
+    pub fn schema_version(&self) -> Result<i64> {
+        self.conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+    }
+
+    /// 与新版本数据库按data_id比较
+    /// 两边都按data_id顺序流式读取后做归并，不需要把整张表读入内存
+    pub fn diff_against(&self, newer: &ObunshaDictDatabase) -> Result<DatabaseDiff> {
+        const SQL: &str = "SELECT data_id, headword, definition_html FROM obunsha_kokugo_dict ORDER BY data_id";
+
+        let mut old_stmt = self.conn.prepare(SQL)?;
+        let mut new_stmt = newer.conn.prepare(SQL)?;
+        let read_row = |row: &rusqlite::Row| -> Result<(String, String, String)> {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        };
+        let mut old_rows = old_stmt.query_map([], read_row)?.peekable();
+        let mut new_rows = new_stmt.query_map([], read_row)?.peekable();
+
+        let mut diff = DatabaseDiff::default();
+        loop {
+            let ordering = match (old_rows.peek(), new_rows.peek()) {
+                (None, None) => break,
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (Some(Ok(old)), Some(Ok(new))) => old.0.cmp(&new.0),
+                // 读取出错时交给下面的next()?返回
+                (Some(Err(_)), _) => std::cmp::Ordering::Less,
+                (_, Some(Err(_))) => std::cmp::Ordering::Greater,
+            };
+
+            match ordering {
+                std::cmp::Ordering::Less => {
+                    if let Some(row) = old_rows.next() {
+                        let (data_id, headword, _) = row?;
+                        diff.removed.push(DiffEntry { data_id, headword });
+                    }
+                }
+                std::cmp::Ordering::Greater => {
+                    if let Some(row) = new_rows.next() {
+                        let (data_id, headword, _) = row?;
+                        diff.added.push(DiffEntry { data_id, headword });
+                    }
+                }
+                std::cmp::Ordering::Equal => {
+                    if let (Some(old), Some(new)) = (old_rows.next(), new_rows.next()) {
+                        let (_, _, old_html) = old?;
+                        let (data_id, headword, new_html) = new?;
+                        if old_html == new_html {
+                            diff.unchanged += 1;
+                        } else {
+                            diff.modified.push(DiffEntry { data_id, headword });
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(diff)
+    }
+
+    /// 获取表的统计信息
+    pub fn get_stats(&self) -> Result<(i64, i64)> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM obunsha_kokugo_dict",
+            [],
+            |row| row.get(0)
+        )?;
+
+        let unique_headwords: i64 = self.conn.query_row(
+            "SELECT COUNT(DISTINCT headword) FROM obunsha_kokugo_dict",
+            [],
+            |row| row.get(0)
+        )?;
+
+        Ok((count, unique_headwords))
+    }
+
+    /// 统计只有指向另一个词的释义的词条数（见 ObunshaDictEntry::is_pure_redirect）
+    /// 先用SQL筛出含箭头的短释义，再逐条判断
+    pub fn count_pure_redirects(&self) -> Result<i64> {
+        let mut stmt = self.conn.prepare(
+            "SELECT definition_text FROM obunsha_kokugo_dict
+             WHERE length(trim(definition_text, ' ' || char(9, 10, 13, 12288))) <= ?1
+               AND (instr(definition_text, '→') > 0 OR instr(definition_text, '⇒') > 0)",
+        )?;
+        let mut rows = stmt.query([PURE_REDIRECT_MAX_CHARS as i64])?;
+        let mut count = 0;
+        while let Some(row) = rows.next()? {
+            let text: String = row.get(0)?;
+            if is_pure_redirect_text(&text) {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// 清空所有词条的raw_mdx_content以缩小发布用的数据库（之后应执行compact回收空间）
+    /// 有损操作：清空后reparse_all无法再从原始内容重新解析，只能重新导入恢复。返回清空的行数
+    pub fn strip_raw(&self) -> Result<usize> {
+        self.ensure_writable()?;
+        let stripped = self.conn.execute(
+            "UPDATE obunsha_kokugo_dict SET raw_mdx_content = '' WHERE raw_mdx_content != ''",
+            [],
+        )?;
+        self.set_metadata("raw_mdx_content", "stripped")?;
+        Ok(stripped)
+    }
+
+    /// 列出所有不同的词性及其词条数，按词条数从多到少排列（相同时按词性排序）
+    /// 词性为原始文本（如"自五"、"（名）"），空值和空字符串不计入
+    pub fn list_parts_of_speech(&self) -> Result<Vec<(String, i64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT part_of_speech, COUNT(*) AS count FROM obunsha_kokugo_dict
+             WHERE part_of_speech IS NOT NULL AND part_of_speech != ''
+             GROUP BY part_of_speech
+             ORDER BY count DESC, part_of_speech"
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    /// 统计各文本列占用的字节数，用于判断打包时可以去掉哪些列（如raw_mdx_content）
+    /// LENGTH对TEXT返回字符数，因此先转为BLOB按字节计算
+    pub fn storage_stats(&self) -> Result<StorageStats> {
+        let sums = STORAGE_COLUMNS
+            .iter()
+            .map(|column| format!("COALESCE(SUM(LENGTH(CAST({} AS BLOB))), 0)", column))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut columns = self.conn.query_row(
+            &format!("SELECT {} FROM obunsha_kokugo_dict", sums),
+            [],
+            |row| {
+                STORAGE_COLUMNS
+                    .iter()
+                    .enumerate()
+                    .map(|(i, column)| Ok(ColumnStorage { column: column.to_string(), bytes: row.get(i)? }))
+                    .collect::<Result<Vec<_>>>()
+            },
+        )?;
+        columns.sort_by_key(|column| std::cmp::Reverse(column.bytes));
+
+        let database_bytes = self.conn.query_row(
+            "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
+            [],
+            |row| row.get(0),
+        )?;
+
+        Ok(StorageStats { columns, database_bytes })
+    }
+
+    /// 按标题字符数统计词条数，返回按长度升序的(字符数, 词条数)
+    /// 只计【】之前的部分（如「あい【愛】」计为2），不含汉字表记
+    pub fn headword_length_histogram(&self) -> Result<Vec<(usize, i64)>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT LENGTH(CASE WHEN INSTR(headword, '【') > 0 THEN SUBSTR(headword, 1, INSTR(headword, '【') - 1) ELSE headword END),
+                   COUNT(*)
+            FROM obunsha_kokugo_dict
+            GROUP BY 1
+            ORDER BY 1
+            "#,
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)? as usize, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    /// 按假名读音的拍数（见kana::split_mora）统计词条数，返回按拍数升序的(拍数, 词条数)
+    /// 没有假名读音的词条不计入
+    pub fn reading_mora_histogram(&self) -> Result<Vec<(usize, i64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT kana_reading FROM obunsha_kokugo_dict WHERE kana_reading IS NOT NULL AND kana_reading != ''"
+        )?;
+        let mut buckets: BTreeMap<usize, i64> = BTreeMap::new();
+        for reading in stmt.query_map([], |row| row.get::<_, String>(0))? {
+            *buckets.entry(kana::split_mora(&reading?).len()).or_insert(0) += 1;
+        }
+        Ok(buckets.into_iter().collect())
+    }
+
+    /// 压缩数据库：VACUUM回收反复 INSERT OR REPLACE 导入留下的碎片空间，
+    /// ANALYZE刷新统计信息供查询规划器使用（如 search_by_kanji_smart 的LIKE查询），最后执行 PRAGMA optimize
+    pub fn compact(&self) -> Result<()> {
+        self.ensure_writable()?;
+        self.conn.execute_batch("VACUUM; ANALYZE; PRAGMA optimize;")
+    }
+
+    /// 重建索引：REINDEX主表和检索形式表的所有索引，存在释义全文索引时一并rebuild，最后ANALYZE
+    /// 与compact不同，不回收空间，用于大批量导入或手工修改后恢复查询规划器的状态
+    pub fn rebuild_indexes(&self) -> Result<()> {
+        self.ensure_writable()?;
+        self.conn.execute_batch("REINDEX obunsha_kokugo_dict;")?;
+        if self.table_exists("search_all")? {
+            self.conn.execute_batch("REINDEX search_all;")?;
+        }
+        if self.has_definition_index()? {
+            self.rebuild_definition_index()?;
+        }
+        self.conn.execute_batch("ANALYZE;")
+    }
+
+    /// 校验数据库完整性
+    /// 依次检查SQLite文件完整性、必需列是否齐全、data_id是否唯一，返回是否全部通过
+    pub fn integrity_check(&self) -> Result<bool> {
+        const REQUIRED_COLUMNS: [&str; 13] = [
+            "id", "data_id", "data_type", "headword", "kana_reading", "kanji_writing",
+            "part_of_speech", "conjugation", "definition_html", "definition_text",
+            "raw_mdx_content", "created_at", "updated_at",
+        ];
+
+        let mut passed = true;
+
+        // 1. SQLite文件完整性
+        let mut stmt = self.conn.prepare("PRAGMA integrity_check")?;
+        let messages = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<String>>>()?;
+        if messages.len() == 1 && messages[0] == "ok" {
+            println!("✅ PRAGMA integrity_check 通过");
+        } else {
+            passed = false;
+            println!("❌ PRAGMA integrity_check 失败:");
+            for message in messages.iter().take(10) {
+                println!("   - {}", message);
+            }
+        }
+
+        // 2. 表结构
+        if !self.table_exists("obunsha_kokugo_dict")? {
+            println!("❌ 缺少表 obunsha_kokugo_dict");
+            return Ok(false);
+        }
+
+        let mut stmt = self.conn.prepare("PRAGMA table_info(obunsha_kokugo_dict)")?;
+        let columns = stmt
+            .query_map([], |row| row.get::<_, String>(1))?
+            .collect::<Result<std::collections::HashSet<String>>>()?;
+        let missing: Vec<&str> = REQUIRED_COLUMNS
+            .iter()
+            .copied()
+            .filter(|column| !columns.contains(*column))
+            .collect();
+        if missing.is_empty() {
+            println!("✅ 表结构完整（{} 个必需列）", REQUIRED_COLUMNS.len());
+        } else {
+            passed = false;
+            println!("❌ 缺少列: {}", missing.join(", "));
+        }
+
+        // 3. 词条数与data_id唯一性
+        let (count, distinct_ids): (i64, i64) = self.conn.query_row(
+            "SELECT COUNT(*), COUNT(DISTINCT data_id) FROM obunsha_kokugo_dict",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?))
+        )?;
+        if count == 0 {
+            passed = false;
+            println!("❌ 词条表为空");
+        } else if count != distinct_ids {
+            passed = false;
+            println!("❌ 词条数 {} 与唯一data_id数 {} 不一致", count, distinct_ids);
+        } else {
+            println!("✅ 共 {} 条词条，data_id 均唯一", count);
+        }
+
+        // 与导入时记录的词条数对比（仅提示）
+        if let Some(imported) = self.get_metadata()?.get("imported_entries")
+            && imported.parse::<i64>().ok() != Some(count)
+        {
+            println!("⚠️  导入时记录 {} 条词条，当前为 {} 条", imported, count);
+        }
+
+        Ok(passed)
+    }
+
+    /// 写入一条词典元数据（已存在则覆盖）
+    pub fn set_metadata(&self, key: &str, value: &str) -> Result<()> {
+        self.ensure_writable()?;
+        self.conn.execute(
+            "INSERT OR REPLACE INTO dict_metadata (key, value, updated_at) VALUES (?1, ?2, CURRENT_TIMESTAMP)",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+
+    /// 词条的最近修改时间（ISO-8601，UTC，如2024-01-01T00:00:00Z），没有词条时为当前时间
+    pub fn last_modified(&self) -> Result<String> {
+        self.conn.query_row(
+            "SELECT strftime('%Y-%m-%dT%H:%M:%SZ', COALESCE(MAX(updated_at), 'now')) FROM obunsha_kokugo_dict",
+            [],
+            |row| row.get(0),
+        )
+    }
+
+    /// 获取全部词典元数据，按键排序
+    /// 旧数据库没有元数据表时返回空表
+    pub fn get_metadata(&self) -> Result<BTreeMap<String, String>> {
+        let mut metadata = BTreeMap::new();
+        if !self.table_exists("dict_metadata")? {
+            return Ok(metadata);
+        }
+
+        let mut stmt = self.conn.prepare("SELECT key, value FROM dict_metadata ORDER BY key")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+        for row in rows {
+            let (key, value) = row?;
+            metadata.insert(key, value);
+        }
+
+        Ok(metadata)
+    }
+
+    /// 记录一条解析失败的词条（同一词条重复导入时只更新原因）
+    pub fn record_parse_failure(&self, title: &str, raw_html: &str, reason: &str) -> Result<()> {
+        self.ensure_writable()?;
+        self.conn.execute(
+            r#"
+            INSERT INTO parse_failures (title, raw_html, reason) VALUES (?1, ?2, ?3)
+            ON CONFLICT(title, raw_html) DO UPDATE SET reason = excluded.reason
+            "#,
+            params![title, raw_html, reason],
+        )?;
+        Ok(())
+    }
+
+    /// 隔离区中的词条数
+    pub fn count_parse_failures(&self) -> Result<i64> {
+        self.conn.query_row("SELECT COUNT(*) FROM parse_failures", [], |row| row.get(0))
+    }
+
+    /// 重新解析隔离区中的词条，成功的写入主表并移出隔离区
+    /// 返回(成功转入主表的数量, 仍然失败的数量)
+    pub fn reprocess_failures(&self) -> Result<(usize, usize)> {
+        self.ensure_writable()?;
+        let failures: Vec<(i64, String, String)> = {
+            let mut stmt = self.conn.prepare("SELECT id, title, raw_html FROM parse_failures ORDER BY id")?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+            rows.collect::<Result<Vec<_>>>()?
+        };
+
+        let parser = ObunshaParser::with_sense_separator(STORED_SENSE_SEPARATOR);
+        let mut entries = Vec::new();
+        let mut promoted_ids = Vec::new();
+        let mut still_failing = Vec::new();
+
+        for (id, title, raw_html) in &failures {
+            match parser.parse_entry_from_html(title, raw_html) {
+                Some(entry) => {
+                    entries.push(entry);
+                    promoted_ids.push(*id);
+                }
+                None => still_failing.push((*id, parser.diagnose_failure(raw_html))),
+            }
+        }
+
+        if !entries.is_empty() {
+            self.insert_entries_batch(&entries)?;
+        }
+
+        let tx = self.conn.unchecked_transaction()?;
+        {
+            let mut delete = tx.prepare("DELETE FROM parse_failures WHERE id = ?1")?;
+            for id in &promoted_ids {
+                delete.execute([id])?;
+            }
+            let mut update = tx.prepare("UPDATE parse_failures SET reason = ?1 WHERE id = ?2")?;
+            for (id, reason) in &still_failing {
+                update.execute(params![reason, id])?;
+            }
+        }
+        tx.commit()?;
+
+        if !promoted_ids.is_empty() {
+            self.rebuild_definition_index()?;
+        }
+
+        Ok((promoted_ids.len(), still_failing.len()))
+    }
+
+    /// 用当前解析器从raw_mdx_content（标题行+HTML）重新生成所有词条的纯文本定义
+    /// 用于解析逻辑改变后（如义项改为换行分隔）更新已有数据，返回更新的词条数
+    /// 与upsert相同，只有内容变化的词条刷新updated_at，增量同步和 export_to_jsonl --since 能取到重新解析的词条
+    pub fn reparse_all(&self) -> Result<usize> {
+        self.ensure_writable()?;
+        let parser = ObunshaParser::with_sense_separator(STORED_SENSE_SEPARATOR);
+
+        let mut updates = Vec::new();
+        self.for_each_entry(|entry| {
+            let Some((title, html)) = entry.raw_mdx_content.split_once('\n') else {
+                return;
+            };
+            if let Some(parsed) = parser.parse_entry_from_html(title, html)
+                && (parsed.definition_text != entry.definition_text || parsed.expansion != entry.expansion)
+            {
+                updates.push((entry.id, parsed.definition_text, parsed.expansion));
+            }
+        })?;
+
+        let tx = self.conn.unchecked_transaction()?;
+        {
+            let mut stmt = tx.prepare(
+                r#"
+                UPDATE obunsha_kokugo_dict SET
+                    definition_text = ?1, english_gloss = ?2, expansion = ?3, updated_at = CURRENT_TIMESTAMP
+                WHERE id = ?4
+                  AND (definition_text IS NOT ?1 OR expansion IS NOT ?3)
+                "#,
+            )?;
+            for (id, definition_text, expansion) in &updates {
+                stmt.execute(params![definition_text, Self::english_gloss_column(definition_text), expansion, id])?;
+            }
+        }
+        tx.commit()?;
+
+        if !updates.is_empty() {
+            self.rebuild_definition_index()?;
+        }
+        Ok(updates.len())
+    }
+
+    /// 将所有词条按order顺序导出为CSV（列见CSV_COLUMNS），返回导出的词条数
+    pub fn export_to_csv(
+        &self,
+        path: &str,
+        delimiter: char,
+        order: ExportOrder,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        use std::io::Write;
+
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+        writeln!(writer, "{}", csv_format::format_record(&CSV_COLUMNS, delimiter))?;
+
+        let mut count = 0;
+        for entry in self.iter_entries(order)? {
+            let entry = entry?;
+            let optional = |value: &Option<String>| value.clone().unwrap_or_default();
+            let fields = [
+                entry.data_id.clone(),
+                entry.data_type.clone(),
+                entry.headword.clone(),
+                optional(&entry.kana_reading),
+                optional(&entry.kanji_writing),
+                optional(&entry.part_of_speech),
+                optional(&entry.conjugation),
+                optional(&entry.expansion),
+                entry.definition_html,
+                entry.definition_text,
+                entry.raw_mdx_content,
+            ];
+            writeln!(writer, "{}", csv_format::format_record(&fields, delimiter))?;
+            count += 1;
+        }
+        writer.flush()?;
+
+        println!("✅ 已导出 {} 条词条到 {}", count, path);
+        Ok(count)
+    }
+
+    /// 将词条导出为JSON Lines（每行一个词条），返回导出的词条数；顺序、压缩和增量导出见JsonlExportOptions
+    pub fn export_to_jsonl(&self, path: &str, options: &JsonlExportOptions) -> Result<usize, Box<dyn std::error::Error>> {
+        use std::io::Write;
+
+        let since = match options.since.as_deref() {
+            Some(timestamp) => match self.normalize_timestamp(timestamp)? {
+                Some(normalized) => Some(normalized),
+                None => return Err(format!("无法解析时间戳: {}", timestamp).into()),
+            },
+            None => None,
+        };
+
+        let mut writer = CompressedWriter::create(path, options.compression)?;
+        let mut count = 0;
+        for entry in self.entries_updated_after(options.order, since.clone()) {
+            serde_json::to_writer(&mut writer, &entry?)?;
+            writer.write_all(b"\n")?;
+            count += 1;
+        }
+        writer.finish()?;
+
+        match &since {
+            Some(since) => println!("✅ 已导出 {} 条 {} 之后更新的词条到 {}", count, since, path),
+            None => println!("✅ 已导出 {} 条词条到 {}", count, path),
+        }
+        Ok(count)
+    }
+
+    /// 从CSV导入词条（列与export_to_csv相同），按data_id插入或更新，返回导入的词条数
+    /// 表头可以只包含部分列：缺少的可空列（读音、汉字表记、词性等）存为NULL，其余存为空字符串；
+    /// 表头含未知列、缺少data_id/headword或某行列数不符时整个文件不导入
+    pub fn import_from_csv(&self, path: &str, delimiter: char) -> Result<usize, Box<dyn std::error::Error>> {
+        self.ensure_writable()?;
+        println!("🚀 开始从CSV导入词条: {}", path);
+
+        let content = std::fs::read_to_string(path)?;
+        let entries = Self::entries_from_csv(&content, delimiter)?;
+
+        for chunk in entries.chunks(1000) {
+            self.insert_entries_batch(chunk)?;
+        }
+        self.rebuild_definition_index()?;
+
+        println!("🎉 CSV导入完成！共 {} 条词条", entries.len());
+        Ok(entries.len())
+    }
+
+    /// 将CSV内容转换为词条，校验表头和每行的列数
+    fn entries_from_csv(content: &str, delimiter: char) -> std::result::Result<Vec<ObunshaDictEntry>, String> {
+        let records = csv_format::parse_records(content, delimiter)?;
+        let Some((header, rows)) = records.split_first() else {
+            return Err("CSV文件为空".to_string());
+        };
+
+        let columns: Vec<&str> = header.fields.iter().map(|column| column.trim()).collect();
+        for (i, column) in columns.iter().enumerate() {
+            if !CSV_COLUMNS.contains(column) {
+                return Err(format!("CSV表头含未知列 {:?}，可用的列: {}", column, CSV_COLUMNS.join(", ")));
+            }
+            if columns[..i].contains(column) {
+                return Err(format!("CSV表头中 {:?} 列重复", column));
+            }
+        }
+        if let Some(missing) = CSV_REQUIRED_COLUMNS.iter().find(|required| !columns.contains(required)) {
+            return Err(format!("CSV表头缺少必需的列 {:?}", missing));
+        }
+
+        let mut entries = Vec::with_capacity(rows.len());
+        for row in rows {
+            if row.fields.len() != columns.len() {
+                return Err(format!("第{}行有 {} 列，表头有 {} 列", row.line, row.fields.len(), columns.len()));
+            }
+            let value = |name: &str| {
+                columns
+                    .iter()
+                    .position(|column| *column == name)
+                    .map(|index| row.fields[index].clone())
+            };
+            let optional = |name: &str| value(name).filter(|text| !text.trim().is_empty());
+
+            let data_id = value("data_id").unwrap_or_default().trim().to_string();
+            if data_id.is_empty() {
+                return Err(format!("第{}行的data_id为空", row.line));
+            }
+            let kanji_writing = optional("kanji_writing");
+            entries.push(ObunshaDictEntry {
+                id: None,
+                data_id,
+                data_type: value("data_type").unwrap_or_default(),
+                headword: value("headword").unwrap_or_default(),
+                kana_reading: optional("kana_reading"),
+                kanji_variants: kanji_writing.as_deref().map(split_kanji_variants).unwrap_or_default(),
+                kanji_writing,
+                expansion: optional("expansion"),
+                part_of_speech: optional("part_of_speech"),
+                conjugation: optional("conjugation"),
+                definition_html: value("definition_html").unwrap_or_default(),
+                definition_text: value("definition_text").unwrap_or_default(),
+                raw_mdx_content: value("raw_mdx_content").unwrap_or_default(),
+            });
+        }
+        Ok(entries)
+    }
+
+    /// 从清理后的数据文件解析并导入所有词条，返回该文件导入的词条总数（含继续导入前已导入的词条）
+    /// 解析失败的词条写入parse_failures隔离区
+    /// 每批词条写入后把已处理到的字节位置、行号以及文件的大小和修改时间记录到 `<文件名>.offset`，全部导入后删除；
+    /// resume为true且该文件存在时从记录的位置继续，跳过已导入的内容（按data_id写入，重复导入也是安全的）；
+    /// 文件大小或修改时间与记录不一致时说明文件已更换，拒绝继续
+    pub fn import_from_cleaned_data(&self, cleaned_data_path: &str, resume: bool) -> Result<usize, Box<dyn std::error::Error>> {
+        use std::fs::File;
+        use std::io::{BufReader, Seek, SeekFrom};
+
+        self.ensure_writable()?;
+        println!("🚀 开始从清理数据导入词条: {}", cleaned_data_path);
+
+        let mut file = File::open(cleaned_data_path)?;
+        let (file_len, modified) = file_fingerprint(&file.metadata()?)?;
+
+        let marker_path = format!("{}{}", cleaned_data_path, IMPORT_OFFSET_SUFFIX);
+        let previous = if resume { Self::read_import_progress(&marker_path)? } else { None };
+        if let Some(previous) = previous
+            && (previous.file_len, previous.modified) != (file_len, modified)
+        {
+            return Err(format!("{} 记录的文件大小或修改时间与 {} 不一致，文件可能已更换，请去掉 --resume 重新导入", marker_path, cleaned_data_path).into());
+        }
+        let start = previous.unwrap_or(ImportProgress { offset: 0, entries: 0, lines: 0, file_len, modified });
+        if start.offset > 0 {
+            file.seek(SeekFrom::Start(start.offset))?;
+            println!("⏩ 从第 {} 行（第 {} 字节）继续导入，跳过已导入的 {} 条词条", start.lines + 1, start.offset, start.entries);
+        }
+
+        let count = self.import_from_reader_with_checkpoint(BufReader::new(file), start.offset, start.lines, |offset, processed, lines| {
+            let progress = ImportProgress { offset, entries: start.entries + processed, lines, file_len, modified };
+            std::fs::write(&marker_path, progress.to_line())
+        })?;
+
+        // 同步释义全文索引
+        self.rebuild_definition_index()?;
+        if std::path::Path::new(&marker_path).exists() {
+            std::fs::remove_file(&marker_path)?;
+        }
+        Ok(start.entries + count)
+    }
+
+    /// 读取导入进度文件（见ImportProgress），文件不存在时返回None
+    fn read_import_progress(marker_path: &str) -> Result<Option<ImportProgress>, Box<dyn std::error::Error>> {
+        let content = match std::fs::read_to_string(marker_path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        match ImportProgress::parse(&content) {
+            Some(progress) => Ok(Some(progress)),
+            None => Err(format!("无法解析导入进度文件 {}: {:?}", marker_path, content).into()),
+        }
+    }
+
+    /// 依次导入多个清理后的数据文件（如按假名行拆分的文件），报告每个文件和总计的词条数
+    /// 全部导入后只重建一次释义全文索引；某个文件出错时停止，已导入的文件保留
+    pub fn import_from_cleaned_files(&self, paths: &[&str]) -> Result<usize, Box<dyn std::error::Error>> {
+        use std::fs::File;
+        use std::io::BufReader;
+
+        self.ensure_writable()?;
+        let mut total = 0;
+        for (index, path) in paths.iter().enumerate() {
+            println!("🚀 [{}/{}] 开始从清理数据导入词条: {}", index + 1, paths.len(), path);
+            let file = File::open(path)?;
+            let count = self.import_from_reader(BufReader::new(file))?;
+            println!("📄 {}: {} 条词条", path, count);
+            total += count;
+        }
+
+        self.rebuild_definition_index()?;
+        println!("🎉 {} 个文件共导入 {} 条词条", paths.len(), total);
+        Ok(total)
+    }
+
+    /// 写入内置的演示数据（约50条词条，含同形异义词、汉字词条、英文缩写和重定向）
+    /// 无需MDX文件即可运行服务器和测试
+    pub fn seed_demo(&self) -> Result<usize, Box<dyn std::error::Error>> {
+        self.ensure_writable()?;
+        let count = self.import_from_reader(DEMO_ENTRIES.as_bytes())?;
+        self.rebuild_definition_index()?;
+        self.insert_redirects(&Self::parse_redirects_tsv(DEMO_REDIRECTS))?;
+        self.set_metadata("source_file", "fixtures/demo_entries.txt")?;
+        self.set_metadata("imported_entries", &count.to_string())?;
+        Ok(count)
+    }
+
+    /// 从清理后格式（标题行、HTML行、空行）的数据流解析并导入词条
+    /// 不重建释义全文索引，由调用方在全部导入后重建
+    /// 不是合法UTF-8的行会被跳过并报告行号，所在词条随之丢弃，不会中断整个导入
+    fn import_from_reader<R: std::io::BufRead>(&self, reader: R) -> Result<usize, Box<dyn std::error::Error>> {
+        self.import_from_reader_with_checkpoint(reader, 0, 0, |_, _, _| Ok(()))
+    }
+
+    /// 同import_from_reader；start_offset、start_line为reader在文件中的起始字节位置和此前已读取的行数，
+    /// 每批词条写入数据库后以（该批之后的字节位置, 本次已导入的词条数, 已读取的行数）调用checkpoint
+    fn import_from_reader_with_checkpoint<R, F>(
+        &self,
+        mut reader: R,
+        start_offset: u64,
+        start_line: usize,
+        mut checkpoint: F,
+    ) -> Result<usize, Box<dyn std::error::Error>>
+    where
+        R: std::io::BufRead,
+        F: FnMut(u64, usize, usize) -> std::io::Result<()>,
+    {
+        let parser = ObunshaParser::with_sense_separator(STORED_SENSE_SEPARATOR);
+        let mut entries = Vec::new();
+        let mut current_title: Option<String> = None;
+        let mut processed_count = 0;
+        let mut failed_count = 0;
+        let mut invalid_line_count = 0;
+        let mut line_number = start_line;
+        let mut offset = start_offset;
+        let mut buffer = Vec::new();
+
+        loop {
+            buffer.clear();
+            let read = reader.read_until(b'\n', &mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            line_number += 1;
+            offset += read as u64;
+            if buffer.ends_with(b"\n") {
+                buffer.pop();
+                if buffer.ends_with(b"\r") {
+                    buffer.pop();
+                }
+            }
+
+            let line = match std::str::from_utf8(&buffer) {
+                Ok(line) => line.to_string(),
+                Err(e) => {
+                    println!("⚠️  第{}行不是合法的UTF-8，已跳过: {}", line_number, e);
+                    invalid_line_count += 1;
+                    // 标题行或HTML行损坏时整个词条都无法可靠解析
+                    current_title = None;
+                    continue;
+                }
+            };
+
+            if line.trim().is_empty() {
+                // 空行表示词条结束，重置状态
+                current_title = None;
+                continue;
+            }
+
+            if line.contains("<link rel=\"stylesheet\"") {
+                // 这是HTML内容行
+                if let Some(title) = current_title.take() {
+                    // 解析这个词条
+                    if let Some(entry) = parser.parse_entry_from_html(&title, &line) {
+                        entries.push(entry);
+                        processed_count += 1;
+
+                        // 每1000条批量插入一次
+                        if entries.len() >= 1000 {
+                            self.insert_entries_batch(&entries)?;
+                            entries.clear();
+                            checkpoint(offset, processed_count, line_number)?;
+                            println!("✅ 已导入 {} 条词条", processed_count);
+                        }
+                    } else {
+                        self.record_parse_failure(&title, &line, &parser.diagnose_failure(&line))?;
+                        failed_count += 1;
+                    }
+                }
+            } else {
+                // 这是标题行
+                current_title = Some(line);
+            }
+        }
+
+        // 插入剩余的词条
+        if !entries.is_empty() {
+            self.insert_entries_batch(&entries)?;
+        }
+
+        println!("🎉 导入完成！共处理 {} 条词条", processed_count);
+        if self.kana_violation_count() > 0 {
+            println!("⚠️  {} 条词条的假名读音含非假名字符", self.kana_violation_count());
+        }
+        if parser.empty_kana_count() > 0 {
+            println!("⚠️  {} 处假名清理后为空（只含符号，无法解析）", parser.empty_kana_count());
+        }
+        if failed_count > 0 {
+            println!("⚠️  {} 条词条解析失败，已写入 parse_failures", failed_count);
+        }
+        if invalid_line_count > 0 {
+            println!("⚠️  跳过 {} 行非UTF-8内容", invalid_line_count);
+        }
+        Ok(processed_count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> ObunshaDictDatabase {
+        let db = ObunshaDictDatabase::new(":memory:").unwrap();
+        db.initialize().unwrap();
+        db
+    }
+
+    #[test]
+    fn test_redirect_chain_resolution() {
+        let db = test_db();
+        db.insert_redirects(&[
+            ("A".to_string(), "B".to_string()),
+            ("B".to_string(), "C".to_string()),
+        ]).unwrap();
+
+        assert_eq!(db.resolve_redirect_chain("A", 5).unwrap(), Some("C".to_string()));
+        assert_eq!(db.resolve_redirect_chain("A", 1).unwrap(), Some("B".to_string()));
+        assert_eq!(db.resolve_redirect_chain("C", 5).unwrap(), None);
+    }
+
+    #[test]
+    fn test_redirect_cycle_terminates() {
+        let db = test_db();
+        db.insert_redirects(&[
+            ("A".to_string(), "B".to_string()),
+            ("B".to_string(), "A".to_string()),
+        ]).unwrap();
+
+        assert_eq!(db.resolve_redirect_chain("A", 100).unwrap(), None);
+    }
+
+    #[test]
+    fn test_find_orphan_redirects() {
+        let db = test_db();
+        db.insert_entry(&sample_entry("1", "かぜ【風邪】")).unwrap();
+        db.insert_redirects(&[
+            ("かぜひき".to_string(), "かぜ【風邪】".to_string()),
+            ("A".to_string(), "B".to_string()),
+            ("B".to_string(), "かぜ【風邪】".to_string()),
+            ("ない".to_string(), "存在しない".to_string()),
+            ("X".to_string(), "Y".to_string()),
+            ("Y".to_string(), "X".to_string()),
+        ]).unwrap();
+
+        assert_eq!(db.find_orphan_redirects(5).unwrap(), vec![
+            ("X".to_string(), "Y".to_string()),
+            ("Y".to_string(), "X".to_string()),
+            ("ない".to_string(), "存在しない".to_string()),
+        ]);
+    }
+
+    fn sample_entry(data_id: &str, headword: &str) -> ObunshaDictEntry {
+        ObunshaDictEntry {
+            id: None,
+            data_id: data_id.to_string(),
+            data_type: "1".to_string(),
+            headword: headword.to_string(),
+            kana_reading: None,
+            kanji_writing: None,
+            kanji_variants: Vec::new(),
+            expansion: None,
+            part_of_speech: None,
+            conjugation: None,
+            definition_html: String::new(),
+            definition_text: String::new(),
+            raw_mdx_content: String::new(),
+        }
+    }
+
+    /// 清理后数据中一个词条的HTML行，释义均为"説明。"
+    fn sample_html(data_id: &str, kana: &str) -> String {
+        format!(
+            "<link rel=\"stylesheet\" href=\"oko.css\"><container data-id=\"{}\" data-type=\"1\"><div class=\"headword\"><span class=\"headword_kana\">{}</span></div><div class=\"mean_normal\">説明。</div></container>",
+            data_id, kana
+        )
+    }
+
+    #[test]
+    fn test_boolean_query_parse() {
+        assert_eq!(
+            BooleanQuery::parse("あつい OR あつ", 8).unwrap().groups,
+            vec![vec!["あつい".to_string()], vec!["あつ".to_string()]]
+        );
+        assert_eq!(
+            BooleanQuery::parse("熱 AND 湯", 8).unwrap(),
+            BooleanQuery::parse("熱 湯", 8).unwrap()
+        );
+        assert!(BooleanQuery::parse("OR あつ", 8).is_err());
+        assert!(BooleanQuery::parse("あつ AND", 8).is_err());
+        assert!(BooleanQuery::parse("a b c", 2).is_err());
+    }
+
+    #[test]
+    fn test_search_boolean() {
+        let db = test_db();
+        let mut atsui = sample_entry("1", "あつい【熱い】");
+        atsui.kana_reading = Some("あつい".to_string());
+        atsui.kanji_writing = Some("熱い".to_string());
+        let mut nettou = sample_entry("2", "ねっとう【熱湯】");
+        nettou.kana_reading = Some("ねっとう".to_string());
+        nettou.kanji_writing = Some("熱湯".to_string());
+        db.insert_entries_batch(&[atsui, nettou]).unwrap();
+
+        let union = db.search_boolean(&BooleanQuery::parse("あつい OR ねっとう", 8).unwrap(), 10).unwrap();
+        assert_eq!(union.len(), 2);
+
+        let intersection = db.search_boolean(&BooleanQuery::parse("熱 AND 湯", 8).unwrap(), 10).unwrap();
+        assert_eq!(intersection.len(), 1);
+        assert_eq!(intersection[0].data_id, "2");
+
+        // %和_按字面匹配，不作为通配符
+        let mut percent = sample_entry("3", "ひゃくパーセント【100%】");
+        percent.kanji_writing = Some("100%".to_string());
+        db.insert_entry(&percent).unwrap();
+        let literal = db.search_boolean(&BooleanQuery::parse("%", 8).unwrap(), 10).unwrap();
+        assert_eq!(literal.len(), 1);
+        assert_eq!(literal[0].data_id, "3");
+        assert!(db.search_boolean(&BooleanQuery::parse("_", 8).unwrap(), 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_reprocess_failures_promotes_fixed_rows() {
+        let db = test_db();
+        let html = r#"<link rel="stylesheet"><container data-id="100" data-type="1">本文</container>"#;
+        db.record_parse_failure("テスト【試験】", html, "旧解析器失败").unwrap();
+        db.record_parse_failure("こわれた", "<div>no container</div>", "缺少container元素").unwrap();
+
+        assert_eq!(db.reprocess_failures().unwrap(), (1, 1));
+        assert_eq!(db.count_parse_failures().unwrap(), 1);
+        assert_eq!(db.find_by_headword("テスト【試験】").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_kana_typo_search_prefers_adjacent_kana() {
+        let db = test_db();
+        let mut sakura = sample_entry("1", "さくら【桜】");
+        sakura.kana_reading = Some("さくら".to_string());
+        let mut sakumi = sample_entry("2", "さくみ");
+        sakumi.kana_reading = Some("さくみ".to_string());
+        db.insert_entries_batch(&[sakura, sakumi]).unwrap();
+
+        // す→さ 同行，代价低于无关替换
+        let results = db.search_by_kana_typo("すくら", 1.0, 10).unwrap();
+        assert_eq!(results[0].entry.data_id, "1");
+        assert_eq!(db.search_by_kana_typo("すくら", 0.5, 10).unwrap().len(), 1);
+
+        // 只加载距离最近的limit个词条
+        let results = db.search_by_kana_typo("すくら", 1.0, 1).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].entry.headword, "さくら【桜】");
+        assert!(results[0].rank.unwrap() > 0.5);
+    }
+
+    #[test]
+    fn test_readonly_connection_rejects_writes() {
+        let path = std::env::temp_dir().join(format!("obunsha_readonly_test_{}.db", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+        {
+            let db = ObunshaDictDatabase::new(&path).unwrap();
+            db.initialize().unwrap();
+            db.insert_entry(&sample_entry("1", "あい")).unwrap();
+        }
+
+        let db = ObunshaDictDatabase::open_readonly(&path).unwrap();
+        assert_eq!(db.find_by_headword("あい").unwrap().len(), 1);
+        let err = db.insert_entry(&sample_entry("2", "こい")).unwrap_err();
+        assert!(err.to_string().contains("只读"));
+        assert!(db.set_metadata("key", "value").is_err());
+
+        drop(db);
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_open_for_write_requires_existing_database() {
+        let path = std::env::temp_dir().join(format!("obunsha_write_test_{}.db", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+        assert!(ObunshaDictDatabase::open_for_write(&path).is_err());
+        assert!(!std::path::Path::new(&path).exists());
+        {
+            let db = ObunshaDictDatabase::new(&path).unwrap();
+            db.initialize().unwrap();
+        }
+
+        let db = ObunshaDictDatabase::open_for_write(&path).unwrap();
+        assert_eq!(db.insert_entries_batch(&[sample_entry("1", "あい")]).unwrap(), 1);
+        assert_eq!(db.find_by_headword("あい").unwrap().len(), 1);
+
+        drop(db);
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_kanji_variants_from_dotted_writing() {
+        let db = test_db();
+        let mut entry = sample_entry("1", "きく【聞く・聴く】");
+        entry.kanji_writing = Some("聞く・聴く".to_string());
+        db.insert_entry(&entry).unwrap();
+
+        let found = db.search_by_kanji_smart("聴く").unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].kanji_variants, vec!["聞く", "聴く"]);
+    }
+
+    #[test]
+    fn test_entries_using_kanji_in_definition() {
+        let db = test_db();
+        for (data_id, definition_text) in [("1", "生まれること"), ("2", "死ぬこと"), ("3", "人生の意味"), ("4", "学生")] {
+            let mut entry = sample_entry(data_id, data_id);
+            entry.definition_text = definition_text.to_string();
+            db.insert_entry(&entry).unwrap();
+        }
+
+        let data_ids = |limit: usize, offset: usize| -> Vec<String> {
+            db.entries_using_kanji_in_definition('生', limit, offset)
+                .unwrap()
+                .into_iter()
+                .map(|entry| entry.data_id)
+                .collect()
+        };
+        assert_eq!(data_ids(10, 0), vec!["1", "3", "4"]);
+        assert_eq!(data_ids(1, 1), vec!["3"]);
+    }
+
+    #[test]
+    fn test_storage_stats_counts_bytes() {
+        let db = test_db();
+        let mut entry = sample_entry("1", "あい");
+        entry.definition_html = "<p>愛</p>".to_string();
+        entry.raw_mdx_content = "x".repeat(100);
+        db.insert_entry(&entry).unwrap();
+
+        let stats = db.storage_stats().unwrap();
+        assert_eq!(stats.columns[0], ColumnStorage { column: "raw_mdx_content".to_string(), bytes: 100 });
+        let bytes_of = |name: &str| stats.columns.iter().find(|column| column.column == name).unwrap().bytes;
+        // 按UTF-8字节计算：あい为6字节，"<p>愛</p>"为10字节
+        assert_eq!(bytes_of("headword"), 6);
+        assert_eq!(bytes_of("definition_html"), 10);
+        assert_eq!(bytes_of("expansion"), 0);
+        assert!(stats.database_bytes > 0);
+    }
+
+    #[test]
+    fn test_search_by_kanji_stem() {
+        let db = test_db();
+        for (data_id, kana_reading, kanji_writing) in [
+            ("1", "どう", "動"),
+            ("2", "うつくしい", "美しい"),
+            ("3", "び", "美"),
+            ("4", "どうき", "動機"),
+            ("5", "うごく", "動く・働く"),
+        ] {
+            let mut entry = sample_entry(data_id, &format!("{}【{}】", kana_reading, kanji_writing));
+            entry.kana_reading = Some(kana_reading.to_string());
+            entry.kanji_writing = Some(kanji_writing.to_string());
+            db.insert_entry(&entry).unwrap();
+        }
+
+        let data_ids = |query: &str| -> Vec<String> {
+            db.search_by_kanji_stem(query, 10).unwrap().into_iter().map(|entry| entry.data_id).collect()
+        };
+        assert_eq!(data_ids("動く"), vec!["1", "5"]);
+        assert_eq!(data_ids("美しい"), vec!["3", "2"]);
+        assert_eq!(data_ids("働いた"), vec!["5"]);
+        assert!(data_ids("機").is_empty());
+    }
+
+    #[test]
+    fn test_count_pure_redirects() {
+        let db = test_db();
+        for (data_id, text) in [("1", "⇒あいきょう（愛敬）"), ("2", "いとしく思う心。"), ("3", "\n→あいそう\n"), ("4", "→あいそう・→あいきょう")] {
+            let mut entry = sample_entry(data_id, "あい");
+            entry.definition_text = text.to_string();
+            db.insert_entry(&entry).unwrap();
+        }
+        assert_eq!(db.count_pure_redirects().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_search_best_follows_chain() {
+        let db = test_db();
+        for (data_id, kana) in [("1", "き"), ("2", "もく")] {
+            let mut entry = sample_entry(data_id, &format!("{}【木】", kana));
+            entry.kana_reading = Some(kana.to_string());
+            entry.kanji_writing = Some("木".to_string());
+            db.insert_entry(&entry).unwrap();
+        }
+
+        let (found, tier) = db.search_best("木", &[SearchTier::Kana, SearchTier::Kanji]).unwrap();
+        assert_eq!((found.len(), tier), (2, Some(SearchTier::Kanji)));
+        let (found, tier) = db.search_best("き", &[SearchTier::Headword, SearchTier::Kana]).unwrap();
+        assert_eq!((found.len(), tier), (1, Some(SearchTier::Kana)));
+        let (found, tier) = db.search_best("木", &[SearchTier::Kana]).unwrap();
+        assert!(found.is_empty());
+        assert_eq!(tier, None);
+
+        assert_eq!("headword".parse(), Ok(SearchTier::Headword));
+        assert!("fuzzy".parse::<SearchTier>().is_err());
+    }
+
+    #[test]
+    fn test_search_anything_matches_all_forms() {
+        let db = test_db();
+        let mut entry = sample_entry("1", "きく【聞く・聴く】");
+        entry.kana_reading = Some("きく".to_string());
+        entry.kanji_writing = Some("聞く・聴く".to_string());
+        db.insert_entry(&entry).unwrap();
+        let mut loanword = sample_entry("2", "アイス");
+        loanword.kana_reading = Some("アイス".to_string());
+        db.insert_entries_batch(&[loanword]).unwrap();
+
+        for query in ["きく", "聴く", "聞く", "キク", "kiku", "きく【聞く・聴く】"] {
+            let found = db.search_anything(query).unwrap();
+            assert_eq!(found.len(), 1, "{}", query);
+            assert_eq!(found[0].data_id, "1");
+        }
+        assert_eq!(db.search_anything("あいす").unwrap()[0].data_id, "2");
+        assert_eq!(db.search_anything("ｱｲｽ").unwrap()[0].data_id, "2");
+        assert!(db.search_anything("聞").unwrap().is_empty());
+
+        // 批量更新后旧的检索形式被替换
+        let mut updated = sample_entry("1", "きく【効く】");
+        updated.kana_reading = Some("きく".to_string());
+        updated.kanji_writing = Some("効く".to_string());
+        db.insert_entries_batch(&[updated]).unwrap();
+        assert!(db.search_anything("聴く").unwrap().is_empty());
+        assert_eq!(db.search_anything("効く").unwrap().len(), 1);
+
+        db.conn.execute("DELETE FROM search_all", []).unwrap();
+        assert!(db.search_anything("効く").unwrap().is_empty());
+        assert_eq!(db.rebuild_search_all().unwrap(), 2);
+        assert_eq!(db.search_anything("効く").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_suggestions_for() {
+        let db = test_db();
+        for (id, headword, kana) in [("1", "あい【愛】", "あい"), ("2", "あお【青】", "あお"), ("3", "かい【貝】", "かい"), ("4", "あいさつ【挨拶】", "あいさつ")] {
+            let mut entry = sample_entry(id, headword);
+            entry.kana_reading = Some(kana.to_string());
+            db.insert_entry(&entry).unwrap();
+        }
+
+        let suggestions = db.suggestions_for("あえ").unwrap();
+        assert!(suggestions.contains(&"あい【愛】".to_string()));
+        assert!(suggestions.contains(&"あお【青】".to_string()));
+        assert!(!suggestions.contains(&"あいさつ【挨拶】".to_string()));
+
+        // 距离匹配在前，前缀匹配补充在后且不重复
+        let suggestions = db.suggestions_for("あい").unwrap();
+        assert_eq!(suggestions[0], "あい【愛】");
+        assert!(suggestions.contains(&"あいさつ【挨拶】".to_string()));
+        assert_eq!(suggestions.iter().filter(|s| *s == "あい【愛】").count(), 1);
+        assert!(db.suggestions_for("  ").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_ids_with_examples() {
+        let db = test_db();
+        let mut with_example = sample_entry("1", "あい【愛】");
+        with_example.definition_html = r#"<div class="mean_normal">いとしく思う心。<span class="ex_text">親子の―。</span></div>"#.to_string();
+        let id_with = db.insert_entry(&with_example).unwrap();
+        let id_without = db.insert_entry(&sample_entry("2", "あお【青】")).unwrap();
+
+        let found = db.ids_with_examples(&[id_with, id_without]).unwrap();
+        assert!(found.contains(&id_with));
+        assert!(!found.contains(&id_without));
+
+        // 旧数据库中未统计的词条在初始化时回填
+        db.conn.execute("UPDATE obunsha_kokugo_dict SET example_count = NULL", []).unwrap();
+        db.initialize().unwrap();
+        assert_eq!(db.ids_with_examples(&[id_with, id_without]).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_entry_of_the_day_is_stable() {
+        let db = test_db();
+        assert!(db.entry_of_the_day("2024-05-01").unwrap().is_none());
+
+        db.seed_demo().unwrap();
+        let first = db.entry_of_the_day("2024-05-01").unwrap().unwrap();
+        let again = db.entry_of_the_day("2024-05-01").unwrap().unwrap();
+        assert_eq!(first.data_id, again.data_id);
+
+        // 不同日期会分散到不同词条
+        let distinct: std::collections::HashSet<String> = (1..=28)
+            .map(|day| db.entry_of_the_day(&format!("2024-02-{:02}", day)).unwrap().unwrap().data_id)
+            .collect();
+        assert!(distinct.len() > 1);
+    }
+
+    #[test]
+    fn test_count_search_results() {
+        let db = test_db();
+        for (data_id, headword) in [("1", "かがく"), ("2", "かがみ"), ("3", "かさ"), ("4", "あか")] {
+            let mut entry = sample_entry(data_id, headword);
+            entry.kana_reading = Some(headword.to_string());
+            entry.definition_text = format!("{}の説明", headword);
+            db.insert_entry(&entry).unwrap();
+        }
+
+        assert_eq!(db.count_search_results("prefix", "かが").unwrap(), Some(2));
+        assert_eq!(db.count_search_results("suffix", "か").unwrap(), Some(1));
+        assert_eq!(db.count_search_results("definition", "説明").unwrap(), Some(4));
+        assert_eq!(db.count_search_results("english", "love").unwrap(), None);
+    }
+
+    #[test]
+    fn test_normalize_kanji_column() {
+        let db = test_db();
+        for (data_id, kanji_writing) in [("1", " 聞く·聴く "), ("2", "可愛‧‧可愛らしい"), ("3", "愛"), ("4", "◇")] {
+            let mut entry = sample_entry(data_id, data_id);
+            entry.kanji_writing = Some(kanji_writing.to_string());
+            db.insert_entry(&entry).unwrap();
+        }
+
+        db.conn.execute("UPDATE obunsha_kokugo_dict SET updated_at = '2024-01-01 00:00:00'", []).unwrap();
+        assert_eq!(db.normalize_kanji_column().unwrap(), 3);
+        // 只有表记变化的词条刷新updated_at
+        assert_eq!(db.entries_modified_since("2024-06-01T00:00:00", 10, 0).unwrap().len(), 3);
+        let kanji_of = |data_id: &str| db.get_by_data_ids(&[data_id.to_string()]).unwrap()[0].kanji_writing.clone();
+        assert_eq!(kanji_of("1").as_deref(), Some("聞く・聴く"));
+        assert_eq!(kanji_of("2").as_deref(), Some("可愛・可愛らしい"));
+        assert_eq!(kanji_of("3").as_deref(), Some("愛"));
+        assert_eq!(kanji_of("4"), None);
+        assert_eq!(db.normalize_kanji_column().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_migration_normalizes_kanji_before_indexing() {
+        let db = test_db();
+        let mut entry = sample_entry("1", "きく");
+        entry.kanji_writing = Some("◇聴く".to_string());
+        db.insert_entry(&entry).unwrap();
+        db.conn.execute("DELETE FROM search_all", []).unwrap();
+        db.conn.pragma_update(None, "user_version", 4).unwrap();
+
+        // 版本5只清理表记，检索形式由版本6按清理后的表记统一重建
+        db.initialize().unwrap();
+        assert_eq!(db.get_by_data_ids(&["1".to_string()]).unwrap()[0].kanji_writing.as_deref(), Some("聴く"));
+        assert_eq!(db.search_anything("聴く").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_migration_strips_reading_dots() {
+        let db = test_db();
+        let mut entry = sample_entry("1", "あが・く【足搔く】");
+        entry.kana_reading = Some("あが・く".to_string());
+        db.insert_entry(&entry).unwrap();
+        db.insert_entry(&sample_entry("2", "あがき")).unwrap();
+        db.conn.pragma_update(None, "user_version", 6).unwrap();
+
+        // 读音、排序键和检索形式都按去掉中点后的读音更新
+        db.initialize().unwrap();
+        assert_eq!(db.get_by_data_ids(&["1".to_string()]).unwrap()[0].kana_reading.as_deref(), Some("あがく"));
+        assert_eq!(db.search_anything("あがく").unwrap().len(), 1);
+        let window = db.entries_window("あがく", 1, 0).unwrap();
+        assert_eq!(window.target.unwrap().data_id, "1");
+        assert_eq!(window.before[0].data_id, "2");
+    }
+
+    #[test]
+    fn test_migration_backfills_expansion() {
+        let db = test_db();
+        let mut entry = sample_entry("1", "ディーエヌエー【DNA】");
+        entry.definition_html = r#"<container data-id="1"><span class="headword_ryaku">DNA</span></container>"#.to_string();
+        entry.definition_text = "〔deoxyribonucleic acid〕デオキシリボ核酸。".to_string();
+        db.insert_entry(&entry).unwrap();
+        db.insert_entry(&sample_entry("2", "あい")).unwrap();
+        db.conn.pragma_update(None, "user_version", 7).unwrap();
+
+        db.initialize().unwrap();
+        let entries = db.get_by_data_ids(&["1".to_string(), "2".to_string()]).unwrap();
+        let expansion = |data_id: &str| entries.iter().find(|e| e.data_id == data_id).unwrap().expansion.clone();
+        assert_eq!(expansion("1").as_deref(), Some("deoxyribonucleic acid"));
+        assert_eq!(expansion("2"), None);
+    }
+
+    #[test]
+    fn test_list_parts_of_speech() {
+        let db = test_db();
+        for (data_id, pos) in [("1", Some("名")), ("2", Some("自五")), ("3", Some("名")), ("4", None), ("5", Some(""))] {
+            let mut entry = sample_entry(data_id, data_id);
+            entry.part_of_speech = pos.map(str::to_string);
+            db.insert_entry(&entry).unwrap();
+        }
+
+        assert_eq!(
+            db.list_parts_of_speech().unwrap(),
+            vec![("名".to_string(), 2), ("自五".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_csv_round_trip() {
+        let db = test_db();
+        let mut entry = sample_entry("1", "あい【愛】");
+        entry.kana_reading = Some("あい".to_string());
+        entry.kanji_writing = Some("愛".to_string());
+        entry.definition_text = "❶かわいがる, いつくしむ\n❷こいしたう".to_string();
+        db.insert_entry(&entry).unwrap();
+
+        let path = std::env::temp_dir().join(format!("obunsha_csv_test_{}.csv", std::process::id()));
+        let path = path.to_str().unwrap();
+        assert_eq!(db.export_to_csv(path, ',', ExportOrder::Insertion).unwrap(), 1);
+
+        let other = test_db();
+        assert_eq!(other.import_from_csv(path, ',').unwrap(), 1);
+        std::fs::remove_file(path).unwrap();
+
+        let imported = other.find_by_headword("あい【愛】").unwrap();
+        assert_eq!(imported[0].definition_text, entry.definition_text);
+        assert_eq!(imported[0].kanji_writing.as_deref(), Some("愛"));
+        assert_eq!(imported[0].part_of_speech, None);
+    }
+
+    #[test]
+    fn test_export_jsonl_since() {
+        let db = test_db();
+        db.insert_entry(&sample_entry("1", "あい【愛】")).unwrap();
+        db.insert_entry(&sample_entry("2", "あお【青】")).unwrap();
+        db.conn.execute("UPDATE obunsha_kokugo_dict SET updated_at = '2024-01-01 00:00:00' WHERE data_id = '1'", []).unwrap();
+        db.conn.execute("UPDATE obunsha_kokugo_dict SET updated_at = '2024-06-01 00:00:00' WHERE data_id = '2'", []).unwrap();
+
+        let path = std::env::temp_dir().join(format!("obunsha_jsonl_test_{}.jsonl", std::process::id()));
+        let path = path.to_str().unwrap();
+        assert_eq!(db.export_to_jsonl(path, &JsonlExportOptions::default()).unwrap(), 2);
+        let since = |timestamp: &str| JsonlExportOptions { since: Some(timestamp.to_string()), ..JsonlExportOptions::default() };
+        assert_eq!(db.export_to_jsonl(path, &since("2024-03-01T00:00:00Z")).unwrap(), 1);
+        let content = std::fs::read_to_string(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        let exported: serde_json::Value = serde_json::from_str(content.lines().next().unwrap()).unwrap();
+        assert_eq!(exported["data_id"], "2");
+        assert!(db.export_to_jsonl(path, &since("not a date")).is_err());
+    }
+
+    #[test]
+    fn test_set_fts5_available_skips_probe() {
+        let db = test_db();
+        db.set_fts5_available(false);
+        assert!(!db.fts5_available());
+        assert!(!db.has_definition_index().unwrap());
+
+        let mut entry = sample_entry("1", "あい【愛】");
+        entry.definition_text = "いとしく思う気持ち。".to_string();
+        db.insert_entry(&entry).unwrap();
+        assert_eq!(db.search_in_definitions("いとしく", 10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_definition_search_without_fts5() {
+        assert!(test_db().fts5_available());
+
+        // 模拟不支持FTS5的SQLite：不创建全文索引，释义搜索退回LIKE扫描
+        let db = ObunshaDictDatabase::new(":memory:").unwrap();
+        db.fts5_supported.set(Some(false));
+        db.initialize().unwrap();
+        assert!(!db.table_exists("obunsha_definitions_fts").unwrap());
+
+        let mut entry = sample_entry("1", "あい【愛】");
+        entry.definition_text = "いとしく思う気持ち。".to_string();
+        db.insert_entry(&entry).unwrap();
+        db.rebuild_definition_index().unwrap();
+
+        let results = db.search_in_definitions("思う気持ち", 10).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(db.count_search_results("definition", "思う気持ち").unwrap(), Some(1));
+    }
+
+    #[test]
+    fn test_definition_search_escapes_like_wildcards() {
+        let db = test_db();
+        for (data_id, headword, definition_text) in [("1", "わりびき【割引】", "5%引き。"), ("2", "ごじゅう【五十】", "50の数。")] {
+            let mut entry = sample_entry(data_id, headword);
+            entry.definition_text = definition_text.to_string();
+            db.insert_entry(&entry).unwrap();
+        }
+
+        let ids = |keyword: &str| {
+            db.search_in_definitions(keyword, 10).unwrap().into_iter().map(|ranked| ranked.entry.data_id).collect::<Vec<_>>()
+        };
+        assert_eq!(ids("5%"), vec!["1"]);
+        assert_eq!(ids("5_"), Vec::<String>::new());
+        assert_eq!(db.count_search_results("definition", "5%").unwrap(), Some(1));
+    }
+
+    #[test]
+    fn test_definition_search_boosts_before_truncating() {
+        let db = test_db();
+        // bm25更高（释义短）但关键词只在第二义项的词条，与关键词在第一义项的词条
+        for (data_id, headword, definition_text) in [
+            ("1", "あ【亜】", "❶次ぐ。❷思う気持ち。"),
+            ("2", "い【意】", "思う気持ち。心の働き。考え。"),
+        ] {
+            let mut entry = sample_entry(data_id, headword);
+            entry.definition_text = definition_text.to_string();
+            db.insert_entry(&entry).unwrap();
+        }
+        db.rebuild_definition_index().unwrap();
+
+        let results = db.search_in_definitions("思う気持ち", 1).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].entry.data_id, "2");
+    }
+
+    #[test]
+    fn test_search_by_kanji_variants() {
+        let db = test_db();
+        for (data_id, headword, kanji) in [
+            ("1", "くに【国】", "国"),
+            ("2", "こく【國】", "國"),
+            ("3", "がく【學】", "學"),
+            ("4", "こっか【国家・國家】", "国家・國家"),
+        ] {
+            let mut entry = sample_entry(data_id, headword);
+            entry.kanji_writing = Some(kanji.to_string());
+            db.insert_entry(&entry).unwrap();
+        }
+
+        let ids = |query: &str| {
+            db.search_by_kanji_variants(query).unwrap().into_iter().map(|entry| entry.data_id).collect::<Vec<_>>()
+        };
+        assert_eq!(ids("国"), ["1", "2"]);
+        assert_eq!(ids("國"), ["2", "1"]);
+        assert_eq!(ids("学"), ["3"]);
+        assert_eq!(ids("國家"), ["4"]);
+        assert_eq!(db.search_by_kanji_smart("国").unwrap().len(), 1);
+
+        // 没有检索形式表的旧数据库结果相同
+        db.conn.execute("DROP TABLE search_all", []).unwrap();
+        assert_eq!(ids("国"), ["1", "2"]);
+        assert_eq!(ids("國家"), ["4"]);
+    }
+
+    #[test]
+    fn test_disambiguate() {
+        let db = test_db();
+        for (data_id, headword, kana) in [
+            ("1", "せい【生】", "せい"),
+            ("2", "なま【生】", "なま"),
+            ("3", "なま【生】", "なま"),
+            ("4", "き【生・生き】", "き"),
+            ("5", "せいかつ【生活】", "せいかつ"),
+        ] {
+            let mut entry = sample_entry(data_id, headword);
+            entry.kana_reading = Some(kana.to_string());
+            entry.kanji_writing = Some(headword[headword.find('【').unwrap() + 3..headword.len() - 3].to_string());
+            db.insert_entry(&entry).unwrap();
+        }
+
+        let readings = db.disambiguate("生").unwrap();
+        let summary: Vec<(&str, &str)> = readings.iter().map(|(reading, entry)| (reading.as_str(), entry.data_id.as_str())).collect();
+        assert_eq!(summary, vec![("せい", "1"), ("なま", "2"), ("き", "4")]);
+        assert!(db.disambiguate("無").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_compact_entries() {
+        let db = test_db();
+        let mut entry = sample_entry("1", "あい【愛】");
+        entry.kana_reading = Some("あい".to_string());
+        entry.kanji_writing = Some("愛".to_string());
+        db.insert_entry(&entry).unwrap();
+        db.insert_entry(&sample_entry("2", "あお【青】")).unwrap();
+
+        let rows = db.compact_entries(10, 0).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(
+            serde_json::json!(rows[0].1),
+            serde_json::json!(["1", "あい【愛】", "あい", "愛", null, entry.definition_text])
+        );
+        assert_eq!(serde_json::json!(rows[0].1).as_array().unwrap().len(), COMPACT_COLUMNS.len());
+
+        // 从上一页最后一个id继续
+        let first_page = db.compact_entries(1, 0).unwrap();
+        assert_eq!(first_page.len(), 1);
+        let next_page = db.compact_entries(10, first_page[0].0).unwrap();
+        assert_eq!(next_page.len(), 1);
+        assert_eq!(next_page[0].1.0, "2");
+        assert!(db.compact_entries(10, next_page[0].0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_length_histograms() {
+        let db = test_db();
+        let mut entry = sample_entry("1", "きゃく【客】");
+        entry.kana_reading = Some("きゃく".to_string());
+        db.insert_entry(&entry).unwrap();
+        let mut entry = sample_entry("2", "あい【愛】");
+        entry.kana_reading = Some("あい".to_string());
+        db.insert_entry(&entry).unwrap();
+        db.insert_entry(&sample_entry("3", "かい【貝】")).unwrap();
+
+        // 【】中的汉字表记不计入
+        db.insert_entry(&sample_entry("4", "アイ")).unwrap();
+        assert_eq!(db.headword_length_histogram().unwrap(), vec![(2, 3), (3, 1)]);
+        // きゃく は2拍，没有读音的词条不计入
+        assert_eq!(db.reading_mora_histogram().unwrap(), vec![(2, 2)]);
+    }
+
+    #[test]
+    fn test_export_order() {
+        let db = test_db();
+        db.insert_entry(&sample_entry("3", "さくら【桜】")).unwrap();
+        db.insert_entry(&sample_entry("1", "あお【青】")).unwrap();
+        db.insert_entry(&sample_entry("2", "かさ【傘】")).unwrap();
+        // 按码位「か」<「が」，按五十音先比较清音，「がい」排在「かさ」之前
+        db.insert_entry(&sample_entry("4", "がい【害】")).unwrap();
+
+        let path = std::env::temp_dir().join(format!("obunsha_order_test_{}.jsonl", std::process::id()));
+        let path = path.to_str().unwrap();
+        let exported_ids = |order: ExportOrder| {
+            db.export_to_jsonl(path, &JsonlExportOptions { order, ..JsonlExportOptions::default() }).unwrap();
+            let content = std::fs::read_to_string(path).unwrap();
+            content
+                .lines()
+                .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["data_id"].as_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(exported_ids(ExportOrder::Insertion), ["3", "1", "2", "4"]);
+        assert_eq!(exported_ids("headword".parse().unwrap()), ["1", "2", "4", "3"]);
+        assert_eq!(exported_ids("sort_key".parse().unwrap()), ["1", "4", "2", "3"]);
+        assert_eq!(exported_ids(ExportOrder::DataId), ["1", "2", "3", "4"]);
+        std::fs::remove_file(path).unwrap();
+
+        assert!("headword; DROP TABLE x".parse::<ExportOrder>().is_err());
+    }
+
+    #[test]
+    fn test_csv_import_partial_columns_and_validation() {
+        let entries = ObunshaDictDatabase::entries_from_csv("data_id;headword;kana_reading\n9;かさ【傘】;\n", ';').unwrap();
+        assert_eq!(entries[0].data_id, "9");
+        assert_eq!(entries[0].kana_reading, None);
+        assert_eq!(entries[0].definition_text, "");
+
+        let error = ObunshaDictDatabase::entries_from_csv("data_id,title\n1,x\n", ',').unwrap_err();
+        assert!(error.contains("title"));
+        assert!(ObunshaDictDatabase::entries_from_csv("headword\nx\n", ',').unwrap_err().contains("data_id"));
+        assert!(ObunshaDictDatabase::entries_from_csv("data_id,headword\n1\n", ',').unwrap_err().contains("第2行"));
+    }
+
+    #[test]
+    fn test_strip_raw() {
+        let db = test_db();
+        let mut entry = sample_entry("1", "あい");
+        entry.raw_mdx_content = "あい\n<div>愛</div>".to_string();
+        db.insert_entry(&entry).unwrap();
+        db.insert_entry(&sample_entry("2", "あお")).unwrap();
+
+        assert_eq!(db.strip_raw().unwrap(), 1);
+        assert_eq!(db.find_by_headword("あい").unwrap()[0].raw_mdx_content, "");
+        assert_eq!(db.get_metadata().unwrap().get("raw_mdx_content").map(String::as_str), Some("stripped"));
+        // 清空后没有可重新解析的原始内容
+        assert_eq!(db.reparse_all().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_readings_for_kanji() {
+        let db = test_db();
+        for (data_id, kana_reading, kanji_writing) in [
+            ("1", "せい", "生"),
+            ("2", "なま", "生"),
+            ("3", "いきる", "生きる"),
+            ("4", "せい", "性"),
+            ("5", "じんせい", "人生"),
+            ("6", "せい", "生"),
+        ] {
+            let mut entry = sample_entry(data_id, kana_reading);
+            entry.kana_reading = Some(kana_reading.to_string());
+            entry.kanji_writing = Some(kanji_writing.to_string());
+            db.insert_entry(&entry).unwrap();
+        }
+
+        assert_eq!(db.readings_for_kanji('生').unwrap(), vec!["いきる", "じんせい", "せい", "なま"]);
+        assert!(db.readings_for_kanji('死').unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_entries_created_between() {
+        let db = test_db();
+        for (data_id, created_at) in [("1", "2024-01-01 10:00:00"), ("2", "2024-02-01 10:00:00"), ("3", "2024-03-01 10:00:00")] {
+            db.insert_entry(&sample_entry(data_id, data_id)).unwrap();
+            db.conn.execute(
+                "UPDATE obunsha_kokugo_dict SET created_at = ?1 WHERE data_id = ?2",
+                params![created_at, data_id],
+            ).unwrap();
+        }
+
+        let entries = db.entries_created_between("2024-01-15T00:00:00Z", "2024-03-01T10:00:00Z", 10, 0).unwrap();
+        let data_ids: Vec<&str> = entries.iter().map(|entry| entry.data_id.as_str()).collect();
+        assert_eq!(data_ids, vec!["2", "3"]);
+
+        let entries = db.entries_created_between("2024-01-01T00:00:00Z", "2024-12-31T00:00:00Z", 1, 1).unwrap();
+        assert_eq!(entries[0].data_id, "2");
+    }
+
+    #[test]
+    fn test_migration_strips_invisible_characters() {
+        let db = test_db();
+        let mut entry = sample_entry("1", "あい\u{200B}【愛】");
+        entry.definition_text = "\u{FEFF}いとしく思う心。".to_string();
+        db.insert_entry(&entry).unwrap();
+        db.insert_entry(&sample_entry("2", "こい【恋】")).unwrap();
+        db.conn.execute("UPDATE obunsha_kokugo_dict SET updated_at = '2024-01-01 00:00:00'", []).unwrap();
+        db.conn.pragma_update(None, "user_version", 1).unwrap();
+
+        db.initialize().unwrap();
+        let found = db.find_by_headword("あい【愛】").unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].headword, "あい【愛】");
+        assert_eq!(found[0].definition_text, "いとしく思う心。");
+        // 只有被清理的词条刷新updated_at
+        let modified = db.entries_modified_since("2024-06-01T00:00:00", 10, 0).unwrap();
+        assert_eq!(modified.len(), 1);
+        assert_eq!(modified[0].headword, "あい【愛】");
+        assert_eq!(db.schema_version().unwrap(), SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_migration_strip_invisible_rebuilds_definition_index() {
+        let db = test_db();
+        let mut entry = sample_entry("1", "あい【愛】");
+        entry.definition_text = "いと\u{200B}しく思う心。".to_string();
+        db.insert_entry(&entry).unwrap();
+        db.rebuild_definition_index().unwrap();
+        assert!(db.search_in_definitions("いとしく", 10).unwrap().is_empty());
+        db.conn.pragma_update(None, "user_version", 1).unwrap();
+
+        db.initialize().unwrap();
+        let found = db.search_in_definitions("いとしく", 10).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].entry.definition_text, "いとしく思う心。");
+    }
+
+    #[test]
+    fn test_reparse_all_uses_newline_senses() {
+        let db = test_db();
+        let html = r#"<container data-id="1" data-type="1"><div class="mean_lv_1">❶一。</div><div class="mean_lv_1">❷二。</div></container>"#;
+        let mut entry = ObunshaParser::new().parse_entry_from_html("いち【一】", html).unwrap();
+        assert_eq!(entry.definition_text, "❶一。 ❷二。");
+        entry.raw_mdx_content = format!("いち【一】\n{}", html);
+        db.insert_entry(&entry).unwrap();
+
+        db.conn.execute("UPDATE obunsha_kokugo_dict SET updated_at = '2024-01-01 00:00:00'", []).unwrap();
+
+        assert_eq!(db.reparse_all().unwrap(), 1);
+        assert_eq!(db.find_by_headword("いち【一】").unwrap()[0].definition_text, "❶一。\n❷二。");
+        // 重新解析的词条出现在增量同步中
+        assert_eq!(db.entries_modified_since("2024-06-01T00:00:00", 10, 0).unwrap().len(), 1);
+        assert_eq!(db.reparse_all().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_search_by_english_gloss() {
+        let db = test_db();
+        for (data_id, headword, definition_text) in [
+            ("1", "あい【愛】", "いとしく思う心。（love）"),
+            ("2", "こい【恋】", "こいしたう気持ち。（romantic love）"),
+            ("3", "かわいい", "愛らしい。（lovely）"),
+            ("4", "ラブ", "love の音訳。"),
+        ] {
+            let mut entry = sample_entry(data_id, headword);
+            entry.definition_text = definition_text.to_string();
+            db.insert_entry(&entry).unwrap();
+        }
+
+        let data_ids: Vec<String> = db.search_by_english_gloss("Love", 10).unwrap().into_iter().map(|entry| entry.data_id).collect();
+        assert_eq!(data_ids, vec!["1", "2"]);
+        assert_eq!(db.search_by_english_gloss("romantic love", 10).unwrap().len(), 1);
+        assert!(db.search_by_english_gloss("%", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_entries_window() {
+        let db = test_db();
+        for (data_id, reading) in [("1", "あ"), ("2", "あい"), ("3", "あう"), ("4", "あお"), ("5", "いえ")] {
+            let mut entry = sample_entry(data_id, reading);
+            entry.kana_reading = Some(reading.to_string());
+            db.insert_entry(&entry).unwrap();
+        }
+        let data_ids = |entries: &[ObunshaDictEntry]| entries.iter().map(|entry| entry.data_id.clone()).collect::<Vec<_>>();
+
+        let window = db.entries_window("あう", 2, 2).unwrap();
+        assert_eq!(data_ids(&window.before), vec!["1", "2"]);
+        assert_eq!(window.target.unwrap().data_id, "3");
+        assert_eq!(data_ids(&window.after), vec!["4", "5"]);
+
+        // 开头附近返回的词条较少
+        let window = db.entries_window("あ", 3, 1).unwrap();
+        assert!(window.before.is_empty());
+        assert_eq!(data_ids(&window.after), vec!["2"]);
+
+        // 读音不存在时返回插入位置前后的词条
+        let window = db.entries_window("あえ", 1, 1).unwrap();
+        assert!(window.target.is_none());
+        assert_eq!(data_ids(&window.before), vec!["3"]);
+        assert_eq!(data_ids(&window.after), vec!["4"]);
+
+        // 按五十音顺序：が在か之后、き之前；片假名与平假名读音同序
+        for (data_id, reading) in [("6", "き"), ("7", "が"), ("8", "か")] {
+            let mut entry = sample_entry(data_id, reading);
+            entry.kana_reading = Some(reading.to_string());
+            db.insert_entry(&entry).unwrap();
+        }
+        let window = db.entries_window("ガ", 1, 1).unwrap();
+        assert_eq!(window.target.unwrap().data_id, "7");
+        assert_eq!(data_ids(&window.before), vec!["8"]);
+        assert_eq!(data_ids(&window.after), vec!["6"]);
+    }
+
+    #[test]
+    fn test_search_by_expansion() {
+        let db = test_db();
+        let mut entry = sample_entry("1", "DNA");
+        entry.expansion = Some("deoxyribonucleic acid".to_string());
+        db.insert_entry(&entry).unwrap();
+
+        let found = db.search_by_headword("Nucleic").unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].expansion.as_deref(), Some("deoxyribonucleic acid"));
+    }
+
+    #[test]
+    fn test_compact() {
+        let db = test_db();
+        db.seed_demo().unwrap();
+        db.compact().unwrap();
+        assert_eq!(db.get_stats().unwrap().0, 50);
+        let analyzed: i64 = db.conn.query_row("SELECT COUNT(*) FROM sqlite_stat1", [], |row| row.get(0)).unwrap();
+        assert!(analyzed > 0);
+    }
+
+    #[test]
+    fn test_rebuild_indexes() {
+        let db = test_db();
+        db.seed_demo().unwrap();
+        db.rebuild_indexes().unwrap();
+        let analyzed: i64 = db.conn.query_row("SELECT COUNT(*) FROM sqlite_stat1", [], |row| row.get(0)).unwrap();
+        assert!(analyzed > 0);
+        assert!(!db.search_in_definitions("いとしく", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_import_skips_invalid_utf8_lines() {
+        let db = test_db();
+        let mut content = Vec::new();
+        content.extend_from_slice(format!("あい\n{}\n\n", sample_html("t1", "あい")).as_bytes());
+        content.extend_from_slice(b"\xff\xfe\n");
+        content.extend_from_slice(format!("{}\n\n", sample_html("t2", "いい")).as_bytes());
+        content.extend_from_slice(format!("うみ\r\n{}\r\n", sample_html("t3", "うみ")).as_bytes());
+
+        assert_eq!(db.import_from_reader(content.as_slice()).unwrap(), 2);
+        assert_eq!(db.get_by_data_ids(&["t1".to_string(), "t2".to_string(), "t3".to_string()]).unwrap().len(), 2);
+        assert_eq!(db.find_by_headword("うみ").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_import_from_cleaned_files() {
+        let db = test_db();
+        let dir = std::env::temp_dir();
+        let a_row = dir.join(format!("obunsha_import_a_{}.txt", std::process::id()));
+        let ka_row = dir.join(format!("obunsha_import_ka_{}.txt", std::process::id()));
+        std::fs::write(&a_row, format!("あい\n{}\n\nあお\n{}\n", sample_html("m1", "あい"), sample_html("m2", "あお"))).unwrap();
+        std::fs::write(&ka_row, format!("かい\n{}\n", sample_html("m3", "かい"))).unwrap();
+
+        let paths = [a_row.to_str().unwrap(), ka_row.to_str().unwrap()];
+        let imported = db.import_from_cleaned_files(&paths);
+        std::fs::remove_file(&a_row).unwrap();
+        std::fs::remove_file(&ka_row).unwrap();
+
+        assert_eq!(imported.unwrap(), 3);
+        assert_eq!(db.get_stats().unwrap().0, 3);
+        assert_eq!(db.search_in_definitions("説明", 10).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_import_resumes_from_offset() {
+        let db = test_db();
+        let first = format!("あい\n{}\n\n", sample_html("r1", "あい"));
+        let rest = format!("あお\n{}\n\nかい\n{}\n", sample_html("r2", "あお"), sample_html("r3", "かい"));
+        let path = std::env::temp_dir().join(format!("obunsha_resume_{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+        let marker = format!("{}{}", path, IMPORT_OFFSET_SUFFIX);
+        std::fs::write(path, format!("{}{}", first, rest)).unwrap();
+        let (file_len, modified) = file_fingerprint(&std::fs::metadata(path).unwrap()).unwrap();
+        // 模拟上次导入在第一个词条之后中断
+        let progress = ImportProgress { offset: first.len() as u64, entries: 1, lines: 3, file_len, modified };
+        assert_eq!(ImportProgress::parse(&progress.to_line()), Some(progress));
+
+        // 文件大小与记录不一致时拒绝继续
+        std::fs::write(&marker, ImportProgress { file_len: file_len + 1, ..progress }.to_line()).unwrap();
+        let mismatched = db.import_from_cleaned_data(path, true);
+
+        std::fs::write(&marker, progress.to_line()).unwrap();
+        let imported = db.import_from_cleaned_data(path, true);
+        let marker_left = std::path::Path::new(&marker).exists();
+        std::fs::remove_file(path).unwrap();
+        let _ = std::fs::remove_file(&marker);
+
+        assert!(mismatched.is_err());
+        // 返回值包含中断前已导入的词条
+        assert_eq!(imported.unwrap(), 3);
+        assert!(!marker_left);
+        assert!(db.get_by_data_ids(&["r1".to_string()]).unwrap().is_empty());
+        assert_eq!(db.get_by_data_ids(&["r2".to_string(), "r3".to_string()]).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_seed_demo() {
+        let db = test_db();
+        let count = db.seed_demo().unwrap();
+        assert_eq!(count, 50);
+        assert_eq!(db.count_parse_failures().unwrap(), 0);
+
+        // 同形异义词
+        assert_eq!(db.search_by_kana_exact("かんしょう").unwrap().len(), 4);
+        // 英文缩写
+        assert_eq!(db.find_by_headword("AI").unwrap().len(), 1);
+        // 重定向
+        assert_eq!(
+            db.resolve_redirect_chain("愛おしい", 5).unwrap(),
+            Some("いとしい【愛しい】".to_string())
+        );
+    }
+
+    #[test]
+    fn test_headword_prefix_and_suffix_search() {
+        let db = test_db();
+        let mut kagakuteki = sample_entry("1", "かがくてき【科学的】");
+        kagakuteki.kanji_writing = Some("科学的".to_string());
+        let mut kagaku = sample_entry("2", "かがく【科学】");
+        kagaku.kanji_writing = Some("科学".to_string());
+        db.insert_entries_batch(&[kagakuteki, kagaku]).unwrap();
+
+        assert_eq!(db.search_headword_prefix("かがく", 10).unwrap().len(), 2);
+        let suffix = db.search_headword_suffix("的", 10).unwrap();
+        assert_eq!(suffix.len(), 1);
+        assert_eq!(suffix[0].data_id, "1");
+        // 通配符按字面匹配
+        assert!(db.search_headword_suffix("%", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_search_by_kana_pattern() {
+        let db = test_db();
+        let readings = [("1", "かっぱつ"), ("2", "かしゃつ"), ("3", "カタマツ"), ("4", "かつ"), ("5", "きゃくつ")];
+        for (data_id, reading) in readings {
+            let mut entry = sample_entry(data_id, reading);
+            entry.kana_reading = Some(reading.to_string());
+            db.insert_entry(&entry).unwrap();
+        }
+
+        let data_ids = |pattern: &str| -> Vec<String> {
+            db.search_by_kana_pattern(pattern, 100).unwrap().into_iter().map(|entry| entry.data_id).collect()
+        };
+        assert_eq!(data_ids("か__つ"), vec!["1", "3"]);
+        // 拗音算一拍
+        assert_eq!(data_ids("か_つ"), vec!["2"]);
+        assert_eq!(data_ids("きゃ_つ"), vec!["5"]);
+        assert_eq!(data_ids("か*つ").len(), 4);
+        assert_eq!(data_ids("___"), vec!["2", "5"]);
+    }
+
+    #[test]
+    fn test_suggest_merges_kana_and_kanji() {
+        let db = test_db();
+        db.seed_demo().unwrap();
+
+        let by_kana = db.suggest("あつ", 10).unwrap();
+        assert!(by_kana.iter().all(|entry| entry.kana_reading.as_deref().unwrap_or("").starts_with("あつ")));
+        assert_eq!(by_kana.len(), 3);
+
+        let by_kanji = db.suggest("熱", 10).unwrap();
+        assert_eq!(by_kanji[0].kanji_writing.as_deref(), Some("熱い"));
+        assert!(by_kanji.iter().any(|entry| entry.kanji_writing.as_deref() == Some("熱湯")));
+
+        // 完全一致排在最前
+        let exact = db.suggest("かぜ", 10).unwrap();
+        assert_eq!(exact[0].kana_reading.as_deref(), Some("かぜ"));
+    }
+
+    #[test]
+    fn test_schema_version_recorded() {
+        let db = test_db();
+        assert_eq!(db.schema_version().unwrap(), SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_get_by_data_ids_preserves_order() {
+        let db = test_db();
+        db.insert_entries_batch(&[sample_entry("1", "あい"), sample_entry("2", "こい"), sample_entry("3", "すき")]).unwrap();
+
+        let ids: Vec<String> = ["3", "missing", "1"].iter().map(|id| id.to_string()).collect();
+        let entries = db.get_by_data_ids(&ids).unwrap();
+        let found: Vec<&str> = entries.iter().map(|entry| entry.data_id.as_str()).collect();
+        assert_eq!(found, vec!["3", "1"]);
+
+        // 超过单条语句参数上限时分批查询
+        let many: Vec<String> = (0..2500).map(|i| i.to_string()).collect();
+        assert_eq!(db.get_by_data_ids(&many).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_diff_against() {
+        let old = test_db();
+        let mut changed = sample_entry("2", "こい");
+        old.insert_entries_batch(&[sample_entry("1", "あい"), changed.clone(), sample_entry("3", "すき")]).unwrap();
+
+        let new = test_db();
+        changed.definition_html = "<div>新しい釈義</div>".to_string();
+        new.insert_entries_batch(&[sample_entry("1", "あい"), changed, sample_entry("4", "ゆめ")]).unwrap();
+
+        let diff = old.diff_against(&new).unwrap();
+        assert_eq!(diff.added, vec![DiffEntry { data_id: "4".to_string(), headword: "ゆめ".to_string() }]);
+        assert_eq!(diff.removed, vec![DiffEntry { data_id: "3".to_string(), headword: "すき".to_string() }]);
+        assert_eq!(diff.modified, vec![DiffEntry { data_id: "2".to_string(), headword: "こい".to_string() }]);
+        assert_eq!(diff.unchanged, 1);
+    }
+
+    #[test]
+    fn test_integrity_check() {
+        let db = test_db();
+        // 空表不通过
+        assert!(!db.integrity_check().unwrap());
+
+        db.insert_entries_batch(&[sample_entry("1", "あい【愛】"), sample_entry("2", "こい【恋】")]).unwrap();
+        assert!(db.integrity_check().unwrap());
+
+        // 缺少必需列时不通过
+        db.conn.execute("ALTER TABLE obunsha_kokugo_dict DROP COLUMN conjugation", []).unwrap();
+        assert!(!db.integrity_check().unwrap());
+    }
+
+    #[test]
+    fn test_iter_entries_spans_pages() {
+        let db = test_db();
+        let entries: Vec<ObunshaDictEntry> = (0..ITER_PAGE_SIZE + 7)
+            .map(|i| sample_entry(&i.to_string(), &format!("見出し{}", i)))
+            .collect();
+        db.insert_entries_batch(&entries).unwrap();
+
+        let data_ids = |order: ExportOrder| -> Vec<String> {
+            db.iter_entries(order).unwrap().map(|entry| entry.unwrap().data_id).collect()
+        };
+        let inserted = data_ids(ExportOrder::Insertion);
+        assert_eq!(inserted.len(), ITER_PAGE_SIZE + 7);
+        assert_eq!(inserted[0], "0");
+        assert_eq!(inserted[ITER_PAGE_SIZE + 6], (ITER_PAGE_SIZE + 6).to_string());
+
+        // 按其他顺序跨页遍历时既不漏也不重复
+        let mut expected = inserted.clone();
+        expected.sort();
+        assert_eq!(data_ids(ExportOrder::DataId), expected);
+    }
+
+    #[test]
+    fn test_kana_validation_modes() {
+        let mut bad = sample_entry("1", "あい【愛");
+        bad.kana_reading = Some("あい【愛".to_string());
+
+        let mut db = test_db();
+        db.insert_entries_batch(&[bad.clone()]).unwrap();
+        assert_eq!(db.kana_violation_count(), 1);
+
+        // 严格模式下被拒绝的词条进入隔离区，同一批的其他词条照常写入
+        db.set_strict_kana(true);
+        bad.data_id = "2".to_string();
+        bad.raw_mdx_content = "あい【愛\n<div>bad</div>".to_string();
+        let mut good = sample_entry("5", "こい【恋】");
+        good.definition_html = "<div>異性を慕う心。</div>".to_string();
+        good.definition_text = "異性を慕う心。".to_string();
+        assert_eq!(db.insert_entries_batch(&[bad.clone(), good]).unwrap(), 1);
+        assert!(db.get_by_data_ids(&["2".to_string()]).unwrap().is_empty());
+        assert_eq!(db.get_by_data_ids(&["5".to_string()]).unwrap().len(), 1);
+        assert_eq!(db.count_parse_failures().unwrap(), 1);
+        match db.insert_entry(&bad) {
+            Err(rusqlite::Error::SqliteFailure(error, _)) => assert_eq!(error.extended_code, rusqlite::ffi::SQLITE_CONSTRAINT_CHECK),
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        // 严格模式只因读音拒绝词条，/validate 的其他问题只警告
+        let mut unclosed = sample_entry("3", "あい【愛】");
+        unclosed.definition_html = "<div>いとしく思う心。".to_string();
+        unclosed.definition_text = "いとしく思う心。".to_string();
+        assert!(!unclosed.validate().is_empty());
+        assert!(db.insert_entry(&unclosed).is_ok());
+    }
+
+    #[test]
+    fn test_fullwidth_headword_matches_halfwidth() {
+        let db = test_db();
+        db.insert_entries_batch(&[sample_entry("1", "AI")]).unwrap();
+
+        let found = db.find_by_headword("ＡＩ").unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].headword, "AI");
+        assert_eq!(db.search_headword_prefix("Ａ", 10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_headword_normalized_backfill() {
+        let db = test_db();
+        db.insert_entry(&sample_entry("1", "㍿")).unwrap();
+        db.conn.execute("UPDATE obunsha_kokugo_dict SET headword_normalized = NULL", []).unwrap();
+
+        db.initialize().unwrap();
+        assert_eq!(db.find_by_headword("株式会社").unwrap().len(), 1);
+    }
+}
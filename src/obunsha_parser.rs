@@ -0,0 +1,1100 @@
+use scraper::{ElementRef, Html, Node};
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+
+use crate::data_cleaner::strip_invisible;
+
+/// 旺文社国語辞典词条结构 (Obunsha Kokugo Dictionary Entry)
+/// 基于MDX格式的专业日语词典数据
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObunshaDictEntry {
+    pub id: Option<i64>,
+    /// 词条ID - 来源于MDX的data-id
+    pub data_id: String,
+    /// 词条类型 - 来源于MDX的data-type  
+    pub data_type: String,
+    /// 词条标题 - 从MDX关键词提取的标题
+    pub headword: String,
+    /// 假名读音 - 提取的假名部分
+    pub kana_reading: Option<String>,
+    /// 汉字表记 - 提取的汉字部分
+    pub kanji_writing: Option<String>,
+    /// 汉字表记的各个写法 - 将"聞・聴"这样的多重表记按中点拆开，不存入数据库
+    #[serde(default)]
+    pub kanji_variants: Vec<String>,
+    /// 英文缩写的全称 - 如DNA词条的"deoxyribonucleic acid"，只有缩写词条才有
+    #[serde(default)]
+    pub expansion: Option<String>,
+    /// 词性信息 - 如"自五"等语法信息
+    pub part_of_speech: Option<String>,
+    /// 活用形 - 动词、形容词的变化形式
+    pub conjugation: Option<String>,
+    /// 词条定义 - 完整的HTML定义内容
+    pub definition_html: String,
+    /// 纯文本定义 - 去除HTML标签的纯文本版本
+    pub definition_text: String,
+    /// 原始MDX内容 - 保留完整的原始数据
+    pub raw_mdx_content: String,
+}
+
+impl ObunshaDictEntry {
+    /// 校验词条，返回发现的问题（为空表示通过），不访问数据库
+    /// 检查标题和释义非空、假名读音只含假名、definition_html标签配对；严格模式导入只拒绝读音问题（见kana_reading_problem）
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = self.content_problems();
+        problems.extend(self.kana_reading_problem());
+        problems
+    }
+
+    /// 假名读音含非假名字符时返回问题描述，严格模式导入据此拒绝词条
+    pub fn kana_reading_problem(&self) -> Option<String> {
+        let kana = self.kana_reading.as_deref()?;
+        if crate::kana::is_valid_kana_reading(kana) {
+            return None;
+        }
+        Some(format!("kana_reading 含非假名字符: {:?}", kana))
+    }
+
+    /// validate中读音以外的检查：必填字段非空、definition_html标签配对
+    pub fn content_problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.data_id.trim().is_empty() {
+            problems.push("data_id 不能为空".to_string());
+        }
+        if self.headword.trim().is_empty() {
+            problems.push("headword 不能为空".to_string());
+        }
+        if self.definition_text.trim().is_empty() {
+            problems.push("definition_text 不能为空".to_string());
+        }
+        if self.definition_html.trim().is_empty() {
+            problems.push("definition_html 不能为空".to_string());
+        } else if let Err(message) = check_html_well_formed(&self.definition_html) {
+            problems.push(format!("definition_html 格式错误: {}", message));
+        }
+        problems
+    }
+
+    /// 是否为只有指向另一个词的释义、没有实际内容的词条（见 is_pure_redirect_text）
+    pub fn is_pure_redirect(&self) -> bool {
+        is_pure_redirect_text(&self.definition_text)
+    }
+}
+
+/// 纯重定向词条释义的最大字符数
+pub const PURE_REDIRECT_MAX_CHARS: usize = 30;
+
+/// 判断释义是否只是指向另一个词（如"⇒あいきょう（愛敬）"）：
+/// 不超过PURE_REDIRECT_MAX_CHARS个字符，只含一个→或⇒，箭头后是单个标题（不含空白和句读）
+pub fn is_pure_redirect_text(definition_text: &str) -> bool {
+    let text = definition_text.trim();
+    if text.chars().count() > PURE_REDIRECT_MAX_CHARS {
+        return false;
+    }
+
+    let mut parts = text.split(['→', '⇒']);
+    let (Some(_), Some(target), None) = (parts.next(), parts.next(), parts.next()) else {
+        return false;
+    };
+    let target = target.trim().trim_end_matches('。');
+    !target.is_empty() && !target.contains(|ch: char| ch.is_whitespace() || matches!(ch, '、' | '。' | '，' | '❶'))
+}
+
+/// 旺文社国語辞典HTML解析器
+/// 不依赖数据库，可单独用于解析导出的词条（包括编译到WebAssembly）
+#[derive(Debug)]
+pub struct ObunshaParser {
+    /// 纯文本定义中连接各义项的分隔符，默认为空格
+    sense_separator: String,
+    /// clean_kana_text把非空输入清理为空字符串的次数（假名全是符号，无法解析）
+    empty_kana_results: Cell<usize>,
+}
+
+impl Default for ObunshaParser {
+    fn default() -> Self {
+        ObunshaParser {
+            sense_separator: " ".to_string(),
+            empty_kana_results: Cell::new(0),
+        }
+    }
+}
+
+impl ObunshaParser {
+    /// 创建新的解析器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 使用指定分隔符连接义项的解析器，如 "\n" 保留义项边界
+    pub fn with_sense_separator(join_with: &str) -> Self {
+        ObunshaParser {
+            sense_separator: join_with.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// clean_kana_text把非空输入清理为空字符串的次数，用于统计需要人工整理的词条
+    pub fn empty_kana_count(&self) -> usize {
+        self.empty_kana_results.get()
+    }
+
+    /// 从HTML解析单个词条
+    pub fn parse_entry_from_html(&self, raw_title: &str, html: &str) -> Option<ObunshaDictEntry> {
+        use scraper::{Html, Selector};
+
+        // 标题中的零宽字符等会导致精确匹配失败，原始内容保留在raw_mdx_content中
+        let title = strip_invisible(raw_title);
+        let title = title.as_str();
+
+        let document = Html::parse_fragment(html);
+        
+        // 提取data-id
+        let container_selector = Selector::parse("container").ok()?;
+        let container = document.select(&container_selector).next()?;
+        let data_id = container.value().attr("data-id")?.to_string();
+        let data_type = container.value().attr("data-type").unwrap_or("unknown").to_string();
+
+        // CSS选择器
+        let kana_selector = Selector::parse(".headword_kana").ok()?;
+        let kanji_selector = Selector::parse(".headword_hyouki").ok()?;
+        let ryaku_selector = Selector::parse(".headword_ryaku").ok()?;
+        let pos_selector = Selector::parse(".pos_s").ok()?;
+        let katsuyo_selector = Selector::parse(".katsuyo").ok()?;
+
+        let mut kana_reading: Option<String> = None;
+        let mut kanji_writing: Option<String> = None;
+        let mut part_of_speech: Option<String> = None;
+        let mut conjugation: Option<String> = None;
+
+        // headline中的假名含有非假名符号时暂存，优先使用HTML中的假名
+        let mut noisy_headline_kana: Option<String> = None;
+
+        // 优先从headline（title）解析假名和汉字
+        if let Some((kana, kanji)) = self.parse_headline(title) {
+            if self.is_kana_only(&kana) {
+                kana_reading = Some(kana);
+            } else {
+                noisy_headline_kana = Some(kana);
+            }
+            kanji_writing = Some(kanji);
+        }
+
+        // 如果从headline解析失败，再从HTML中选择器提取
+        if kana_reading.is_none() {
+            if let Some(kana_element) = document.select(&kana_selector).next() {
+                let kana_text = kana_element.text().collect::<String>();
+                let cleaned_kana = self.clean_kana_text(&kana_text);
+                if !cleaned_kana.is_empty() {
+                    kana_reading = Some(cleaned_kana);
+                }
+            }
+        }
+
+        if kanji_writing.is_none() {
+            if let Some(kanji_element) = document.select(&kanji_selector).next() {
+                let kanji_text = kanji_element.text().collect::<String>();
+                let cleaned_kanji = self.clean_kanji_text(&kanji_text);
+                if !cleaned_kanji.is_empty() {
+                    kanji_writing = Some(cleaned_kanji);
+                }
+            }
+        }
+
+        // HTML中也没有假名时，退回使用headline中的假名
+        if kana_reading.is_none() {
+            kana_reading = noisy_headline_kana;
+        }
+
+        // 对于英文缩写词条，提取ryaku作为假名读音
+        if kana_reading.is_none() {
+            if let Some(ryaku_element) = document.select(&ryaku_selector).next() {
+                let ryaku_text = ryaku_element.text().collect::<String>();
+                let cleaned_ryaku = self.clean_kana_text(&ryaku_text);
+                if !cleaned_ryaku.is_empty() {
+                    kana_reading = Some(cleaned_ryaku);
+                }
+            }
+        }
+
+        // 最后从MDX关键词本身提取假名（去掉括号中的汉字，如"あい〔愛〕"、"愛(あい)"）
+        if kana_reading.is_none() {
+            kana_reading = self.kana_from_title(title);
+        }
+
+        // 提取词性信息
+        if let Some(pos_element) = document.select(&pos_selector).next() {
+            let pos_text = strip_invisible(pos_element.text().collect::<String>().trim());
+            if !pos_text.is_empty() {
+                part_of_speech = Some(pos_text);
+            }
+        }
+
+        // 提取活用形
+        if let Some(katsuyo_element) = document.select(&katsuyo_selector).next() {
+            let katsuyo_text = strip_invisible(katsuyo_element.text().collect::<String>().trim());
+            if !katsuyo_text.is_empty() {
+                conjugation = Some(katsuyo_text);
+            }
+        }
+
+        // 提取纯文本定义
+        let definition_text = strip_invisible(&self.extract_definition_text(&document, &self.sense_separator));
+
+        // 英文缩写词条：从释义中提取全称
+        let expansion = expansion_from_document(&document, &definition_text);
+
+        Some(ObunshaDictEntry {
+            id: None,
+            data_id,
+            data_type,
+            headword: title.to_string(),
+            kana_reading,
+            kanji_variants: kanji_writing.as_deref().map(split_kanji_variants).unwrap_or_default(),
+            kanji_writing,
+            expansion,
+            part_of_speech,
+            conjugation,
+            definition_html: html.to_string(),
+            definition_text,
+            raw_mdx_content: format!("{}\n{}", raw_title, html),
+        })
+    }
+
+    /// 说明词条无法解析的原因（parse_entry_from_html返回None时调用）
+    pub fn diagnose_failure(&self, html: &str) -> String {
+        use scraper::{Html, Selector};
+
+        let document = Html::parse_fragment(html);
+        let container_selector = match Selector::parse("container") {
+            Ok(selector) => selector,
+            Err(_) => return "选择器无效".to_string(),
+        };
+        match document.select(&container_selector).next() {
+            None => "缺少container元素".to_string(),
+            Some(container) if container.value().attr("data-id").is_none() => "container缺少data-id属性".to_string(),
+            Some(_) => "未知原因".to_string(),
+        }
+    }
+
+    /// 从headline解析假名和汉字
+    pub fn parse_headline(&self, headline: &str) -> Option<(String, String)> {
+        let headline = headline.trim();
+        
+        // 检查是否包含【】括号格式：假名【汉字】
+        if let Some(start) = headline.find('【') {
+            if let Some(end) = headline.find('】') {
+                if start < end {
+                    // 使用chars()迭代器来正确处理中文字符
+                    let chars: Vec<char> = headline.chars().collect();
+                    
+                    // 将字节索引转换为字符索引
+                    let start_char = headline[..start].chars().count();
+                    let end_char = headline[..end].chars().count();
+                    
+                    if start_char < end_char && start_char < chars.len() && end_char < chars.len() {
+                        let kana_part = self.strip_reading_dots(chars[..start_char].iter().collect());
+                        let kanji_part: String = chars[start_char + 1..end_char].iter().collect();
+                        
+                        // 假名部分不能为空，汉字部分可以为空（如：ば【】）
+                        if !kana_part.is_empty() {
+                            return Some((kana_part, kanji_part));
+                        }
+                    }
+                }
+            }
+        }
+        
+        // 如果没有括号，检查是否只有假名
+        if !headline.is_empty() {
+            // 检查是否包含汉字
+            let has_kanji = headline.chars().any(|c| {
+                c >= '\u{4e00}' && c <= '\u{9fff}' // CJK统一汉字
+            });
+            
+            if !has_kanji {
+                // 只有假名的情况
+                return Some((self.strip_reading_dots(headline.to_string()), String::new()));
+            }
+        }
+        
+        None
+    }
+
+    /// 检查文本是否只由假名组成（平假名、片假名、长音符号、中点）
+    fn is_kana_only(&self, text: &str) -> bool {
+        !text.is_empty() && text.chars().all(|ch| matches!(ch,
+            '\u{3041}'..='\u{309f}' | '\u{30a0}'..='\u{30ff}' | '·' | '‧'
+        ))
+    }
+
+    /// 去掉headline读音中表示活用分界的中点（あが・く → あがく）
+    /// 含其他符号的读音保持原样，由调用方改用HTML中的假名
+    fn strip_reading_dots(&self, kana: String) -> String {
+        if self.is_kana_only(&kana) {
+            self.clean_kana_text(&kana.replace('・', ""))
+        } else {
+            kana
+        }
+    }
+
+    /// 清理假名文本，去除特殊符号和HTML标签
+    /// 非空输入被清理为空字符串时计数（见empty_kana_count）
+    pub fn clean_kana_text(&self, text: &str) -> String {
+        let result = Self::clean_kana_chars(text);
+        if result.is_empty() && !text.trim().is_empty() {
+            self.empty_kana_results.set(self.empty_kana_results.get() + 1);
+        }
+        result
+    }
+
+    /// 只保留假名、长音符号和英文缩写字符
+    fn clean_kana_chars(text: &str) -> String {
+        let mut result = String::new();
+        
+        for ch in text.chars() {
+            match ch {
+                // 保留平假名
+                '\u{3040}'..='\u{309f}' => result.push(ch),
+                // 保留片假名
+                '\u{30a0}'..='\u{30ff}' => result.push(ch),
+                // 保留片假名长音符号
+                'ー' => result.push(ch),
+                // 保留英文和数字（用于英文缩写词条）
+                _ if ch.is_ascii_alphanumeric() => result.push(ch),
+                // 对于英文词条，保留连字符和下划线
+                '-' | '_' if text.chars().any(|c| c.is_ascii_alphabetic()) => result.push(ch),
+                // 过滤掉所有其他符号，包括日语词条中的ASCII连字符
+                _ => {}
+            }
+        }
+        
+        result.trim().to_string()
+    }
+
+    /// 从MDX关键词中提取假名：去掉含汉字的括号部分后清理出假名，关键词中没有假名时返回None
+    /// 括号外有汉字时（如"愛する"、"動く"）只用括号中的读音，没有读音时返回None，
+    /// 避免把送假名（する、く）当作读音
+    fn kana_from_title(&self, title: &str) -> Option<String> {
+        let is_kanji = |c: char| ('\u{4e00}'..='\u{9fff}').contains(&c);
+        let mut stripped = String::new();
+        let mut bracket_reading = String::new();
+        let mut bracket: Option<String> = None;
+
+        for ch in title.chars() {
+            match (ch, bracket.as_mut()) {
+                ('【' | '〔' | '（' | '(' | '［' | '[', None) => bracket = Some(String::new()),
+                ('】' | '〕' | '）' | ')' | '］' | ']', Some(content)) => {
+                    // 不含汉字的括号内容（如读音）保留
+                    if !content.chars().any(is_kanji) {
+                        stripped.push_str(content);
+                        bracket_reading.push_str(content);
+                    }
+                    bracket = None;
+                }
+                (_, Some(content)) => content.push(ch),
+                (_, None) => stripped.push(ch),
+            }
+        }
+        // 括号未闭合时按普通文本处理
+        if let Some(content) = bracket {
+            stripped.push_str(&content);
+        }
+
+        if stripped.chars().any(is_kanji) {
+            stripped = bracket_reading;
+        }
+
+        // 关键词本来就可能不含假名，不计入empty_kana_count
+        let kana = Self::clean_kana_chars(&stripped);
+        let has_kana = kana.chars().any(|c| ('\u{3040}'..='\u{30ff}').contains(&c));
+        if has_kana { Some(kana) } else { None }
+    }
+
+    /// 清理汉字文本，去除标记符号
+    /// 中点（・、‧、·）统一为・，连续的中点合并为一个，首尾的中点去掉
+    pub fn clean_kanji_text(&self, text: &str) -> String {
+        let mut result = String::new();
+        
+        for ch in text.chars() {
+            match ch {
+                // 统一中点，不产生连续或开头的中点
+                '・' | '‧' | '·' if !result.is_empty() && !result.ends_with('・') => result.push('・'),
+                '・' | '‧' | '·' => {},
+                // 保留汉字 (CJK统一汉字)
+                '\u{4e00}'..='\u{9fff}' => result.push(ch),
+                // 保留平假名
+                '\u{3040}'..='\u{309f}' => result.push(ch),
+                // 保留片假名
+                '\u{30a0}'..='\u{30ff}' => result.push(ch),
+                // 保留一些基本符号（长音ー已包含在片假名范围内）
+                '-' => result.push(ch),
+                // 过滤掉标记符号
+                '【' | '】' | '◇' | '△' | '▽' | '▲' | '▼' | '○' | '●' | '◯' | 
+                '□' | '■' | '▢' | '▣' | '◆' | '※' | '＊' | '☆' | '★' => {
+                    // 跳过这些标记符号
+                },
+                // 保留其他可能有用的字符（如英文、数字）
+                _ if ch.is_alphanumeric() => result.push(ch),
+                _ => {} // 跳过其他特殊符号
+            }
+        }
+        
+        result.trim_end_matches('・').trim().to_string()
+    }
+
+    /// 提取定义的纯文本内容，各义项用 `join_with` 连接
+    fn extract_definition_text(&self, document: &Html, join_with: &str) -> String {
+        use scraper::Selector;
+
+        let meaning_selectors = [
+            ".mean_normal",
+            ".mean_lv_2", 
+            ".mean_lv_1",
+            ".mean_no_1",
+            ".mean_no_2", 
+            ".mean_no_3",
+        ];
+        
+        let mut meanings = Vec::new();
+        
+        for selector_str in &meaning_selectors {
+            if let Ok(selector) = Selector::parse(selector_str) {
+                for element in document.select(&selector) {
+                    let text = element.text().collect::<Vec<_>>().join("");
+                    let cleaned_text = text.trim();
+                    if !cleaned_text.is_empty() {
+                        meanings.push(cleaned_text.to_string());
+                    }
+                }
+            }
+        }
+        
+        if meanings.is_empty() {
+            // 如果没有找到特定的释义元素，提取所有文本
+            document.root_element().text().collect::<String>().trim().to_string()
+        } else {
+            meanings.join(join_with)
+        }
+    }
+}
+
+/// 例句所在元素的class
+const EXAMPLE_CLASSES: [&str; 2] = ["ex_text", "example"];
+
+/// 统计释义HTML中的例句数（嵌套在另一个例句元素中的不重复计数）
+pub fn count_examples(definition_html: &str) -> usize {
+    let document = Html::parse_fragment(definition_html);
+    let is_example = |element: ElementRef| element.value().classes().any(|class| EXAMPLE_CLASSES.contains(&class));
+    document
+        .root_element()
+        .descendants()
+        .filter_map(ElementRef::wrap)
+        .filter(|element| is_example(*element))
+        .filter(|element| !element.ancestors().filter_map(ElementRef::wrap).any(is_example))
+        .count()
+}
+
+/// 没有结束标签的HTML元素
+const VOID_TAGS: [&str; 13] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source", "wbr",
+];
+
+/// 检查HTML片段的标签是否正确配对和嵌套，返回第一个问题
+/// 只检查标签结构，不校验属性和元素名是否合法
+pub fn check_html_well_formed(html: &str) -> Result<(), String> {
+    let mut open_tags: Vec<String> = Vec::new();
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start..];
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            let end = comment.find("-->").ok_or("注释未结束")?;
+            rest = &comment[end + 3..];
+            continue;
+        }
+
+        // 引号内的 > 不结束标签
+        let mut quote = None;
+        let end = rest
+            .char_indices()
+            .skip(1)
+            .find(|&(_, ch)| match quote {
+                Some(q) => {
+                    if ch == q {
+                        quote = None;
+                    }
+                    false
+                }
+                None if ch == '"' || ch == '\'' => {
+                    quote = Some(ch);
+                    false
+                }
+                None => ch == '>',
+            })
+            .map(|(index, _)| index)
+            .ok_or_else(|| format!("标签未结束: {}", rest.chars().take(20).collect::<String>()))?;
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+
+        if tag.starts_with('!') || tag.starts_with('?') {
+            continue;
+        }
+        let (closing, body) = match tag.strip_prefix('/') {
+            Some(body) => (true, body),
+            None => (false, tag),
+        };
+        let name: String = body
+            .chars()
+            .take_while(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | ':'))
+            .collect::<String>()
+            .to_ascii_lowercase();
+        if name.is_empty() {
+            return Err(format!("无效的标签: <{}>", tag));
+        }
+
+        if closing {
+            match open_tags.pop() {
+                Some(open) if open == name => {}
+                Some(open) => return Err(format!("</{}> 与 <{}> 不匹配", name, open)),
+                None => return Err(format!("多余的结束标签 </{}>", name)),
+            }
+        } else if !body.trim_end().ends_with('/') && !VOID_TAGS.contains(&name.as_str()) {
+            open_tags.push(name);
+        }
+    }
+
+    match open_tags.pop() {
+        Some(open) => Err(format!("<{}> 未闭合", open)),
+        None => Ok(()),
+    }
+}
+
+/// 转换为Markdown时跳过的元素
+const SKIPPED_TAGS: [&str; 3] = ["link", "style", "script"];
+
+/// Markdown转换的中间片段
+enum MarkdownSegment {
+    Text(String),
+    Example(String),
+}
+
+/// 括号中由拉丁字母组成的片段（保留原大小写），按出现顺序
+fn bracketed_latin_segments(text: &str) -> Vec<String> {
+    let mut segments = Vec::new();
+    let mut depth = 0usize;
+    let mut current = String::new();
+
+    let mut flush = |current: &mut String| {
+        let segment = current.trim().trim_matches(['-', '\'', '.']).trim().to_string();
+        if segment.chars().filter(|ch| ch.is_ascii_alphabetic()).count() >= 2 {
+            segments.push(segment);
+        }
+        current.clear();
+    };
+
+    for ch in text.chars() {
+        match ch {
+            '（' | '(' | '［' | '[' | '〔' => {
+                flush(&mut current);
+                depth += 1;
+            }
+            '）' | ')' | '］' | ']' | '〕' => {
+                flush(&mut current);
+                depth = depth.saturating_sub(1);
+            }
+            _ if depth > 0 && (ch.is_ascii_alphabetic() || matches!(ch, ' ' | '-' | '\'' | '.')) => current.push(ch),
+            _ => flush(&mut current),
+        }
+    }
+
+    segments
+}
+
+/// 缩写词条（含.headword_ryaku）的全称，非缩写词条返回None
+fn expansion_from_document(document: &Html, definition_text: &str) -> Option<String> {
+    let ryaku_selector = scraper::Selector::parse(".headword_ryaku").ok()?;
+    let abbreviation = document.select(&ryaku_selector).next()?.text().collect::<String>();
+    extract_expansion(abbreviation.trim(), definition_text)
+}
+
+/// 从已存储的definition_html和纯文本释义计算缩写全称，供迁移回填旧数据库使用
+pub fn expansion_from_html(definition_html: &str, definition_text: &str) -> Option<String> {
+    expansion_from_document(&Html::parse_fragment(definition_html), definition_text)
+}
+
+/// 从缩写词条的释义中提取全称（如"DNA"的"deoxyribonucleic acid"）
+/// 取括号中至少两个单词的英文片段，优先选首字母与缩写相同的；
+/// 没有括号时取释义第一行开头的英文（如"deoxyribonucleic acid の略。"）
+pub fn extract_expansion(abbreviation: &str, definition_text: &str) -> Option<String> {
+    let first_letter = abbreviation
+        .chars()
+        .find(|ch| ch.is_ascii_alphabetic())
+        .map(|ch| ch.to_ascii_lowercase());
+    let is_phrase = |segment: &str| segment.split_whitespace().count() >= 2;
+    let starts_like_abbreviation = |segment: &str| {
+        segment.chars().next().map(|ch| ch.to_ascii_lowercase()) == first_letter
+    };
+
+    let segments = bracketed_latin_segments(definition_text);
+    let candidate = segments
+        .iter()
+        .find(|segment| is_phrase(segment) && starts_like_abbreviation(segment))
+        .or_else(|| segments.iter().find(|segment| is_phrase(segment)));
+    if let Some(segment) = candidate {
+        return Some(segment.clone());
+    }
+
+    let first_line = definition_text.lines().next().unwrap_or("").trim();
+    let leading: String = first_line
+        .chars()
+        .take_while(|ch| ch.is_ascii_alphabetic() || matches!(ch, ' ' | '-' | '\''))
+        .collect();
+    let leading = leading.trim();
+    if is_phrase(leading) && !leading.eq_ignore_ascii_case(abbreviation) {
+        Some(leading.to_string())
+    } else {
+        None
+    }
+}
+
+/// 提取释义中括号内的英文对应词（如"（love）"），转为小写
+/// 只取括号（（）、()、［］、[]、〔〕）中由拉丁字母组成、至少含两个字母的片段
+pub fn extract_english_glosses(text: &str) -> Vec<String> {
+    let mut glosses: Vec<String> = Vec::new();
+    for segment in bracketed_latin_segments(text) {
+        let gloss = segment.to_lowercase();
+        if !glosses.contains(&gloss) {
+            glosses.push(gloss);
+        }
+    }
+    glosses
+}
+
+/// 拆分多重汉字表记（如"聞・聴"、"可愛·可愛らしい"），中点可以是・、·或‧
+pub fn split_kanji_variants(kanji_writing: &str) -> Vec<String> {
+    kanji_writing
+        .split(['・', '·', '‧'])
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// 将旺文社词条HTML转换为Markdown
+/// 义项编号❶❷…转为有序列表`1.` `2.`，例句转为引用块，注音（ruby）显示为"漢字(よみ)"
+pub fn definition_to_markdown(html: &str) -> String {
+    let document = Html::parse_fragment(html);
+    let mut segments = Vec::new();
+    collect_markdown_segments(document.root_element(), &mut segments);
+
+    let mut markdown = String::new();
+    let mut in_list = false;
+
+    for segment in segments {
+        match segment {
+            MarkdownSegment::Text(text) => {
+                for ch in text.chars() {
+                    match sense_number(ch) {
+                        Some(number) => {
+                            start_markdown_line(&mut markdown);
+                            markdown.push_str(&format!("{}. ", number));
+                            in_list = true;
+                        }
+                        None if ch == '\n' => start_markdown_line(&mut markdown),
+                        None => markdown.push(ch),
+                    }
+                }
+            }
+            MarkdownSegment::Example(text) => {
+                start_markdown_line(&mut markdown);
+                markdown.push_str(if in_list { "   > " } else { "> " });
+                markdown.push_str(text.trim());
+                markdown.push('\n');
+            }
+        }
+    }
+
+    markdown
+        .lines()
+        .map(|line| line.trim_end())
+        .filter(|line| !line.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// ❶〜❿对应的义项编号
+fn sense_number(ch: char) -> Option<u32> {
+    match ch {
+        '\u{2776}'..='\u{277F}' => Some(ch as u32 - 0x2775),
+        _ => None,
+    }
+}
+
+/// 释义中的一个义项（mean_normal 或 mean_lv_N 元素）
+/// mean_normal 为第0级，mean_lv_N 为第N级；级数更大的义项归入前面最近的级数更小的义项
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Sense {
+    pub level: u8,
+    /// 义项编号，来自 mean_no_N 或开头的❶〜❿，没有编号时为None
+    pub number: Option<u32>,
+    /// 义项本身的文本（不含子义项，去掉开头的❶〜❿）
+    pub text: String,
+    pub children: Vec<Sense>,
+}
+
+/// 元素的义项级数：mean_normal 为0，mean_lv_N 为N，不是义项元素时为None
+fn sense_level(element: ElementRef) -> Option<u8> {
+    element.value().classes().find_map(|class| match class {
+        "mean_normal" => Some(0),
+        _ => class.strip_prefix("mean_lv_")?.parse().ok(),
+    })
+}
+
+/// 收集元素的文本，跳过嵌套在其中的义项元素（它们作为子义项单独解析）
+/// 与definition_to_markdown一致，ruby转为"漢字(よみ)"；mlg注音（如"状況じようきよう"中的读音）不计入文本
+fn collect_sense_text(element: ElementRef, text: &mut String) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(content) => text.push_str(content),
+            Node::Element(_) => {
+                let Some(child_element) = ElementRef::wrap(child) else { continue };
+                if sense_level(child_element).is_some() || child_element.value().classes().any(|class| class == "mlg") {
+                    continue;
+                }
+                if child_element.value().name() == "ruby" {
+                    text.push_str(&ruby_to_text(child_element));
+                } else {
+                    collect_sense_text(child_element, text);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// 从释义HTML重建义项树（extract_definition_text 会把各级义项拍平成一列）
+/// 义项元素无论是并列还是互相嵌套，都按文档顺序和级数确定层级；没有义项元素时返回空列表
+pub fn parse_sense_tree(html: &str) -> Vec<Sense> {
+    let document = Html::parse_fragment(html);
+    let mut flat = Vec::new();
+    for node in document.root_element().descendants() {
+        let Some(element) = ElementRef::wrap(node) else { continue };
+        let Some(level) = sense_level(element) else { continue };
+
+        let mut text = String::new();
+        collect_sense_text(element, &mut text);
+        let text = strip_invisible(text.trim());
+        let leading_number = text.chars().next().and_then(sense_number);
+        let number = element
+            .value()
+            .classes()
+            .find_map(|class| class.strip_prefix("mean_no_")?.parse().ok())
+            .or(leading_number);
+        let text = match leading_number {
+            Some(_) => text.chars().skip(1).collect::<String>().trim_start().to_string(),
+            None => text,
+        };
+        flat.push(Sense { level, number, text, children: Vec::new() });
+    }
+
+    let mut senses = flat.into_iter().peekable();
+    build_sense_children(&mut senses, None)
+}
+
+/// 依次取出级数大于parent_level的义项，每个义项再取出其后级数更大的义项作为子义项
+fn build_sense_children(senses: &mut std::iter::Peekable<std::vec::IntoIter<Sense>>, parent_level: Option<u8>) -> Vec<Sense> {
+    let mut children = Vec::new();
+    while let Some(mut sense) = senses.next_if(|sense| parent_level.is_none_or(|parent| sense.level > parent)) {
+        sense.children = build_sense_children(senses, Some(sense.level));
+        children.push(sense);
+    }
+    children
+}
+
+/// 当前行非空时换行
+fn start_markdown_line(markdown: &mut String) {
+    if !markdown.is_empty() && !markdown.ends_with('\n') {
+        markdown.push('\n');
+    }
+}
+
+/// 按文档顺序收集文本与例句
+fn collect_markdown_segments(element: ElementRef, segments: &mut Vec<MarkdownSegment>) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => segments.push(MarkdownSegment::Text(text.to_string())),
+            Node::Element(_) => {
+                let Some(child_element) = ElementRef::wrap(child) else {
+                    continue;
+                };
+                let tag = child_element.value().name();
+                let is_headword = child_element.value().classes().any(|class| class == "headword");
+                let is_example = child_element.value().classes().any(|class| EXAMPLE_CLASSES.contains(&class));
+
+                if SKIPPED_TAGS.contains(&tag) || is_headword {
+                    continue;
+                }
+                if tag == "br" {
+                    segments.push(MarkdownSegment::Text("\n".to_string()));
+                } else if tag == "ruby" {
+                    segments.push(MarkdownSegment::Text(ruby_to_text(child_element)));
+                } else if is_example {
+                    segments.push(MarkdownSegment::Example(ruby_aware_text(child_element)));
+                } else {
+                    collect_markdown_segments(child_element, segments);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// ruby转为"漢字(よみ)"
+fn ruby_to_text(ruby: ElementRef) -> String {
+    let mut base = String::new();
+    let mut reading = String::new();
+    for child in ruby.children() {
+        match child.value() {
+            Node::Text(text) => base.push_str(text),
+            Node::Element(element) => match element.name() {
+                "rt" => reading.extend(ElementRef::wrap(child).into_iter().flat_map(|rt| rt.text())),
+                "rp" => {}
+                _ => base.extend(ElementRef::wrap(child).into_iter().flat_map(|el| el.text())),
+            },
+            _ => {}
+        }
+    }
+    if reading.is_empty() {
+        base
+    } else {
+        format!("{}({})", base, reading)
+    }
+}
+
+/// 提取元素文本，其中的ruby转为"漢字(よみ)"
+fn ruby_aware_text(element: ElementRef) -> String {
+    let mut text = String::new();
+    for child in element.children() {
+        match child.value() {
+            Node::Text(content) => text.push_str(content),
+            Node::Element(_) => {
+                if let Some(child_element) = ElementRef::wrap(child) {
+                    if child_element.value().name() == "ruby" {
+                        text.push_str(&ruby_to_text(child_element));
+                    } else {
+                        text.push_str(&ruby_aware_text(child_element));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_examples() {
+        let html = r#"<div class="mean_normal">❶いとしく思う心。<span class="ex_text">親子の―。<span class="example">―を注ぐ</span></span>❷こいしたう。<span class="ex_text">―の告白</span></div>"#;
+        assert_eq!(count_examples(html), 2);
+        assert_eq!(count_examples("<div class=\"mean_normal\">例なし。</div>"), 0);
+    }
+
+    #[test]
+    fn test_check_html_well_formed() {
+        assert!(check_html_well_formed(r#"<div class="a>b"><br><img src="x"/><!-- <p> --><span>愛</span></div>"#).is_ok());
+        assert_eq!(check_html_well_formed("<div><span>愛</div>"), Err("</div> 与 <span> 不匹配".to_string()));
+        assert_eq!(check_html_well_formed("<div>愛"), Err("<div> 未闭合".to_string()));
+        assert_eq!(check_html_well_formed("愛</b>"), Err("多余的结束标签 </b>".to_string()));
+        assert!(check_html_well_formed("<div class=\"a\"").is_err());
+    }
+
+    #[test]
+    fn test_validate_entry() {
+        let parser = ObunshaParser::new();
+        let html = r#"<container data-id="1" data-type="1"><div class="headword">あい【愛】</div><div class="mean_normal">いとしく思う心。</div></container>"#;
+        let mut entry = parser.parse_entry_from_html("あい【愛】", html).unwrap();
+        assert!(entry.validate().is_empty(), "{:?}", entry.validate());
+
+        entry.headword = " ".to_string();
+        entry.kana_reading = Some("あい愛".to_string());
+        entry.definition_html = "<div>いとしく思う心。".to_string();
+        let problems = entry.validate();
+        assert_eq!(problems.len(), 3, "{:?}", problems);
+        assert!(problems[0].contains("headword"));
+        assert!(problems[1].contains("<div> 未闭合"));
+        assert!(problems[2].contains("kana_reading"));
+    }
+
+    #[test]
+    fn test_parse_sense_tree() {
+        // あがく的原始HTML（与 test-agaku 相同）
+        let html = r#"<link rel="stylesheet" href="style.css"><container data-id="236" data-type="2"><div id="id_00000236" class="item item_ippan"><div class="head"><span class="head_kana">あが・く</span><span class="head_hyo_2"><span class="ka_hyo_2">〖</span><span class="mj_sup">◇</span>足<span class="mj_sup">△</span>搔く<span class="ka_hyo_2">〗</span></span></div><div class="mean_normal"><span class="pos"><span class="ka_pos_s">（</span>自五<span class="ka_pos_e">）</span></span><span class="ka_inflec">｛</span><span class="inflec">カ<span class="mj_inflec">（</span>コ<span class="mj_inflec">）</span>・キ<span class="mj_inflec">（</span>イ<span class="mj_inflec">）</span>・<br>ク・ク・ケ・ケ</span><span class="ka_inflec">｝</span></div><div class="mean_lv_2 mean_no_1">❶手足を動かしてもがく。じたばたする。<span class="ex_text">組み敷<span class="mlg mlg_1">し</span>かれて━</span></div><div class="mean_lv_2 mean_no_2">❷悪い状況<span class="mlg mlg_6">じようきよう</span>からぬけ出そうとして、いろいろむだな試みをする。<span class="ex_text">今さら━・いてもむだだ</span></div></div></contaienr></html>"#;
+        let senses = parse_sense_tree(html);
+        assert_eq!(senses.len(), 1);
+        assert_eq!((senses[0].level, senses[0].number), (0, None));
+        assert_eq!(senses[0].text, "（自五）｛カ（コ）・キ（イ）・ク・ク・ケ・ケ｝");
+
+        let children = &senses[0].children;
+        assert_eq!(children.len(), 2);
+        assert_eq!((children[0].level, children[0].number), (2, Some(1)));
+        assert_eq!(children[0].text, "手足を動かしてもがく。じたばたする。組み敷かれて━");
+        assert_eq!(children[1].number, Some(2));
+        assert_eq!(children[1].text, "悪い状況からぬけ出そうとして、いろいろむだな試みをする。今さら━・いてもむだだ");
+        assert!(children.iter().all(|child| child.children.is_empty()));
+
+        // ruby与Markdown一样显示为"漢字(よみ)"
+        let ruby = r#"<div class="mean_lv_1">❶<ruby>状況<rt>じょうきょう</rt></ruby>が悪い。</div>"#;
+        assert_eq!(parse_sense_tree(ruby)[0].text, "状況(じょうきょう)が悪い。");
+
+        // 嵌套的义项元素不重复计入父义项的文本
+        let nested = r#"<div class="mean_lv_1">❶一。<div class="mean_lv_2">㋐甲。</div></div><div class="mean_lv_1">❷二。</div>"#;
+        let senses = parse_sense_tree(nested);
+        assert_eq!(senses.len(), 2);
+        assert_eq!((senses[0].number, senses[0].text.as_str()), (Some(1), "一。"));
+        assert_eq!(senses[0].children[0].text, "㋐甲。");
+        assert_eq!(senses[1].number, Some(2));
+        assert!(parse_sense_tree("<div>本文</div>").is_empty());
+    }
+
+    #[test]
+    fn test_is_pure_redirect() {
+        assert!(is_pure_redirect_text("⇒あいきょう（愛敬）"));
+        assert!(is_pure_redirect_text("「あいそう」に同じ。→あいそう。"));
+        assert!(!is_pure_redirect_text("→あいそう・→あいきょう"));
+        assert!(!is_pure_redirect_text("いとしく思う心。"));
+        assert!(!is_pure_redirect_text("❶手足を動かしてもがく。じたばたする。⇒もがく ❷悪い状況からぬけ出そうとする。"));
+        assert!(!is_pure_redirect_text("⇒あいそう、あいきょう"));
+    }
+
+    #[test]
+    fn test_abbreviation_expansion() {
+        let parser = ObunshaParser::new();
+        let html = r#"<container data-id="200" data-type="1"><div class="headword"><span class="headword_ryaku">DNA</span></div><div class="mean_normal">〔deoxyribonucleic acid〕デオキシリボ核酸。遺伝子の本体。</div></container>"#;
+
+        let entry = parser.parse_entry_from_html("DNA", html).unwrap();
+        assert_eq!(entry.kana_reading, Some("DNA".to_string()));
+        assert_eq!(entry.expansion, Some("deoxyribonucleic acid".to_string()));
+
+        assert_eq!(
+            extract_expansion("PR", "（public relations）広報活動。(PR)"),
+            Some("public relations".to_string())
+        );
+        assert_eq!(
+            extract_expansion("OB", "old boy の略。卒業生。"),
+            Some("old boy".to_string())
+        );
+        assert_eq!(extract_expansion("AI", "人工知能。"), None);
+    }
+
+    #[test]
+    fn test_extract_english_glosses() {
+        assert_eq!(extract_english_glosses("いとしく思う心。（love）"), vec!["love"]);
+        assert_eq!(extract_english_glosses("〔英 Public Relations〕広報。(PR) [co-op]"), vec!["public relations", "pr", "co-op"]);
+        // 括号外的英文和单个字母不算
+        assert!(extract_english_glosses("love（A）").is_empty());
+    }
+
+    #[test]
+    fn test_clean_kanji_text_normalizes_dots() {
+        let parser = ObunshaParser::new();
+        assert_eq!(parser.clean_kanji_text("【聞く·聴く】"), "聞く・聴く");
+        assert_eq!(parser.clean_kanji_text(" ・可愛‧‧可愛らしい・ "), "可愛・可愛らしい");
+        assert_eq!(parser.clean_kanji_text("◇足△搔く"), "足搔く");
+    }
+
+    #[test]
+    fn test_split_kanji_variants() {
+        assert_eq!(split_kanji_variants("聞・聴"), vec!["聞", "聴"]);
+        assert_eq!(split_kanji_variants("可愛·可愛らしい"), vec!["可愛", "可愛らしい"]);
+        assert_eq!(split_kanji_variants("愛"), vec!["愛"]);
+        assert!(split_kanji_variants("").is_empty());
+    }
+
+    #[test]
+    fn test_noisy_headline_prefers_html_kana() {
+        let parser = ObunshaParser::new();
+        let html = r#"<link rel="stylesheet" href="style.css"><container data-id="100" data-type="1"><div class="headword"><span class="headword_kana">あい</span><span class="headword_hyouki">【愛】</span></div><div class="mean_normal">いとしく思う心。</div></container>"#;
+
+        let entry = parser.parse_entry_from_html("あい▼【愛】", html).unwrap();
+        assert_eq!(entry.kana_reading, Some("あい".to_string()));
+        assert_eq!(entry.kanji_writing, Some("愛".to_string()));
+        assert_eq!(entry.headword, "あい▼【愛】");
+    }
+
+    #[test]
+    fn test_kana_fallback_from_title() {
+        let parser = ObunshaParser::new();
+        let html = r#"<container data-id="101" data-type="1"><div class="mean_normal">いとしく思う心。</div></container>"#;
+
+        let entry = parser.parse_entry_from_html("あい〔愛〕", html).unwrap();
+        assert_eq!(entry.kana_reading, Some("あい".to_string()));
+        assert_eq!(entry.headword, "あい〔愛〕");
+
+        let entry = parser.parse_entry_from_html("愛(あい)", html).unwrap();
+        assert_eq!(entry.kana_reading, Some("あい".to_string()));
+
+        // 关键词中没有假名时保持None
+        let entry = parser.parse_entry_from_html("愛情", html).unwrap();
+        assert_eq!(entry.kana_reading, None);
+
+        // 括号外的汉字带送假名时不能把送假名当作读音
+        let entry = parser.parse_entry_from_html("愛する", html).unwrap();
+        assert_eq!(entry.kana_reading, None);
+        let entry = parser.parse_entry_from_html("動く", html).unwrap();
+        assert_eq!(entry.kana_reading, None);
+        let entry = parser.parse_entry_from_html("動く(うごく)", html).unwrap();
+        assert_eq!(entry.kana_reading, Some("うごく".to_string()));
+    }
+
+    #[test]
+    fn test_dotted_reading_is_cleaned() {
+        let parser = ObunshaParser::new();
+        assert_eq!(parser.strip_reading_dots("あが・く".to_string()), "あがく");
+        // 中点只在headline读音中去掉，其他调用方保留
+        assert_eq!(parser.clean_kana_text("アダム・スミス"), "アダム・スミス");
+        assert_eq!(
+            parser.parse_headline("あが・く【足搔く】"),
+            Some(("あがく".to_string(), "足搔く".to_string()))
+        );
+
+        let html = r#"<container data-id="102" data-type="1"><div class="mean_normal">もがく。</div></container>"#;
+        let entry = parser.parse_entry_from_html("あが・く【足搔く】", html).unwrap();
+        assert_eq!(entry.kana_reading, Some("あがく".to_string()));
+        assert_eq!(entry.kanji_writing, Some("足搔く".to_string()));
+    }
+
+    #[test]
+    fn test_empty_kana_count() {
+        let parser = ObunshaParser::new();
+        assert_eq!(parser.clean_kana_text("▽◆"), "");
+        assert_eq!(parser.clean_kana_text(""), "");
+        assert_eq!(parser.clean_kana_text("あい"), "あい");
+        assert_eq!(parser.empty_kana_count(), 1);
+    }
+
+    #[test]
+    fn test_sense_separator() {
+        let html = r#"<container data-id="1" data-type="1"><div class="mean_lv_1">❶いとしく思う心。</div><div class="mean_lv_1">❷こいしたう気持ち。</div></container>"#;
+
+        let entry = ObunshaParser::new().parse_entry_from_html("あい【愛】", html).unwrap();
+        assert_eq!(entry.definition_text, "❶いとしく思う心。 ❷こいしたう気持ち。");
+
+        let entry = ObunshaParser::with_sense_separator("\n").parse_entry_from_html("あい【愛】", html).unwrap();
+        assert_eq!(entry.definition_text, "❶いとしく思う心。\n❷こいしたう気持ち。");
+        assert_eq!(entry.definition_html, html);
+    }
+
+    #[test]
+    fn test_definition_to_markdown() {
+        let html = r#"<link rel="stylesheet" href="style.css"><container data-id="1"><div class="headword"><span class="headword_kana">あい</span></div><div class="mean_normal">❶いとしく思う心。<span class="ex_text">親子の<ruby>愛<rt>あい</rt></ruby>。</span>❷こいしたう気持ち。</div></container>"#;
+
+        assert_eq!(
+            definition_to_markdown(html),
+            "1. いとしく思う心。\n   > 親子の愛(あい)。\n2. こいしたう気持ち。"
+        );
+    }
+}
@@ -1,8 +1,112 @@
-use crate::database::DictionaryEntry;
 use regex::Regex;
-use scraper::{Html, Selector};
+use scraper::{ElementRef, Html, Node, Selector};
+use serde::{Deserialize, Serialize};
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Read};
+
+/// 读音类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReadingKind {
+    /// 音读
+    On,
+    /// 训读
+    Kun,
+    /// 无法判断
+    Unknown,
+}
+
+/// 带类型的读音
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Reading {
+    pub text: String,
+    pub kind: ReadingKind,
+}
+
+/// 汉字表记中标记为常用汉字表外的字（◇△▽标记紧跟的那个字）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JouyouFlag {
+    /// 被标记的字，如 足
+    pub text: String,
+    /// 原始标记符号：◇、△ 或 ▽
+    pub marker: String,
+}
+
+/// 标记常用汉字表外汉字・音训的符号
+const JOUYOU_MARKERS: [char; 3] = ['◇', '△', '▽'];
+
+/// 汉字表记的类别，对应HTML中表记元素的class
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HyoCategory {
+    /// head_hyo_1：标准表记
+    Hyo1,
+    /// head_hyo_2：含表外字或送假名变体的表记（〖〗括起）
+    Hyo2,
+    /// head_joyo：常用汉字
+    Joyo,
+    /// head_kyoiku：教育汉字
+    Kyoiku,
+    /// head_gen：其他表记
+    Gen,
+}
+
+/// 汉字表记选择器及其类别，按优先顺序排列（kanji_form取第一个有效的表记）
+const KANJI_SELECTORS: [(&str, HyoCategory); 5] = [
+    (".head_hyo_1", HyoCategory::Hyo1),
+    (".head_hyo_2", HyoCategory::Hyo2),
+    (".head_joyo", HyoCategory::Joyo),
+    (".head_kyoiku", HyoCategory::Kyoiku),
+    (".head_gen", HyoCategory::Gen),
+];
+
+/// 带类别的汉字表记
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KanjiWriting {
+    /// 清理后的表记，如 足搔く
+    pub form: String,
+    /// 匹配的表记元素类别
+    pub category: HyoCategory,
+}
+
+/// 表現読解国語辞典 - 日语词典条目结构
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DictionaryEntry {
+    pub id: Option<i64>,
+    /// 假名entry - 词条的假名读音
+    pub kana_entry: String,
+    /// 汉字格式 - 汉字表记（可能为空，如纯假名词汇）
+    pub kanji_form: Option<String>,
+    /// 释义字段 - 词汇的含义解释
+    pub meaning: String,
+    /// 发音字段 - 发音信息（音读、训读等）
+    pub pronunciation: Option<String>,
+    /// 带类型的读音列表 - 区分音读/训读，以JSON存储
+    pub readings: Vec<Reading>,
+    /// 词条类型 - 如汉字条目、一般条目等
+    pub entry_type: String,
+    /// 原始HTML内容 - 保留原始数据用于调试
+    pub raw_html: String,
+    /// 汉字表记中的表外字标记 - 仅在解析器启用with_jouyou_flags时填充，以JSON存储
+    #[serde(default)]
+    pub jouyou_flags: Vec<JouyouFlag>,
+    /// 所有汉字表记及其类别（按选择器优先顺序），以JSON存储
+    #[serde(default)]
+    pub kanji_writings: Vec<KanjiWriting>,
+}
+
+/// 解析jpdict.txt的统计信息，用于检查提取率
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ParseSummary {
+    /// 读取的总行数
+    pub total_lines: usize,
+    /// 包含<container的行数
+    pub containers_seen: usize,
+    /// 成功提取的词条数
+    pub entries_extracted: usize,
+    /// 包含<container但未能提取词条的行数（缺少假名或释义）
+    pub skipped: usize,
+}
 
 /// HTML解析器 - 用于提取jpdict.txt中的词典数据
 pub struct DictParser {
@@ -10,8 +114,8 @@ pub struct DictParser {
     kana_cleaner: Regex,
     /// 清理汉字的正则表达式  
     kanji_cleaner: Regex,
-    /// 提取发音的正则表达式
-    pronunciation_extractor: Regex,
+    /// 是否记录汉字表记中的◇△▽表外字标记（否则直接丢弃）
+    keep_jouyou_flags: bool,
 }
 
 impl DictParser {
@@ -22,11 +126,32 @@ impl DictParser {
             kana_cleaner: Regex::new(r"[・\-\s]+").unwrap(),
             // 清理汉字中的括号和标记符号
             kanji_cleaner: Regex::new(r"[【】〔〕（）\(\)〖〗]").unwrap(),
-            // 提取粗体发音标记
-            pronunciation_extractor: Regex::new(r"<b>([^<]+)</b>").unwrap(),
+            keep_jouyou_flags: false,
         }
     }
 
+    /// 创建记录表外字标记的解析器：kanji_form仍去掉标记，被标记的字记录到jouyou_flags
+    pub fn with_jouyou_flags() -> Self {
+        Self {
+            keep_jouyou_flags: true,
+            ..Self::new()
+        }
+    }
+
+    /// 提取汉字表记中◇△▽标记的字，如"〖◇足△搔く〗"得到 足(◇)、搔(△)
+    fn extract_jouyou_flags(&self, kanji: &str) -> Vec<JouyouFlag> {
+        let mut flags = Vec::new();
+        let mut pending_marker = None;
+        for ch in self.kanji_cleaner.replace_all(kanji, "").chars() {
+            if JOUYOU_MARKERS.contains(&ch) {
+                pending_marker = Some(ch);
+            } else if let Some(marker) = pending_marker.take() {
+                flags.push(JouyouFlag { text: ch.to_string(), marker: marker.to_string() });
+            }
+        }
+        flags
+    }
+
     /// 清理假名键值 - 去除标点符号
     fn clean_kana(&self, kana: &str) -> String {
         let cleaned = self.kana_cleaner.replace_all(kana.trim(), "");
@@ -52,21 +177,47 @@ impl DictParser {
         }
     }
 
-    /// 提取发音信息
-    fn extract_pronunciation(&self, html: &str) -> Option<String> {
-        let mut pronunciations = Vec::new();
-        
-        for cap in self.pronunciation_extractor.captures_iter(html) {
-            if let Some(pronunciation) = cap.get(1) {
-                pronunciations.push(pronunciation.as_str());
+    /// 提取带类型的读音
+    /// 读音取自`b`或`.pron`元素的全部文本（包括嵌套标签中的文本）；
+    /// 根据读音前最近出现的"音"/"訓"标记判断类型，同一标记后的多个读音沿用该类型
+    fn extract_readings(&self, document: &Html) -> Vec<Reading> {
+        let mut readings = Vec::new();
+        let mut current_kind = ReadingKind::Unknown;
+        Self::collect_readings(document.root_element(), &mut current_kind, &mut readings);
+        readings
+    }
+
+    /// 按文档顺序收集读音，文本中的"音"/"訓"标记更新当前类型
+    fn collect_readings(element: ElementRef, current_kind: &mut ReadingKind, readings: &mut Vec<Reading>) {
+        for child in element.children() {
+            match child.value() {
+                Node::Text(text) => match (text.rfind('音'), text.rfind('訓')) {
+                    (Some(on), Some(kun)) => {
+                        *current_kind = if on > kun { ReadingKind::On } else { ReadingKind::Kun };
+                    }
+                    (Some(_), None) => *current_kind = ReadingKind::On,
+                    (None, Some(_)) => *current_kind = ReadingKind::Kun,
+                    (None, None) => {}
+                },
+                Node::Element(value) => {
+                    let Some(child_element) = ElementRef::wrap(child) else {
+                        continue;
+                    };
+                    if value.name() == "b" || value.classes().any(|class| class == "pron") {
+                        let text = child_element.text().collect::<String>();
+                        if !text.trim().is_empty() {
+                            readings.push(Reading {
+                                text: text.trim().to_string(),
+                                kind: *current_kind,
+                            });
+                        }
+                    } else {
+                        Self::collect_readings(child_element, current_kind, readings);
+                    }
+                }
+                _ => {}
             }
         }
-        
-        if pronunciations.is_empty() {
-            None
-        } else {
-            Some(pronunciations.join("・"))
-        }
     }
 
     /// 提取释义文本 - 去除HTML标签，保留文本内容
@@ -123,30 +274,36 @@ impl DictParser {
             return None;
         }
         
-        // 提取汉字
-        let kanji_selectors = [
-            ".head_hyo_1", 
-            ".head_hyo_2", 
-            ".head_joyo", 
-            ".head_kyoiku", 
-            ".head_gen"
-        ];
+        // 提取汉字：记录每个表记元素及其类别，kanji_form取第一个有效的表记
         let mut kanji_form = None;
+        let mut jouyou_flags = Vec::new();
+        let mut kanji_writings = Vec::new();
         
-        for selector_str in &kanji_selectors {
+        for (selector_str, category) in &KANJI_SELECTORS {
             if let Ok(selector) = Selector::parse(selector_str) {
-                if let Some(element) = document.select(&selector).next() {
+                for element in document.select(&selector) {
                     let raw_kanji = element.text().collect::<String>();
-                    kanji_form = self.clean_kanji(&raw_kanji);
-                    if kanji_form.is_some() {
-                        break;
+                    let Some(form) = self.clean_kanji(&raw_kanji) else {
+                        continue;
+                    };
+                    if kanji_form.is_none() {
+                        if self.keep_jouyou_flags {
+                            jouyou_flags = self.extract_jouyou_flags(&raw_kanji);
+                        }
+                        kanji_form = Some(form.clone());
                     }
+                    kanji_writings.push(KanjiWriting { form, category: *category });
                 }
             }
         }
         
         // 提取发音
-        let pronunciation = self.extract_pronunciation(html_content);
+        let readings = self.extract_readings(&document);
+        let pronunciation = if readings.is_empty() {
+            None
+        } else {
+            Some(readings.iter().map(|reading| reading.text.as_str()).collect::<Vec<_>>().join("・"))
+        };
         
         // 提取释义
         let meaning = self.extract_meaning(html_content);
@@ -171,40 +328,67 @@ impl DictParser {
             kanji_form,
             meaning,
             pronunciation,
+            readings,
             entry_type,
             raw_html: html_content.to_string(),
+            jouyou_flags,
+            kanji_writings,
         })
     }
 
     /// 从文件中解析所有词条
     pub fn parse_file(&self, file_path: &str) -> Result<Vec<DictionaryEntry>, Box<dyn std::error::Error>> {
+        let (entries, _) = self.parse_file_with_summary(file_path)?;
+        Ok(entries)
+    }
+
+    /// 从文件中解析所有词条，同时返回行数、容器数、跳过数等统计
+    pub fn parse_file_with_summary(
+        &self,
+        file_path: &str,
+    ) -> Result<(Vec<DictionaryEntry>, ParseSummary), Box<dyn std::error::Error>> {
         let file = File::open(file_path)?;
-        let reader = BufReader::new(file);
+        self.parse_reader(file)
+    }
+
+    /// 从任意输入中逐行解析词条（每行一个完整的<container>）
+    pub fn parse_reader(
+        &self,
+        input: impl Read,
+    ) -> Result<(Vec<DictionaryEntry>, ParseSummary), Box<dyn std::error::Error>> {
+        let reader = BufReader::new(input);
         
         let mut entries = Vec::new();
+        let mut summary = ParseSummary::default();
         
         println!("🔍 开始解析jpdict.txt文件...");
-        let mut line_count = 0;
         
         for line in reader.lines() {
             let line = line?;
-            line_count += 1;
+            summary.total_lines += 1;
             
-            if line_count % 10000 == 0 {
-                println!("📖 已处理 {} 行，提取到 {} 个词条", line_count, entries.len());
+            if summary.total_lines % 10000 == 0 {
+                println!("📖 已处理 {} 行，提取到 {} 个词条", summary.total_lines, entries.len());
             }
             
             // 检查这一行是否包含完整的词条（以<container开始）
             if line.contains("<container") {
+                summary.containers_seen += 1;
                 // 每行都是一个完整的词条，直接解析
-                if let Some(entry) = self.parse_entry(&line) {
-                    entries.push(entry);
+                match self.parse_entry(&line) {
+                    Some(entry) => entries.push(entry),
+                    None => summary.skipped += 1,
                 }
             }
         }
+        summary.entries_extracted = entries.len();
         
         println!("✅ 解析完成！共提取到 {} 个词条", entries.len());
-        Ok(entries)
+        println!(
+            "📊 共 {} 行，{} 个词条容器，跳过 {} 个",
+            summary.total_lines, summary.containers_seen, summary.skipped
+        );
+        Ok((entries, summary))
     }
 }
 
@@ -229,4 +413,77 @@ mod tests {
         assert_eq!(parser.clean_kanji("〔英〕"), None);
         assert_eq!(parser.clean_kanji(""), None);
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_reading_kinds() {
+        let parser = DictParser::new();
+        let html = r#"<container data-id="1" data-type="1"><div class="item item_kanji"><div class="head"><span class="head_kana">あい</span><span class="head_kyoiku">愛</span></div><div class="mean_normal">音<b>アイ</b> 訓<b>いとしい</b>・<b>めでる</b> いつくしむ。</div></div></container>"#;
+
+        let entry = parser.parse_entry(html).unwrap();
+        assert_eq!(entry.readings, vec![
+            Reading { text: "アイ".to_string(), kind: ReadingKind::On },
+            Reading { text: "いとしい".to_string(), kind: ReadingKind::Kun },
+            Reading { text: "めでる".to_string(), kind: ReadingKind::Kun },
+        ]);
+        assert_eq!(entry.pronunciation, Some("アイ・いとしい・めでる".to_string()));
+    }
+
+    #[test]
+    fn test_jouyou_flags() {
+        let html = r#"<container data-id="236" data-type="2"><div class="item item_ippan"><div class="head"><span class="head_kana">あが・く</span><span class="head_hyo_2"><span class="ka_hyo_2">〖</span><span class="mj_sup">◇</span>足<span class="mj_sup">△</span>搔く<span class="ka_hyo_2">〗</span></span></div><div class="mean_normal">❶手足を動かしてもがく。</div></div></container>"#;
+
+        let entry = DictParser::with_jouyou_flags().parse_entry(html).unwrap();
+        assert_eq!(entry.kanji_form, Some("足搔く".to_string()));
+        assert_eq!(entry.jouyou_flags, vec![
+            JouyouFlag { text: "足".to_string(), marker: "◇".to_string() },
+            JouyouFlag { text: "搔".to_string(), marker: "△".to_string() },
+        ]);
+
+        // 默认模式下仍然丢弃标记
+        assert!(DictParser::new().parse_entry(html).unwrap().jouyou_flags.is_empty());
+    }
+
+    #[test]
+    fn test_kanji_writings_keep_category() {
+        let html = r#"<container data-id="3" data-type="1"><div class="item item_ippan"><div class="head"><span class="head_kana">あが・く</span><span class="head_hyo_1">【足掻く】</span><span class="head_hyo_2"><span class="ka_hyo_2">〖</span><span class="mj_sup">◇</span>足<span class="mj_sup">△</span>搔く<span class="ka_hyo_2">〗</span></span></div><div class="mean_normal">もがく。</div></div></container>"#;
+
+        let entry = DictParser::new().parse_entry(html).unwrap();
+        assert_eq!(entry.kanji_form, Some("足掻く".to_string()));
+        assert_eq!(entry.kanji_writings, vec![
+            KanjiWriting { form: "足掻く".to_string(), category: HyoCategory::Hyo1 },
+            KanjiWriting { form: "足搔く".to_string(), category: HyoCategory::Hyo2 },
+        ]);
+        assert_eq!(serde_json::to_string(&entry.kanji_writings[1]).unwrap(), r#"{"form":"足搔く","category":"hyo2"}"#);
+    }
+
+    #[test]
+    fn test_parse_reader_summary() {
+        let parser = DictParser::new();
+        let input = concat!(
+            "header\n",
+            r#"<container data-id="1"><div class="item item_ippan"><span class="head_kana">あい</span><div class="mean_normal">いつくしむ。</div></div></container>"#, "\n",
+            r#"<container data-id="2"><div class="item item_ippan"><div class="mean_normal">假名がない</div></div></container>"#, "\n",
+        );
+
+        let (entries, summary) = parser.parse_reader(input.as_bytes()).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(summary, ParseSummary {
+            total_lines: 3,
+            containers_seen: 2,
+            entries_extracted: 1,
+            skipped: 1,
+        });
+    }
+
+    #[test]
+    fn test_nested_tag_pronunciation() {
+        let parser = DictParser::new();
+        let html = r#"<container data-id="2" data-type="1"><div class="item item_kanji"><div class="head"><span class="head_kana">あい</span></div><div class="mean_normal">訓<b>あ<span class="okuri">い</span></b> 音<span class="pron">ア<i>イ</i></span></div></div></container>"#;
+
+        let entry = parser.parse_entry(html).unwrap();
+        assert_eq!(entry.readings, vec![
+            Reading { text: "あい".to_string(), kind: ReadingKind::Kun },
+            Reading { text: "アイ".to_string(), kind: ReadingKind::On },
+        ]);
+    }
+}
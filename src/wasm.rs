@@ -0,0 +1,37 @@
+use wasm_bindgen::prelude::*;
+
+use crate::obunsha_parser::ObunshaParser;
+use crate::parser::DictParser;
+
+/// 解析一条表現読解国語辞典词条HTML，返回词条JSON；无法解析时返回undefined
+#[wasm_bindgen(js_name = parseEntry)]
+pub fn parse_entry(html: &str) -> Option<String> {
+    let entry = DictParser::new().parse_entry(html)?;
+    serde_json::to_string(&entry).ok()
+}
+
+/// 解析一条旺文社国語辞典词条（标题行 + HTML行），返回词条JSON；无法解析时返回undefined
+#[wasm_bindgen(js_name = parseObunshaEntry)]
+pub fn parse_obunsha_entry(title: &str, html: &str) -> Option<String> {
+    let entry = ObunshaParser::new().parse_entry_from_html(title, html)?;
+    serde_json::to_string(&entry).ok()
+}
+
+/// 从标题解析假名和汉字，返回 `[kana, kanji]` 形式的JSON
+#[wasm_bindgen(js_name = parseHeadline)]
+pub fn parse_headline(headline: &str) -> Option<String> {
+    let (kana, kanji) = ObunshaParser::new().parse_headline(headline)?;
+    serde_json::to_string(&[kana, kanji]).ok()
+}
+
+/// 清理假名文本，去除特殊符号
+#[wasm_bindgen(js_name = cleanKanaText)]
+pub fn clean_kana_text(text: &str) -> String {
+    ObunshaParser::new().clean_kana_text(text)
+}
+
+/// 清理汉字文本，去除标记符号
+#[wasm_bindgen(js_name = cleanKanjiText)]
+pub fn clean_kanji_text(text: &str) -> String {
+    ObunshaParser::new().clean_kanji_text(text)
+}
@@ -1,27 +1,461 @@
 use axum::{
-    extract::{Query, State},
-    http::StatusCode,
-    response::Json,
-    routing::get,
+    extract::{
+        rejection::{JsonRejection, QueryRejection, StringRejection},
+        DefaultBodyLimit, Path, Query, Request, State,
+    },
+    http::{header, HeaderMap, StatusCode},
+    middleware::{self, Next},
+    response::{Json, Response},
+    routing::{get, post},
     Router,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 use tokio::net::TcpListener;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 
-use crate::obunsha_dict::{ObunshaDictDatabase, ObunshaDictEntry};
+use crate::conjugation;
+use crate::obunsha_dict::{BooleanQuery, ObunshaDictDatabase, ObunshaDictEntry, RankedEntry, SearchTier, COMPACT_COLUMNS, MAX_REDIRECT_DEPTH};
+use crate::obunsha_parser::{definition_to_markdown, parse_sense_tree, split_kanji_variants};
+
+/// 释义搜索返回的最大词条数
+const DEFINITION_SEARCH_LIMIT: usize = 100;
+
+/// 按has_examples、hide_redirects过滤时，有上限的搜索多取的倍数；过滤后再截断为DEFINITION_SEARCH_LIMIT
+const FILTERED_SEARCH_OVERFETCH: usize = 10;
+
+/// 容错搜索允许的最大假名距离（见kana::kana_distance）
+const TYPO_MAX_DISTANCE: f64 = 1.0;
+
+/// 布尔搜索最多允许的查询词数
+const MAX_BOOLEAN_TERMS: usize = 8;
+
+/// 搜索类型，/search 的 search_type 参数；支持的取值均由 SearchType::ALL 给出
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", rename_all = "lowercase")]
+pub enum SearchType {
+    /// 精确匹配标题、读音或任一汉字表记
+    #[default]
+    Exact,
+    /// 标题包含查询词
+    Fuzzy,
+    /// 假名读音全等
+    Kana,
+    /// 汉字表记匹配（支持多重表记）
+    Kanji,
+    /// 释义全文搜索
+    Definition,
+    /// AND/OR组合查询
+    Boolean,
+    /// 假名容错搜索
+    Typo,
+    /// 标题前缀
+    Prefix,
+    /// 标题、表记或读音后缀
+    Suffix,
+    /// 英文对应词
+    English,
+    /// 汉字词干
+    Stem,
+    /// 汉字表记匹配，查询词中的新字体・旧字体互相展开（国/國）
+    Variant,
+}
+
+impl SearchType {
+    /// 全部搜索类型（按文档中的顺序）
+    pub const ALL: [SearchType; 12] = [
+        SearchType::Exact,
+        SearchType::Fuzzy,
+        SearchType::Kana,
+        SearchType::Kanji,
+        SearchType::Definition,
+        SearchType::Boolean,
+        SearchType::Typo,
+        SearchType::Prefix,
+        SearchType::Suffix,
+        SearchType::English,
+        SearchType::Stem,
+        SearchType::Variant,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SearchType::Exact => "exact",
+            SearchType::Fuzzy => "fuzzy",
+            SearchType::Kana => "kana",
+            SearchType::Kanji => "kanji",
+            SearchType::Definition => "definition",
+            SearchType::Boolean => "boolean",
+            SearchType::Typo => "typo",
+            SearchType::Prefix => "prefix",
+            SearchType::Suffix => "suffix",
+            SearchType::English => "english",
+            SearchType::Stem => "stem",
+            SearchType::Variant => "variant",
+        }
+    }
+
+    /// 全部搜索类型的名称，供服务信息和错误提示使用
+    pub fn names() -> Vec<&'static str> {
+        Self::ALL.iter().map(|search_type| search_type.as_str()).collect()
+    }
+
+    /// 是否受DEFINITION_SEARCH_LIMIT限制（其余类型返回全部匹配结果）
+    fn is_capped(self) -> bool {
+        matches!(
+            self,
+            SearchType::Definition
+                | SearchType::Typo
+                | SearchType::Boolean
+                | SearchType::Prefix
+                | SearchType::Suffix
+                | SearchType::English
+                | SearchType::Stem
+        )
+    }
+}
+
+impl std::fmt::Display for SearchType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for SearchType {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|search_type| search_type.as_str() == value)
+            .ok_or_else(|| format!("未知的搜索类型 {:?}，支持: {}", value, Self::names().join(", ")))
+    }
+}
+
+impl TryFrom<String> for SearchType {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+/// search_type参数：一个或多个逗号分隔的搜索类型，如 "kana,kanji"
+/// 各类型均须为已知的SearchType，重复的类型只保留一次
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct SearchTypes(Vec<SearchType>);
+
+impl SearchTypes {
+    pub fn as_slice(&self) -> &[SearchType] {
+        &self.0
+    }
+}
+
+impl Default for SearchTypes {
+    fn default() -> Self {
+        SearchTypes(vec![SearchType::default()])
+    }
+}
+
+impl std::fmt::Display for SearchTypes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names: Vec<&str> = self.0.iter().map(|search_type| search_type.as_str()).collect();
+        f.write_str(&names.join(","))
+    }
+}
+
+impl Serialize for SearchTypes {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl TryFrom<String> for SearchTypes {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let mut types = Vec::new();
+        for name in value.split(',') {
+            let search_type: SearchType = name.trim().parse()?;
+            if !types.contains(&search_type) {
+                types.push(search_type);
+            }
+        }
+        Ok(SearchTypes(types))
+    }
+}
+
+/// chain参数：逗号分隔的回退搜索顺序，如 "kanji,kana,headword"
+/// 各项均须为已知的SearchTier，重复的项只保留一次
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct SearchChain(Vec<SearchTier>);
+
+impl SearchChain {
+    pub fn as_slice(&self) -> &[SearchTier] {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for SearchChain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names: Vec<&str> = self.0.iter().map(|tier| tier.as_str()).collect();
+        f.write_str(&names.join(","))
+    }
+}
+
+impl TryFrom<String> for SearchChain {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let mut tiers = Vec::new();
+        for name in value.split(',') {
+            let tier: SearchTier = name.trim().parse()?;
+            if !tiers.contains(&tier) {
+                tiers.push(tier);
+            }
+        }
+        Ok(SearchChain(tiers))
+    }
+}
 
 /// 查询请求参数
 #[derive(Debug, Deserialize)]
 pub struct SearchQuery {
     /// 查询的单词
     pub word: String,
-    /// 查询类型：exact(精确匹配), fuzzy(模糊匹配), kana(假名匹配), kanji(汉字匹配)
-    #[serde(default = "default_search_type")]
-    pub search_type: String,
+    /// 查询类型，默认exact，见SearchType；逗号分隔多个类型时按类型分组返回结果
+    #[serde(default)]
+    pub search_type: SearchTypes,
+    /// 是否用<mark>标记匹配部分（释义搜索标记释义，其余标记标题）
+    #[serde(default)]
+    pub highlight: bool,
+    /// 是否按标题分组返回（同形异义词归入同一组）
+    #[serde(default)]
+    pub grouped: bool,
+    /// 释义格式：markdown时每个词条附带definition_markdown字段
+    #[serde(default)]
+    pub format: Option<String>,
+    /// 结构化释义：tree时每个词条附带senses字段（按mean_lv层级重建的义项树）
+    #[serde(default)]
+    pub structured: Option<String>,
+    /// 列表预览：将definition_text截断为不超过该字符数并加省略号（完整内容用 /entry/:data_id 获取）
+    #[serde(default)]
+    pub preview_len: Option<usize>,
+    /// 只返回释义中含例句的词条
+    #[serde(default)]
+    pub has_examples: bool,
+    /// 排除只有指向另一个词的释义、没有实际内容的词条（见 ObunshaDictEntry::is_pure_redirect）
+    #[serde(default)]
+    pub hide_redirects: bool,
+    /// 响应格式：jsonapi时返回JSON:API风格的 {data, meta}，默认为原有格式
+    #[serde(default)]
+    pub envelope: Option<String>,
+    /// 精确搜索的回退顺序，如 kanji,kana,headword：依次搜索，返回第一个有结果的一级
+    #[serde(default)]
+    pub chain: Option<SearchChain>,
+    /// 没有结果时是否返回"您是不是要找"候选（需要按读音扫描全表，默认不返回）
+    #[serde(default)]
+    pub suggest: bool,
+}
+
+/// 紧凑格式整库导出请求参数
+#[derive(Debug, Deserialize)]
+pub struct CompactQuery {
+    /// 每页词条数
+    #[serde(default = "default_sync_limit")]
+    pub limit: usize,
+    /// 上一页返回的next_after_id，从该id之后继续；第一页为0
+    #[serde(default)]
+    pub after_id: i64,
+}
+
+/// 增量同步请求参数
+#[derive(Debug, Deserialize)]
+pub struct SyncQuery {
+    /// 上次同步时间（ISO-8601），返回此后修改过的词条
+    pub since: String,
+    /// 每页词条数
+    #[serde(default = "default_sync_limit")]
+    pub limit: usize,
+    /// 分页偏移
+    #[serde(default)]
+    pub offset: usize,
+}
+
+fn default_sync_limit() -> usize {
+    1000
+}
+
+/// 按创建时间查询请求参数
+#[derive(Debug, Deserialize)]
+pub struct CreatedQuery {
+    /// 起始时间（ISO-8601，包含）
+    pub created_from: String,
+    /// 结束时间（ISO-8601，包含）
+    pub created_to: String,
+    /// 每页词条数
+    #[serde(default = "default_sync_limit")]
+    pub limit: usize,
+    /// 分页偏移
+    #[serde(default)]
+    pub offset: usize,
+}
+
+/// 增量同步单页最大词条数
+const MAX_SYNC_LIMIT: usize = 5000;
+
+/// 检查分页接口的limit：为0时返回400，超过该接口的上限max时截断
+fn page_limit(limit: usize, max: usize) -> Result<usize, (StatusCode, Json<ErrorResponse>)> {
+    if limit == 0 {
+        return Err(error_response(StatusCode::BAD_REQUEST, "limit必须大于0".to_string()));
+    }
+    Ok(limit.min(max))
+}
+
+/// 按data_id批量查询时最多允许的id数
+const MAX_LOOKUP_IDS: usize = 5000;
+
+/// 自动补全请求参数
+#[derive(Debug, Deserialize)]
+pub struct SuggestQuery {
+    /// 输入前缀（假名、汉字或标题），如 あ
+    #[serde(default)]
+    pub q: Option<String>,
+    /// 罗马字前缀，如 sak
+    #[serde(default)]
+    pub romaji: Option<String>,
+    /// 最多返回的提示数
+    #[serde(default = "default_suggest_limit")]
+    pub limit: usize,
+}
+
+fn default_suggest_limit() -> usize {
+    10
+}
+
+/// 自动补全最多返回的提示数
+const MAX_SUGGEST_LIMIT: usize = 50;
+
+/// 按拍模式搜索请求参数
+#[derive(Debug, Deserialize)]
+pub struct PatternQuery {
+    /// 读音模式，如 か__つ（`_` 为任意一拍，`*` 为任意多拍）
+    pub pattern: String,
+    /// 最多返回的词条数
+    #[serde(default = "default_pattern_limit")]
+    pub limit: usize,
+}
+
+fn default_pattern_limit() -> usize {
+    100
+}
+
+/// 浏览窗口请求参数
+#[derive(Debug, Deserialize)]
+pub struct WindowQuery {
+    /// 目标读音，如 あい
+    pub at: String,
+    /// 目标之前的词条数
+    #[serde(default = "default_window_size")]
+    pub before: usize,
+    /// 目标之后的词条数
+    #[serde(default = "default_window_size")]
+    pub after: usize,
+}
+
+fn default_window_size() -> usize {
+    3
+}
+
+/// 浏览窗口单侧最多返回的词条数
+const MAX_WINDOW_SIZE: usize = 50;
+
+/// 按拍模式搜索最多返回的词条数
+const MAX_PATTERN_LIMIT: usize = 1000;
+
+/// 按拍模式的最大字符数
+const MAX_PATTERN_CHARS: usize = 32;
+
+/// 按拍模式中 `*` 的最大个数
+const MAX_PATTERN_WILDCARDS: usize = 4;
+
+/// 释义用例查询请求参数
+#[derive(Debug, Deserialize)]
+pub struct KanjiUsageQuery {
+    /// 单个汉字，如 生
+    pub kanji: String,
+    /// 每页词条数
+    #[serde(default = "default_kanji_usage_limit")]
+    pub limit: usize,
+    /// 分页偏移
+    #[serde(default)]
+    pub offset: usize,
 }
 
-fn default_search_type() -> String {
-    "exact".to_string()
+fn default_kanji_usage_limit() -> usize {
+    50
+}
+
+/// 释义用例查询单页最大词条数（需要全表扫描）
+const MAX_KANJI_USAGE_LIMIT: usize = 200;
+
+/// 汉字读音查询请求参数
+#[derive(Debug, Deserialize)]
+pub struct KanjiReadingsQuery {
+    /// 单个汉字，如 生
+    pub kanji: String,
+}
+
+/// 同形异读词读音选择请求参数
+#[derive(Debug, Deserialize)]
+pub struct DisambiguateQuery {
+    /// 汉字表记，如 生、上手
+    pub kanji: String,
+}
+
+/// 活用形请求参数
+#[derive(Debug, Deserialize)]
+pub struct ConjugateQuery {
+    /// 要活用的词，按 search_anything 解析（读音、汉字表记或标题均可），如 あがく
+    pub word: String,
+}
+
+/// 标题长度分布请求参数
+#[derive(Debug, Deserialize)]
+pub struct HistogramQuery {
+    /// 统计单位：chars（标题字符数，默认）或 mora（读音拍数）
+    #[serde(default)]
+    pub unit: Option<String>,
+}
+
+/// 每日一词请求参数
+#[derive(Debug, Deserialize)]
+pub struct WordOfTheDayQuery {
+    /// 日期（YYYY-MM-DD），默认为当天（UTC）
+    #[serde(default)]
+    pub date: Option<String>,
+}
+
+/// 自动补全提示项
+#[derive(Debug, Serialize)]
+pub struct Suggestion {
+    pub headword: String,
+    pub kana: Option<String>,
+    pub kanji: Option<String>,
+}
+
+impl From<ObunshaDictEntry> for Suggestion {
+    fn from(entry: ObunshaDictEntry) -> Self {
+        Suggestion {
+            headword: entry.headword,
+            kana: entry.kana_reading,
+            kanji: entry.kanji_writing.filter(|kanji| !kanji.is_empty()),
+        }
+    }
 }
 
 /// API响应结构
@@ -32,11 +466,18 @@ pub struct SearchResponse {
     /// 返回的词条数量
     pub count: usize,
     /// 词条列表
-    pub entries: Vec<ObunshaDictEntry>,
+    pub entries: Vec<RankedEntry>,
     /// 查询信息
     pub query_info: QueryInfo,
 }
 
+/// 按标题分组的词条
+#[derive(Debug, Serialize)]
+pub struct EntryGroup {
+    pub headword: String,
+    pub entries: Vec<RankedEntry>,
+}
+
 /// 查询信息
 #[derive(Debug, Serialize)]
 pub struct QueryInfo {
@@ -55,31 +496,227 @@ pub struct ErrorResponse {
     pub error: String,
 }
 
+/// POST /entries 请求体的默认大小上限（1 MB）
+pub const DEFAULT_BATCH_BODY_LIMIT: usize = 1024 * 1024;
+
+/// POST /import 请求体的默认大小上限（64 MB）
+pub const DEFAULT_IMPORT_BODY_LIMIT: usize = 64 * 1024 * 1024;
+
+/// 未指定 --db-pool-size 时同时执行的数据库查询数上限：CPU核心数（无法获取时为4）
+pub fn default_db_pool_size() -> usize {
+    std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4)
+}
+
+/// /stats 缓存的默认刷新间隔
+const DEFAULT_STATS_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// /stats 的缓存内容，由后台任务定期刷新
+#[derive(Debug, Clone)]
+pub struct CachedStats {
+    pub total_entries: i64,
+    pub unique_headwords: i64,
+    /// 只有指向另一个词的释义的词条数
+    pub pure_redirects: i64,
+    pub metadata: BTreeMap<String, String>,
+    /// 统计完成的时间，用于计算stale_seconds
+    pub refreshed_at: Instant,
+}
+
+/// /stats 的统计缓存，带代数（generation）
+/// 导入使缓存失效时代数加一；统计开始前记下代数，写回时代数已变说明统计期间发生过导入，结果作废
+#[derive(Debug, Default)]
+pub struct StatsCache {
+    generation: u64,
+    stats: Option<CachedStats>,
+}
+
+impl StatsCache {
+    /// 当前代数
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// 缓存的统计结果
+    fn stats(&self) -> Option<CachedStats> {
+        self.stats.clone()
+    }
+
+    /// 清空缓存并使之前开始的统计全部作废
+    fn invalidate(&mut self) {
+        self.generation += 1;
+        self.stats = None;
+    }
+
+    /// 写入在第generation代开始的统计结果；期间缓存已失效时丢弃，返回是否写入
+    fn store(&mut self, generation: u64, stats: CachedStats) -> bool {
+        if generation != self.generation {
+            return false;
+        }
+        self.stats = Some(stats);
+        true
+    }
+}
+
+/// 以只读模式打开数据库，FTS5支持情况使用启动时的探测结果，不在每个连接上重新探测
+fn open_readonly_db(db_path: &str, fts5_available: bool) -> rusqlite::Result<ObunshaDictDatabase> {
+    let db = ObunshaDictDatabase::open_readonly(db_path)?;
+    db.set_fts5_available(fts5_available);
+    Ok(db)
+}
+
+/// 重新统计词条数和词典元数据
+fn compute_stats(db_path: &str) -> Result<CachedStats, Box<dyn std::error::Error + Send + Sync>> {
+    let db = ObunshaDictDatabase::open_readonly(db_path)?;
+    let (total_entries, unique_headwords) = db.get_stats()?;
+    let metadata = db.get_metadata()?;
+    Ok(CachedStats {
+        total_entries,
+        unique_headwords,
+        pure_redirects: db.count_pure_redirects()?,
+        metadata,
+        refreshed_at: Instant::now(),
+    })
+}
+
 /// 应用状态 - 使用数据库路径而非直接共享连接
 #[derive(Clone)]
 pub struct AppState {
     pub db_path: String,
+    /// 词频表：词 -> 频率排名（越小越常用），为空时不按词频排序
+    pub frequencies: Arc<HashMap<String, u32>>,
+    /// 慢查询阈值（毫秒），为空时不记录慢查询
+    pub slow_query_threshold_ms: Option<u64>,
+    /// 管理接口的访问令牌，为空时管理接口不可用
+    pub admin_token: Option<Arc<str>>,
+    /// /stats 的统计缓存，尚未统计或导入后失效时为空
+    pub stats_cache: Arc<RwLock<StatsCache>>,
+    /// 同时执行的数据库查询数上限，每个查询占用一个SQLite连接，超出的请求排队等待
+    pub db_permits: Arc<Semaphore>,
+    /// SQLite是否支持FTS5（启动时探测一次），不支持时释义搜索退回LIKE扫描
+    pub fts5_available: bool,
+}
+
+/// 服务器的可选配置
+#[derive(Debug, Default)]
+pub struct ServerOptions {
+    /// 词频表（见 load_frequency_list），用于搜索结果排序
+    pub frequencies: HashMap<String, u32>,
+    /// 搜索耗时超过该毫秒数时打印慢查询警告
+    pub slow_query_threshold_ms: Option<u64>,
+    /// 管理接口（写入数据的接口）需要的Bearer令牌，未设置时管理接口返回403
+    pub admin_token: Option<String>,
+    /// /stats 缓存的刷新间隔，未设置时为60秒
+    pub stats_refresh_interval: Option<Duration>,
+    /// 批量查询（POST /entries）请求体的字节数上限，未设置时为 DEFAULT_BATCH_BODY_LIMIT
+    pub batch_body_limit: Option<usize>,
+    /// 导入（POST /import）请求体的字节数上限，未设置时为 DEFAULT_IMPORT_BODY_LIMIT
+    pub import_body_limit: Option<usize>,
+    /// 同时打开的数据库连接数上限，未设置时为 default_db_pool_size()
+    pub db_pool_size: Option<usize>,
 }
 
 /// 启动Web服务器
-pub async fn start_server(db_path: &str, port: u16) -> Result<(), Box<dyn std::error::Error>> {
+pub async fn start_server(
+    db_path: &str,
+    port: u16,
+    options: ServerOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // 绑定端口并启动服务器
+    let addr = format!("0.0.0.0:{}", port);
+    let listener = TcpListener::bind(&addr).await?;
+    serve(listener, db_path, options).await
+}
+
+/// 在已绑定的端口上启动服务器（负载测试先绑定空闲端口，再把实际地址交给客户端）
+pub async fn serve(
+    listener: TcpListener,
+    db_path: &str,
+    options: ServerOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
     println!("🚀 正在启动旺文社词典API服务器...");
+
+    // SQLite不支持FTS5时释义搜索仍可用，但退回LIKE扫描；只在启动时探测一次，各请求的连接共用结果
+    let fts5_available = ObunshaDictDatabase::open_readonly(db_path).map_or(true, |db| db.fts5_available());
+    if !fts5_available {
+        println!("⚠️  当前SQLite不支持FTS5，释义搜索将使用LIKE扫描，速度会明显变慢");
+    }
     
+    let db_pool_size = options.db_pool_size.unwrap_or_else(default_db_pool_size);
+    println!("🔌 最多同时使用 {} 个数据库连接", db_pool_size);
+
     let app_state = AppState {
         db_path: db_path.to_string(),
+        frequencies: Arc::new(options.frequencies),
+        slow_query_threshold_ms: options.slow_query_threshold_ms,
+        admin_token: options.admin_token.map(Arc::from),
+        stats_cache: Arc::new(RwLock::new(StatsCache::default())),
+        db_permits: Arc::new(Semaphore::new(db_pool_size)),
+        fts5_available,
     };
 
+    // 后台定期刷新 /stats 缓存，请求时直接返回缓存的结果
+    let refresh_interval = options.stats_refresh_interval.unwrap_or(DEFAULT_STATS_REFRESH_INTERVAL);
+    let cache = app_state.stats_cache.clone();
+    let stats_db_path = app_state.db_path.clone();
+    let stats_permits = app_state.db_permits.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(refresh_interval);
+        loop {
+            interval.tick().await;
+            let Ok(permit) = stats_permits.clone().acquire_owned().await else {
+                break;
+            };
+            let db_path = stats_db_path.clone();
+            let generation = cache.read().unwrap().generation();
+            let refresh = tokio::task::spawn_blocking(move || {
+                let _permit = permit;
+                compute_stats(&db_path)
+            });
+            match refresh.await {
+                Ok(Ok(stats)) => {
+                    cache.write().unwrap().store(generation, stats);
+                }
+                Ok(Err(e)) => println!("⚠️  刷新统计缓存失败: {}", e),
+                Err(e) => println!("⚠️  统计任务失败: {}", e),
+            }
+        }
+    });
+
+    // 读取请求体的接口限制请求体大小，超过时返回413
+    let batch_body_limit = DefaultBodyLimit::max(options.batch_body_limit.unwrap_or(DEFAULT_BATCH_BODY_LIMIT));
+    let import_body_limit = DefaultBodyLimit::max(options.import_body_limit.unwrap_or(DEFAULT_IMPORT_BODY_LIMIT));
+
+    // 管理接口：需要 Authorization: Bearer <admin_token>
+    let admin_routes = Router::new()
+        .route("/import", post(import_handler).layer(import_body_limit))
+        .route_layer(middleware::from_fn_with_state(app_state.clone(), require_admin_token));
+
     // 构建路由
     let app = Router::new()
         .route("/", get(root_handler))
         .route("/search", get(search_handler))
         .route("/stats", get(stats_handler))
+        .route("/stats/storage", get(storage_stats_handler))
+        .route("/stats/histogram", get(histogram_handler))
+        .route("/sync", get(sync_handler))
+        .route("/export/compact", get(compact_export_handler))
+        .route("/suggest", get(suggest_handler))
+        .route("/pattern", get(pattern_handler))
+        .route("/browse/window", get(window_handler))
+        .route("/kanji/readings", get(kanji_readings_handler))
+        .route("/disambiguate", get(disambiguate_handler))
+        .route("/conjugate", get(conjugate_handler))
+        .route("/kanji/usage", get(kanji_usage_handler))
+        .route("/pos", get(pos_handler))
+        .route("/word-of-the-day", get(word_of_the_day_handler))
+        .route("/about", get(about_handler))
+        .route("/entries", get(created_entries_handler).post(entries_handler).layer(batch_body_limit))
+        .route("/validate", post(validate_handler).layer(batch_body_limit))
+        .route("/entry/:data_id", get(entry_handler))
+        .merge(admin_routes)
         .with_state(app_state);
 
-    // 绑定端口并启动服务器
-    let addr = format!("0.0.0.0:{}", port);
-    let listener = TcpListener::bind(&addr).await?;
-    
+    let port = listener.local_addr()?.port();
     println!("✅ 服务器已启动！");
     println!("📡 API地址: http://localhost:{}", port);
     println!("🔍 查询接口: http://localhost:{}/search?word=単語", port);
@@ -99,25 +736,41 @@ async fn root_handler() -> Json<serde_json::Value> {
         "endpoints": {
             "/": "服务信息",
             "/search": "词条查询 (参数: word, search_type)",
-            "/stats": "数据库统计信息"
+            "/stats": "数据库统计信息",
+            "/stats/storage": "各文本列占用的字节数及占比",
+            "/stats/histogram": "标题长度分布 (参数: unit=chars|mora)",
+            "/sync": "增量同步 (参数: since, limit, offset)",
+            "/export/compact": "按id顺序分页导出紧凑格式（数组）的词条 (参数: limit, after_id；返回next_after_id作为下一页的after_id)",
+            "/suggest": "自动补全 (参数: q 或 romaji, limit)",
+            "/pattern": "按拍模式搜索读音 (参数: pattern, limit)，如 か__つ",
+            "/browse/window": "按读音顺序浏览词条及其前后词条 (参数: at, before, after)",
+            "/kanji/readings": "汇总某个汉字在所有词条中的读音 (参数: kanji)",
+            "/disambiguate": "同形异读词每个读音的代表词条 (参数: kanji)",
+            "/conjugate": "按词性生成动词、形容词的活用形 (参数: word)",
+            "/kanji/usage": "释义中使用了某个汉字的词条 (参数: kanji, limit, offset)",
+            "/pos": "所有词性及其词条数",
+            "/word-of-the-day": "每日一词，同一日期总是同一个词条 (参数: date，默认当天)",
+            "/about": "服务构建与数据版本",
+            "/entries": "按data_id批量查询 (POST，JSON数组)；GET按创建时间查询 (参数: created_from, created_to, limit, offset)",
+            "/entry/:data_id": "获取单个词条的完整内容",
+            "/validate": "校验词条 (POST，单个词条的JSON；不写入数据库)",
+            "/import": "导入词条 (POST，NDJSON，每行一个词条；需要管理令牌)"
         },
-        "search_types": [
-            "exact",
-            "fuzzy", 
-            "kana",
-            "kanji"
-        ],
+        "search_types": SearchType::names(),
         "example": "/search?word=愛&search_type=fuzzy"
     }))
 }
 
 /// 查询处理器
 async fn search_handler(
-    Query(params): Query<SearchQuery>,
+    query: Result<Query<SearchQuery>, QueryRejection>,
     State(state): State<AppState>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
     let start_time = std::time::Instant::now();
 
+    // 参数无法解析（如未知的search_type）时返回JSON格式的400，而不是默认的纯文本
+    let Query(params) = query.map_err(query_rejection_response)?;
+
     // 验证查询参数
     if params.word.trim().is_empty() {
         return Err((
@@ -129,35 +782,77 @@ async fn search_handler(
         ));
     }
 
+    if let Some(structured) = params.structured.as_deref().filter(|structured| *structured != "tree") {
+        return Err(error_response(StatusCode::BAD_REQUEST, format!("未知的structured: {}，支持: tree", structured)));
+    }
+    if let Some(format) = params.format.as_deref().filter(|format| *format != "markdown") {
+        return Err(error_response(StatusCode::BAD_REQUEST, format!("未知的format: {}，支持: markdown", format)));
+    }
+
+    // chain代替精确搜索的固定回退顺序，不能与其他搜索类型组合
+    if params.chain.is_some() && params.search_type.as_slice() != [SearchType::Exact] {
+        return Err(error_response(StatusCode::BAD_REQUEST, "chain 只能与 search_type=exact 一起使用".to_string()));
+    }
+
+    // 多个搜索类型时分别查询，按类型分组返回
+    if let [_, _, ..] = params.search_type.as_slice() {
+        return multi_type_search(params, state, start_time).await;
+    }
+    let search_type = params.search_type.as_slice()[0];
+
+    let jsonapi = match params.envelope.as_deref() {
+        None | Some("default") => false,
+        Some("jsonapi") if params.grouped => {
+            return Err(error_response(StatusCode::BAD_REQUEST, "envelope=jsonapi 不能与 grouped 同时使用".to_string()));
+        }
+        Some("jsonapi") => true,
+        Some(other) => {
+            return Err(error_response(StatusCode::BAD_REQUEST, format!("未知的envelope: {}，支持: default, jsonapi", other)));
+        }
+    };
+
+    // 布尔查询在查库前解析，语法错误或查询词过多时直接返回400
+    let boolean_query = parse_boolean_query(&params)?;
+
     // 在新线程中执行数据库查询
     let db_path = state.db_path.clone();
+    let fts5_available = state.fts5_available;
     let search_word = params.word.clone();
-    let search_type = params.search_type.clone();
-
-    let result = tokio::task::spawn_blocking(move || {
-        let db = ObunshaDictDatabase::new(&db_path)?;
-        
-        // 使用改进的搜索逻辑
-        let entries = match search_type.as_str() {
-            "exact" => {
-                // 先尝试假名精确搜索
-                let mut results = db.search_by_kana_exact(&search_word)?;
-                if results.is_empty() {
-                    // 如果假名搜索无结果，尝试汉字智能搜索
-                    results = db.search_by_kanji_smart(&search_word)?;
-                }
-                results
-            },
-            "kana" => db.search_by_kana_exact(&search_word)?,
-            "kanji" => db.search_by_kanji_smart(&search_word)?,
-            "fuzzy" | _ => db.search_by_headword(&search_word)?,
+    let has_examples = params.has_examples;
+    let hide_redirects = params.hide_redirects;
+    let chain = params.chain.clone();
+    let suggest = params.suggest;
+    let limit = filtered_search_limit(has_examples || hide_redirects);
+
+    let permit = acquire_db_permit(&state).await?;
+    let result = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        let db = open_readonly_db(&db_path, fts5_available)?;
+        let (mut entries, redirected_to, matched_tier) = match &chain {
+            Some(chain) => {
+                let (entries, tier) = db.search_best(&search_word, chain.as_slice())?;
+                let (entries, redirected_to) = follow_redirect(&db, &search_word, entries)?;
+                (entries, redirected_to, tier)
+            }
+            None => {
+                let (entries, redirected_to) = run_search(&db, search_type, &search_word, boolean_query, limit)?;
+                (entries, redirected_to, None)
+            }
+        };
+        let total_available = filter_and_count(&db, search_type, &search_word, &mut entries, limit, has_examples, hide_redirects)?;
+
+        // 指定suggest且无结果时给出"您是不是要找"候选
+        let suggestions = if suggest && entries.is_empty() {
+            db.suggestions_for(&search_word)?
+        } else {
+            Vec::new()
         };
 
-        Ok::<Vec<ObunshaDictEntry>, Box<dyn std::error::Error + Send + Sync>>(entries)
+        Ok::<_, Box<dyn std::error::Error + Send + Sync>>((entries, redirected_to, matched_tier, total_available, suggestions))
     }).await;
 
-    let entries = match result {
-        Ok(Ok(entries)) => entries,
+    let (mut entries, redirected_to, matched_tier, total_available, suggestions) = match result {
+        Ok(Ok(data)) => data,
         Ok(Err(e)) => {
             return Err((
                 StatusCode::INTERNAL_SERVER_ERROR,
@@ -178,65 +873,2104 @@ async fn search_handler(
         }
     };
 
+    rank_results(&mut entries, &state.frequencies);
+    present_entries(&mut entries, &params, search_type);
+
     let duration = start_time.elapsed();
 
-    Ok(Json(serde_json::json!({
+    // 记录慢查询，便于定位 search_by_kanji_smart 等扫描大量LIKE结果的查询
+    if is_slow_query(duration.as_millis(), state.slow_query_threshold_ms) {
+        println!(
+            "⚠️  慢查询: word={:?} search_type={} duration_ms={} results={}",
+            params.word,
+            search_type,
+            duration.as_millis(),
+            entries.len()
+        );
+    }
+
+    let truncated = is_truncated(total_available, entries.len());
+
+    let mut response = serde_json::json!({
         "success": true,
         "count": entries.len(),
-        "entries": entries,
         "query_info": {
             "word": params.word,
-            "search_type": params.search_type,
-            "duration_ms": duration.as_millis()
+            "search_type": search_type,
+            "duration_ms": duration.as_millis(),
+            "redirected_to": redirected_to,
+            "truncated": truncated,
+            "total_available": total_available
         }
-    })))
+    });
+
+    // 指定了chain时说明回退顺序和实际命中的一级（都没有结果时为null）
+    if let Some(chain) = &params.chain {
+        response["query_info"]["chain"] = serde_json::json!(chain.to_string());
+        response["query_info"]["matched_tier"] = serde_json::json!(matched_tier.map(SearchTier::as_str));
+    }
+
+    if suggest && entries.is_empty() {
+        response["suggestions"] = serde_json::json!(suggestions);
+    }
+
+    // JSON:API格式：成功标志以外的信息都放入meta
+    if jsonapi {
+        let mut meta = response;
+        if let Some(meta) = meta.as_object_mut() {
+            meta.remove("success");
+        }
+        return Ok(Json(jsonapi_document(&entries, meta)));
+    }
+
+    // 按标题分组时用groups代替扁平的entries
+    if params.grouped {
+        let groups = group_by_headword(entries);
+        response["group_count"] = serde_json::json!(groups.len());
+        response["groups"] = serde_json::json!(groups);
+    } else {
+        response["entries"] = serde_json::json!(entries);
+    }
+
+    Ok(Json(response))
 }
 
-/// 统计信息处理器
-async fn stats_handler(
-    State(state): State<AppState>,
+/// 多个搜索类型的查询：逐个类型查询，每组内按data_id去重，返回 {results: {类型: [...]}}
+/// 不支持grouped和envelope=jsonapi
+async fn multi_type_search(
+    params: SearchQuery,
+    state: AppState,
+    start_time: Instant,
 ) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+    if params.grouped || params.envelope.as_deref().is_some_and(|envelope| envelope != "default") {
+        return Err(error_response(
+            StatusCode::BAD_REQUEST,
+            "多个搜索类型时不支持 grouped 和 envelope".to_string(),
+        ));
+    }
+
+    let boolean_query = parse_boolean_query(&params)?;
     let db_path = state.db_path.clone();
-    
+    let fts5_available = state.fts5_available;
+    let search_word = params.word.clone();
+    let search_types = params.search_type.clone();
+    let has_examples = params.has_examples;
+    let hide_redirects = params.hide_redirects;
+    let suggest = params.suggest;
+    let limit = filtered_search_limit(has_examples || hide_redirects);
+
+    let permit = acquire_db_permit(&state).await?;
     let result = tokio::task::spawn_blocking(move || {
-        let db = ObunshaDictDatabase::new(&db_path)?;
-        let (count, unique_headwords) = db.get_stats()?;
-        Ok::<(i64, i64), Box<dyn std::error::Error + Send + Sync>>((count, unique_headwords))
+        let _permit = permit;
+        let db = open_readonly_db(&db_path, fts5_available)?;
+        let mut groups = Vec::new();
+        for &search_type in search_types.as_slice() {
+            let boolean_query = if search_type == SearchType::Boolean { boolean_query.clone() } else { None };
+            let (mut entries, redirected_to) = run_search(&db, search_type, &search_word, boolean_query, limit)?;
+            let total_available = filter_and_count(&db, search_type, &search_word, &mut entries, limit, has_examples, hide_redirects)?;
+            let mut seen = std::collections::HashSet::new();
+            entries.retain(|ranked| seen.insert(ranked.entry.data_id.clone()));
+            groups.push((search_type, entries, redirected_to, total_available));
+        }
+
+        // 所有类型都没有结果时才给出候选（候选只取决于查询词）
+        let suggestions = if suggest && groups.iter().all(|(_, entries, _, _)| entries.is_empty()) {
+            db.suggestions_for(&search_word)?
+        } else {
+            Vec::new()
+        };
+        Ok::<_, Box<dyn std::error::Error + Send + Sync>>((groups, suggestions))
     }).await;
 
-    let (count, unique_headwords) = match result {
-        Ok(Ok(stats)) => stats,
+    let (mut groups, suggestions) = match result {
+        Ok(Ok(groups)) => groups,
         Ok(Err(e)) => {
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    success: false,
-                    error: format!("获取统计信息失败: {}", e),
-                }),
-            ));
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("数据库查询失败: {}", e)));
         }
         Err(e) => {
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    success: false,
-                    error: format!("统计任务失败: {}", e),
-                }),
-            ));
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("查询任务失败: {}", e)));
         }
     };
 
-    Ok(Json(serde_json::json!({
+    let mut results = serde_json::Map::new();
+    let mut group_info = serde_json::Map::new();
+    let mut count = 0;
+    for (search_type, entries, redirected_to, total_available) in &mut groups {
+        rank_results(entries, &state.frequencies);
+        present_entries(entries, &params, *search_type);
+        count += entries.len();
+        group_info.insert(search_type.to_string(), serde_json::json!({
+            "redirected_to": redirected_to,
+            "truncated": is_truncated(*total_available, entries.len()),
+            "total_available": total_available
+        }));
+        results.insert(search_type.to_string(), serde_json::json!(entries));
+    }
+
+    let mut response = serde_json::json!({
         "success": true,
-        "database": {
-            "path": state.db_path,
-            "total_entries": count,
-            "unique_headwords": unique_headwords,
-            "status": "已连接"
+        "count": count,
+        "query_info": {
+            "word": params.word,
+            "search_type": params.search_type,
+            "duration_ms": start_time.elapsed().as_millis(),
+            "groups": group_info
         },
-        "api": {
-            "version": "1.0.0",
-            "supported_search_types": ["exact", "fuzzy", "kana", "kanji"]
-        }
-    })))
-}
\ No newline at end of file
+        "results": results
+    });
+    if suggest && count == 0 {
+        response["suggestions"] = serde_json::json!(suggestions);
+    }
+    Ok(Json(response))
+}
+
+/// 按has_examples、hide_redirects过滤一种搜索类型的结果，有上限的类型截断为DEFINITION_SEARCH_LIMIT条
+/// 返回过滤后的匹配总数：结果达到上限时再统计（不支持统计的搜索类型或过滤后为None）
+fn filter_and_count(
+    db: &ObunshaDictDatabase,
+    search_type: SearchType,
+    search_word: &str,
+    entries: &mut Vec<RankedEntry>,
+    limit: usize,
+    has_examples: bool,
+    hide_redirects: bool,
+) -> Result<Option<i64>, Box<dyn std::error::Error + Send + Sync>> {
+    let capped = search_type.is_capped() && entries.len() >= limit;
+
+    if has_examples {
+        retain_with_examples(db, entries)?;
+    }
+    if hide_redirects {
+        entries.retain(|ranked| !ranked.entry.is_pure_redirect());
+    }
+    let filtered_count = entries.len();
+    if search_type.is_capped() {
+        entries.truncate(DEFINITION_SEARCH_LIMIT);
+    }
+
+    if !capped {
+        return Ok(Some(filtered_count as i64));
+    }
+    if has_examples || hide_redirects {
+        return Ok(None);
+    }
+    Ok(db.count_search_results(search_type.as_str(), search_word)?)
+}
+
+/// 结果是否被截断：总数未知时，结果达到上限即视为被截断
+fn is_truncated(total_available: Option<i64>, returned: usize) -> bool {
+    match total_available {
+        Some(total) => total > returned as i64,
+        None => true,
+    }
+}
+
+/// 搜索类型包含boolean时在查库前解析布尔查询，语法错误或查询词过多时返回400
+fn parse_boolean_query(params: &SearchQuery) -> Result<Option<BooleanQuery>, (StatusCode, Json<ErrorResponse>)> {
+    if !params.search_type.as_slice().contains(&SearchType::Boolean) {
+        return Ok(None);
+    }
+    BooleanQuery::parse(&params.word, MAX_BOOLEAN_TERMS)
+        .map(Some)
+        .map_err(|message| error_response(StatusCode::BAD_REQUEST, message))
+}
+
+/// 有上限的搜索取多少条：按结果过滤时多取，使过滤后仍能凑满DEFINITION_SEARCH_LIMIT
+fn filtered_search_limit(filtered: bool) -> usize {
+    if filtered {
+        DEFINITION_SEARCH_LIMIT * FILTERED_SEARCH_OVERFETCH
+    } else {
+        DEFINITION_SEARCH_LIMIT
+    }
+}
+
+/// 只保留释义中含例句的词条
+fn retain_with_examples(
+    db: &ObunshaDictDatabase,
+    entries: &mut Vec<RankedEntry>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let ids: Vec<i64> = entries.iter().filter_map(|ranked| ranked.entry.id).collect();
+    let with_examples = db.ids_with_examples(&ids)?;
+    entries.retain(|ranked| ranked.entry.id.is_some_and(|id| with_examples.contains(&id)));
+    Ok(())
+}
+
+/// 按请求参数处理返回的词条：预览截断、高亮、Markdown释义
+fn present_entries(entries: &mut [RankedEntry], params: &SearchQuery, search_type: SearchType) {
+    // 先截断再高亮，避免切断<mark>标签
+    if let Some(preview_len) = params.preview_len {
+        for ranked in entries.iter_mut() {
+            ranked.entry.definition_text = truncate_preview(&ranked.entry.definition_text, preview_len);
+        }
+    }
+
+    if params.highlight {
+        let query = params.word.trim();
+        for ranked in entries.iter_mut() {
+            if search_type == SearchType::Definition {
+                ranked.entry.definition_text = highlight_matches(&ranked.entry.definition_text, query);
+            } else {
+                ranked.entry.headword = highlight_matches(&ranked.entry.headword, query);
+            }
+        }
+    }
+
+    if params.format.as_deref() == Some("markdown") {
+        for ranked in entries.iter_mut() {
+            ranked.definition_markdown = Some(definition_to_markdown(&ranked.entry.definition_html));
+        }
+    }
+
+    if params.structured.as_deref() == Some("tree") {
+        for ranked in entries.iter_mut() {
+            ranked.senses = Some(parse_sense_tree(&ranked.entry.definition_html));
+        }
+    }
+}
+
+/// 将搜索结果包装为JSON:API文档：每个词条为 {type: "entry", id: data_id, attributes}
+fn jsonapi_document(entries: &[RankedEntry], meta: serde_json::Value) -> serde_json::Value {
+    let data: Vec<serde_json::Value> = entries
+        .iter()
+        .map(|ranked| {
+            let mut attributes = serde_json::json!(ranked);
+            if let Some(attributes) = attributes.as_object_mut() {
+                attributes.remove("data_id");
+            }
+            serde_json::json!({
+                "type": "entry",
+                "id": ranked.entry.data_id,
+                "attributes": attributes
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "data": data,
+        "meta": meta
+    })
+}
+
+/// 按搜索类型查询词条，返回结果和重定向目标（无结果时沿重定向链查找）
+fn run_search(
+    db: &ObunshaDictDatabase,
+    search_type: SearchType,
+    search_word: &str,
+    boolean_query: Option<BooleanQuery>,
+    limit: usize,
+) -> Result<(Vec<RankedEntry>, Option<String>), Box<dyn std::error::Error + Send + Sync>> {
+    // 使用改进的搜索逻辑
+    let entries = match search_type {
+        // 释义搜索按相关度排序，返回带得分的结果
+        SearchType::Definition => {
+            return Ok((db.search_in_definitions(search_word, limit)?, None));
+        }
+        // 容错搜索按假名距离排序，返回带得分的结果
+        SearchType::Typo => {
+            return Ok((db.search_by_kana_typo(search_word.trim(), TYPO_MAX_DISTANCE, limit)?, None));
+        }
+        SearchType::Boolean => match boolean_query {
+            Some(query) => {
+                let entries = db.search_boolean(&query, limit)?;
+                return Ok((entries.into_iter().map(RankedEntry::from).collect(), None));
+            }
+            None => Vec::new(),
+        },
+        SearchType::Exact => db.search_anything(search_word)?,
+        SearchType::Kana => db.search_by_kana_exact(search_word)?,
+        SearchType::Kanji => db.search_by_kanji_smart(search_word)?,
+        SearchType::Prefix => db.search_headword_prefix(search_word.trim(), limit)?,
+        SearchType::Suffix => db.search_headword_suffix(search_word.trim(), limit)?,
+        SearchType::English => db.search_by_english_gloss(search_word, limit)?,
+        SearchType::Stem => db.search_by_kanji_stem(search_word, limit)?,
+        SearchType::Fuzzy => db.search_by_headword(search_word)?,
+        SearchType::Variant => db.search_by_kanji_variants(search_word)?,
+    };
+
+    follow_redirect(db, search_word, entries)
+}
+
+/// 无结果时沿重定向链查找目标词条，返回结果和重定向到的标题
+fn follow_redirect(
+    db: &ObunshaDictDatabase,
+    search_word: &str,
+    mut entries: Vec<ObunshaDictEntry>,
+) -> Result<(Vec<RankedEntry>, Option<String>), Box<dyn std::error::Error + Send + Sync>> {
+    let mut redirected_to = None;
+    if entries.is_empty()
+        && let Some(target) = db.resolve_redirect_chain(search_word, MAX_REDIRECT_DEPTH)?
+    {
+        entries = db.find_by_headword(&target)?;
+        redirected_to = Some(target);
+    }
+
+    Ok((entries.into_iter().map(RankedEntry::from).collect(), redirected_to))
+}
+
+/// 导入失败时最多返回的错误详情数
+const MAX_IMPORT_ERRORS: usize = 20;
+
+/// 管理接口中间件：未配置令牌时返回403，缺少或错误的令牌返回401
+async fn require_admin_token(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    let Some(token) = &state.admin_token else {
+        return Err(error_response(
+            StatusCode::FORBIDDEN,
+            "服务器未配置管理令牌（--admin-token），管理接口不可用".to_string(),
+        ));
+    };
+    if !bearer_token_matches(request.headers(), token) {
+        return Err(error_response(StatusCode::UNAUTHORIZED, "缺少或错误的访问令牌".to_string()));
+    }
+    Ok(next.run(request).await)
+}
+
+/// Authorization头是否为与expected一致的Bearer令牌（按固定时间比较，避免按耗时猜测令牌）
+fn bearer_token_matches(headers: &HeaderMap, expected: &str) -> bool {
+    let Some(provided) = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    else {
+        return false;
+    };
+
+    provided.len() == expected.len()
+        && provided
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// NDJSON导入处理器 - 每行一个ObunshaDictEntry，按data_id更新已有词条
+/// 无法解析的行被跳过并计入failed（管理接口，见require_admin_token）
+async fn import_handler(
+    State(state): State<AppState>,
+    body: Result<String, StringRejection>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+    let body = body.map_err(|rejection| error_response(rejection.status(), rejection.body_text()))?;
+    let (entries, errors) = parse_ndjson_entries(&body);
+    if entries.is_empty() && errors.is_empty() {
+        return Err(error_response(StatusCode::BAD_REQUEST, "请求体中没有词条".to_string()));
+    }
+
+    let db_path = state.db_path.clone();
+    let permit = acquire_db_permit(&state).await?;
+    let result = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        if entries.is_empty() {
+            return Ok(0);
+        }
+        let db = ObunshaDictDatabase::open_for_write(&db_path)?;
+        let inserted = db.insert_entries_batch(&entries)?;
+        db.rebuild_definition_index()?;
+        Ok::<_, Box<dyn std::error::Error + Send + Sync>>(inserted)
+    }).await;
+
+    let inserted = match result {
+        Ok(Ok(inserted)) => inserted,
+        Ok(Err(e)) => {
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("导入失败: {}", e)));
+        }
+        Err(e) => {
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("导入任务失败: {}", e)));
+        }
+    };
+
+    // 词条数已变化，下次 /stats 重新统计
+    if inserted > 0 {
+        state.stats_cache.write().unwrap().invalidate();
+    }
+
+    let error_details: Vec<serde_json::Value> = errors
+        .iter()
+        .take(MAX_IMPORT_ERRORS)
+        .map(|(line, error)| serde_json::json!({ "line": line, "error": error }))
+        .collect();
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "inserted": inserted,
+        "failed": errors.len(),
+        "errors": error_details
+    })))
+}
+
+/// 词条校验处理器
+/// 对单个词条执行ObunshaDictEntry::validate的全部检查，不写入数据库
+async fn validate_handler(
+    entry: Result<Json<ObunshaDictEntry>, JsonRejection>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+    let Json(entry) = entry.map_err(|rejection| error_response(rejection.status(), rejection.body_text()))?;
+    let problems = entry.validate();
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "valid": problems.is_empty(),
+        "problems": problems
+    })))
+}
+
+/// 解析NDJSON请求体，跳过空行；返回解析成功的词条和失败的(行号, 错误信息)
+fn parse_ndjson_entries(body: &str) -> (Vec<ObunshaDictEntry>, Vec<(usize, String)>) {
+    let mut entries = Vec::new();
+    let mut errors = Vec::new();
+
+    for (index, line) in body.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<ObunshaDictEntry>(line) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => errors.push((index + 1, e.to_string())),
+        }
+    }
+
+    (entries, errors)
+}
+
+/// 按data_id批量查询处理器
+/// 请求体为data_id的JSON数组，按请求顺序返回，不存在的id被忽略
+async fn entries_handler(
+    State(state): State<AppState>,
+    ids: Result<Json<Vec<String>>, JsonRejection>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+    // 请求体超过大小上限时为413，格式错误时为400/422
+    let Json(ids) = ids.map_err(|rejection| error_response(rejection.status(), rejection.body_text()))?;
+    if ids.len() > MAX_LOOKUP_IDS {
+        return Err(error_response(
+            StatusCode::BAD_REQUEST,
+            format!("一次最多查询 {} 个data_id", MAX_LOOKUP_IDS),
+        ));
+    }
+
+    let db_path = state.db_path.clone();
+    let fts5_available = state.fts5_available;
+    let requested = ids.len();
+
+    let permit = acquire_db_permit(&state).await?;
+    let result = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        let db = open_readonly_db(&db_path, fts5_available)?;
+        let entries = db.get_by_data_ids(&ids)?;
+        Ok::<_, Box<dyn std::error::Error + Send + Sync>>(entries)
+    }).await;
+
+    let entries = match result {
+        Ok(Ok(entries)) => entries,
+        Ok(Err(e)) => {
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("数据库查询失败: {}", e)));
+        }
+        Err(e) => {
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("查询任务失败: {}", e)));
+        }
+    };
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "requested": requested,
+        "count": entries.len(),
+        "entries": entries
+    })))
+}
+
+/// 单个词条处理器，返回完整内容
+async fn entry_handler(
+    Path(data_id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+    let db_path = state.db_path.clone();
+    let fts5_available = state.fts5_available;
+    let lookup_id = data_id.clone();
+
+    let permit = acquire_db_permit(&state).await?;
+    let result = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        let db = open_readonly_db(&db_path, fts5_available)?;
+        let entries = db.get_by_data_ids(&[lookup_id])?;
+        Ok::<_, Box<dyn std::error::Error + Send + Sync>>(entries.into_iter().next())
+    }).await;
+
+    match result {
+        Ok(Ok(Some(entry))) => Ok(Json(serde_json::json!({
+            "success": true,
+            "entry": entry
+        }))),
+        Ok(Ok(None)) => Err(error_response(StatusCode::NOT_FOUND, format!("词条 {} 不存在", data_id))),
+        Ok(Err(e)) => Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("数据库查询失败: {}", e))),
+        Err(e) => Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("查询任务失败: {}", e))),
+    }
+}
+
+/// 构建与数据版本处理器
+/// 用于确认部署的服务版本和数据库对应的词典版本，便于报告问题
+async fn about_handler(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+    let db_path = state.db_path.clone();
+    let fts5_available = state.fts5_available;
+
+    let permit = acquire_db_permit(&state).await?;
+    let result = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        let db = open_readonly_db(&db_path, fts5_available)?;
+        let schema_version = db.schema_version()?;
+        let metadata = db.get_metadata()?;
+        Ok::<_, Box<dyn std::error::Error + Send + Sync>>((schema_version, metadata))
+    }).await;
+
+    let (schema_version, metadata) = match result {
+        Ok(Ok(about)) => about,
+        Ok(Err(e)) => {
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("读取版本信息失败: {}", e)));
+        }
+        Err(e) => {
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("版本信息任务失败: {}", e)));
+        }
+    };
+
+    let git_commit = option_env!("GIT_COMMIT").filter(|commit| !commit.is_empty());
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "build": {
+            "version": env!("CARGO_PKG_VERSION"),
+            "git_commit": git_commit
+        },
+        "data": {
+            "schema_version": schema_version,
+            "metadata": metadata
+        }
+    })))
+}
+
+/// 将词条按标题分组，组的顺序为各标题首次出现的顺序
+fn group_by_headword(entries: Vec<RankedEntry>) -> Vec<EntryGroup> {
+    let mut groups: Vec<EntryGroup> = Vec::new();
+    let mut group_index: HashMap<String, usize> = HashMap::new();
+
+    for ranked in entries {
+        match group_index.get(&ranked.entry.headword) {
+            Some(&index) => groups[index].entries.push(ranked),
+            None => {
+                group_index.insert(ranked.entry.headword.clone(), groups.len());
+                groups.push(EntryGroup {
+                    headword: ranked.entry.headword.clone(),
+                    entries: vec![ranked],
+                });
+            }
+        }
+    }
+
+    groups
+}
+
+/// 增量同步处理器 - 返回指定时间之后修改过的词条
+async fn sync_handler(
+    Query(params): Query<SyncQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+    let db_path = state.db_path.clone();
+    let fts5_available = state.fts5_available;
+    let since = params.since.clone();
+    let limit = page_limit(params.limit, MAX_SYNC_LIMIT)?;
+    let offset = params.offset;
+
+    let permit = acquire_db_permit(&state).await?;
+    let result = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        let db = open_readonly_db(&db_path, fts5_available)?;
+        let normalized = match db.normalize_timestamp(&since)? {
+            Some(normalized) => normalized,
+            None => return Ok(None),
+        };
+        // 多取一条判断是否还有下一页
+        let entries = db.entries_modified_since(&normalized, limit + 1, offset)?;
+        Ok::<_, Box<dyn std::error::Error + Send + Sync>>(Some((normalized, entries)))
+    }).await;
+
+    let (normalized, mut entries) = match result {
+        Ok(Ok(Some(data))) => data,
+        Ok(Ok(None)) => {
+            return Err(error_response(
+                StatusCode::BAD_REQUEST,
+                format!("无效的时间戳: {}（应为ISO-8601格式，如 2024-01-01T00:00:00Z）", params.since),
+            ));
+        }
+        Ok(Err(e)) => {
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("数据库查询失败: {}", e)));
+        }
+        Err(e) => {
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("查询任务失败: {}", e)));
+        }
+    };
+
+    let has_more = entries.len() > limit;
+    entries.truncate(limit);
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "count": entries.len(),
+        "entries": entries,
+        "sync_info": {
+            "since": normalized,
+            "limit": limit,
+            "offset": offset,
+            "has_more": has_more
+        }
+    })))
+}
+
+/// 紧凑格式导出处理器 - 每个词条为数组，列名只在columns中出现一次，用于整库传输
+async fn compact_export_handler(
+    Query(params): Query<CompactQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+    let db_path = state.db_path.clone();
+    let fts5_available = state.fts5_available;
+    let limit = page_limit(params.limit, MAX_SYNC_LIMIT)?;
+    let after_id = params.after_id;
+
+    let permit = acquire_db_permit(&state).await?;
+    let result = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        let db = open_readonly_db(&db_path, fts5_available)?;
+        // 多取一条判断是否还有下一页
+        let rows = db.compact_entries(limit + 1, after_id)?;
+        Ok::<_, Box<dyn std::error::Error + Send + Sync>>(rows)
+    }).await;
+
+    let mut rows = match result {
+        Ok(Ok(rows)) => rows,
+        Ok(Err(e)) => {
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("数据库查询失败: {}", e)));
+        }
+        Err(e) => {
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("查询任务失败: {}", e)));
+        }
+    };
+
+    let has_more = rows.len() > limit;
+    rows.truncate(limit);
+    let next_after_id = rows.last().map(|(id, _)| *id).unwrap_or(after_id);
+    let rows: Vec<_> = rows.into_iter().map(|(_, row)| row).collect();
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "columns": COMPACT_COLUMNS,
+        "count": rows.len(),
+        "rows": rows,
+        "page_info": {
+            "limit": limit,
+            "after_id": after_id,
+            "next_after_id": next_after_id,
+            "has_more": has_more
+        }
+    })))
+}
+
+/// 按创建时间查询处理器 - 用于核对或回滚某次导入写入的词条
+async fn created_entries_handler(
+    Query(params): Query<CreatedQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+    let db_path = state.db_path.clone();
+    let fts5_available = state.fts5_available;
+    let created_from = params.created_from.clone();
+    let created_to = params.created_to.clone();
+    let limit = page_limit(params.limit, MAX_SYNC_LIMIT)?;
+    let offset = params.offset;
+
+    let permit = acquire_db_permit(&state).await?;
+    let result = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        let db = open_readonly_db(&db_path, fts5_available)?;
+        let (from, to) = match (db.normalize_timestamp(&created_from)?, db.normalize_timestamp(&created_to)?) {
+            (Some(from), Some(to)) => (from, to),
+            _ => return Ok(None),
+        };
+        // 多取一条判断是否还有下一页
+        let entries = db.entries_created_between(&from, &to, limit + 1, offset)?;
+        Ok::<_, Box<dyn std::error::Error + Send + Sync>>(Some((from, to, entries)))
+    }).await;
+
+    let (from, to, mut entries) = match result {
+        Ok(Ok(Some(data))) => data,
+        Ok(Ok(None)) => {
+            return Err(error_response(
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "无效的时间戳: {} / {}（应为ISO-8601格式，如 2024-01-01T00:00:00Z）",
+                    params.created_from, params.created_to
+                ),
+            ));
+        }
+        Ok(Err(e)) => {
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("数据库查询失败: {}", e)));
+        }
+        Err(e) => {
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("查询任务失败: {}", e)));
+        }
+    };
+
+    let has_more = entries.len() > limit;
+    entries.truncate(limit);
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "count": entries.len(),
+        "entries": entries,
+        "query_info": {
+            "created_from": from,
+            "created_to": to,
+            "limit": limit,
+            "offset": offset,
+            "has_more": has_more
+        }
+    })))
+}
+
+/// 自动补全处理器 - 根据罗马字前缀返回候选词条
+async fn suggest_handler(
+    Query(params): Query<SuggestQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+    let query = params.q.as_deref().map(str::trim).filter(|q| !q.is_empty()).map(str::to_string);
+    let romaji = params.romaji.as_deref().map(str::trim).filter(|romaji| !romaji.is_empty()).map(str::to_string);
+    if query.is_none() && romaji.is_none() {
+        return Err(error_response(StatusCode::BAD_REQUEST, "q或romaji参数不能为空".to_string()));
+    }
+
+    let db_path = state.db_path.clone();
+    let fts5_available = state.fts5_available;
+    let limit = params.limit.min(MAX_SUGGEST_LIMIT);
+
+    let permit = acquire_db_permit(&state).await?;
+    let result = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        let db = open_readonly_db(&db_path, fts5_available)?;
+        let entries = match (query, romaji) {
+            (Some(query), _) => db.suggest(&query, limit)?,
+            (None, Some(romaji)) => db.suggest_by_romaji_prefix(&romaji, limit)?,
+            (None, None) => Vec::new(),
+        };
+        Ok::<_, Box<dyn std::error::Error + Send + Sync>>(entries)
+    }).await;
+
+    let entries = match result {
+        Ok(Ok(entries)) => entries,
+        Ok(Err(e)) => {
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("数据库查询失败: {}", e)));
+        }
+        Err(e) => {
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("查询任务失败: {}", e)));
+        }
+    };
+
+    let suggestions: Vec<Suggestion> = entries.into_iter().map(Suggestion::from).collect();
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "count": suggestions.len(),
+        "suggestions": suggestions
+    })))
+}
+
+/// 单个汉字参数：去掉首尾空白后必须恰好是一个字符
+fn single_kanji(text: &str) -> Result<char, (StatusCode, Json<ErrorResponse>)> {
+    let mut chars = text.trim().chars();
+    match (chars.next(), chars.next()) {
+        (Some(kanji), None) => Ok(kanji),
+        _ => Err(error_response(StatusCode::BAD_REQUEST, "kanji参数必须是单个汉字".to_string())),
+    }
+}
+
+/// 汉字用例处理器 - 释义中使用了该汉字的词条，按id分页（全表扫描，limit最大200）
+async fn kanji_usage_handler(
+    Query(params): Query<KanjiUsageQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+    let kanji = single_kanji(&params.kanji)?;
+    let limit = page_limit(params.limit, MAX_KANJI_USAGE_LIMIT)?;
+    let offset = params.offset;
+
+    let db_path = state.db_path.clone();
+    let fts5_available = state.fts5_available;
+    let permit = acquire_db_permit(&state).await?;
+    let result = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        let db = open_readonly_db(&db_path, fts5_available)?;
+        // 多取一条判断是否还有下一页
+        let entries = db.entries_using_kanji_in_definition(kanji, limit + 1, offset)?;
+        Ok::<_, Box<dyn std::error::Error + Send + Sync>>(entries)
+    }).await;
+
+    let mut entries = match result {
+        Ok(Ok(entries)) => entries,
+        Ok(Err(e)) => {
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("数据库查询失败: {}", e)));
+        }
+        Err(e) => {
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("查询任务失败: {}", e)));
+        }
+    };
+
+    let has_more = entries.len() > limit;
+    entries.truncate(limit);
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "count": entries.len(),
+        "entries": entries,
+        "query_info": {
+            "kanji": kanji.to_string(),
+            "limit": limit,
+            "offset": offset,
+            "has_more": has_more
+        }
+    })))
+}
+
+/// 汉字读音处理器 - 汇总汉字表记中含该汉字的所有词条的读音
+async fn kanji_readings_handler(
+    Query(params): Query<KanjiReadingsQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+    let kanji = single_kanji(&params.kanji)?;
+
+    let db_path = state.db_path.clone();
+    let fts5_available = state.fts5_available;
+    let permit = acquire_db_permit(&state).await?;
+    let result = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        let db = open_readonly_db(&db_path, fts5_available)?;
+        let readings = db.readings_for_kanji(kanji)?;
+        Ok::<_, Box<dyn std::error::Error + Send + Sync>>(readings)
+    }).await;
+
+    let readings = match result {
+        Ok(Ok(readings)) => readings,
+        Ok(Err(e)) => {
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("数据库查询失败: {}", e)));
+        }
+        Err(e) => {
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("查询任务失败: {}", e)));
+        }
+    };
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "kanji": kanji.to_string(),
+        "count": readings.len(),
+        "readings": readings
+    })))
+}
+
+/// 同形异读词处理器 - 汉字表记对应的每个读音返回一个代表词条，供用户选择要注音的读音
+async fn disambiguate_handler(
+    Query(params): Query<DisambiguateQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+    let kanji = params.kanji.trim().to_string();
+    if kanji.is_empty() {
+        return Err(error_response(StatusCode::BAD_REQUEST, "汉字表记不能为空".to_string()));
+    }
+
+    let db_path = state.db_path.clone();
+    let fts5_available = state.fts5_available;
+    let search_kanji = kanji.clone();
+    let permit = acquire_db_permit(&state).await?;
+    let result = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        let db = open_readonly_db(&db_path, fts5_available)?;
+        let readings = db.disambiguate(&search_kanji)?;
+        Ok::<_, Box<dyn std::error::Error + Send + Sync>>(readings)
+    }).await;
+
+    let readings = match result {
+        Ok(Ok(readings)) => readings,
+        Ok(Err(e)) => {
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("数据库查询失败: {}", e)));
+        }
+        Err(e) => {
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("查询任务失败: {}", e)));
+        }
+    };
+
+    let readings: Vec<serde_json::Value> = readings
+        .into_iter()
+        .map(|(reading, entry)| serde_json::json!({ "reading": reading, "entry": entry }))
+        .collect();
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "kanji": kanji,
+        "count": readings.len(),
+        "readings": readings
+    })))
+}
+
+/// 活用形处理器 - 查到词条后按词性生成活用形，不可活用的词性返回空的forms和说明
+async fn conjugate_handler(
+    Query(params): Query<ConjugateQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+    let word = params.word.trim().to_string();
+    if word.is_empty() {
+        return Err(error_response(StatusCode::BAD_REQUEST, "查询词不能为空".to_string()));
+    }
+
+    let db_path = state.db_path.clone();
+    let fts5_available = state.fts5_available;
+    let search_word = word.clone();
+    let permit = acquire_db_permit(&state).await?;
+    let result = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        let db = open_readonly_db(&db_path, fts5_available)?;
+        let entries = db.search_anything(&search_word)?;
+        Ok::<_, Box<dyn std::error::Error + Send + Sync>>(entries)
+    }).await;
+
+    let entries = match result {
+        Ok(Ok(entries)) => entries,
+        Ok(Err(e)) => {
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("数据库查询失败: {}", e)));
+        }
+        Err(e) => {
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("查询任务失败: {}", e)));
+        }
+    };
+
+    // 同一个词有多个词条（如名词和动词）时优先用可活用的词条
+    let class_of = |entry: &ObunshaDictEntry| entry.part_of_speech.as_deref().and_then(conjugation::conjugation_class);
+    let Some(entry) = entries.iter().find(|&entry| class_of(entry).is_some()).or(entries.first()) else {
+        return Err(error_response(StatusCode::NOT_FOUND, format!("未找到词条: {}", word)));
+    };
+
+    let kanji = entry.kanji_variants.first().map(String::as_str);
+    let (dictionary_form, forms, note) = match class_of(entry) {
+        Some(class) => {
+            let dictionary_form = conjugation::dictionary_form(entry.kana_reading.as_deref(), kanji, class);
+            let forms = dictionary_form.as_deref().map(|form| conjugation::conjugate(form, class)).unwrap_or_default();
+            let note = forms.is_empty().then(|| "词典形的词尾与词性不符，无法生成活用形".to_string());
+            (dictionary_form, forms, note)
+        }
+        None => (
+            kanji.or(entry.kana_reading.as_deref()).map(str::to_string),
+            Vec::new(),
+            Some(format!("词性「{}」不是可活用的动词或形容词", entry.part_of_speech.as_deref().unwrap_or("未标注"))),
+        ),
+    };
+
+    let forms: serde_json::Map<String, serde_json::Value> =
+        forms.into_iter().map(|(name, surface)| (name.to_string(), serde_json::Value::String(surface))).collect();
+    let mut response = serde_json::json!({
+        "success": true,
+        "word": word,
+        "data_id": entry.data_id,
+        "headword": entry.headword,
+        "part_of_speech": entry.part_of_speech,
+        "dictionary_form": dictionary_form,
+        "forms": forms
+    });
+    if let Some(note) = note {
+        response["note"] = serde_json::Value::String(note);
+    }
+    Ok(Json(response))
+}
+
+/// 每日一词处理器 - 按日期确定性地选出一个词条，无需服务端状态
+async fn word_of_the_day_handler(
+    Query(params): Query<WordOfTheDayQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+    if let Some(date) = &params.date
+        && !is_iso_date(date)
+    {
+        return Err(error_response(StatusCode::BAD_REQUEST, format!("日期格式应为YYYY-MM-DD: {}", date)));
+    }
+
+    let db_path = state.db_path.clone();
+    let fts5_available = state.fts5_available;
+    let permit = acquire_db_permit(&state).await?;
+    let result = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        let db = open_readonly_db(&db_path, fts5_available)?;
+        let date = match params.date {
+            Some(date) => date,
+            None => today_utc(),
+        };
+        let entry = db.entry_of_the_day(&date)?;
+        Ok::<_, Box<dyn std::error::Error + Send + Sync>>((date, entry))
+    }).await;
+
+    let (date, entry) = match result {
+        Ok(Ok(data)) => data,
+        Ok(Err(e)) => {
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("数据库查询失败: {}", e)));
+        }
+        Err(e) => {
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("查询任务失败: {}", e)));
+        }
+    };
+
+    let Some(entry) = entry else {
+        return Err(error_response(StatusCode::NOT_FOUND, "数据库中没有词条".to_string()));
+    };
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "date": date,
+        "entry": entry
+    })))
+}
+
+/// 当前日期（UTC，YYYY-MM-DD）
+fn today_utc() -> String {
+    let seconds = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+    date_from_unix_days((seconds / 86_400) as i64)
+}
+
+/// 1970-01-01起的天数换算为YYYY-MM-DD（公历）
+fn date_from_unix_days(days: i64) -> String {
+    // 以3月1日为一年的开始，400年为一个周期
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// 是否为YYYY-MM-DD格式的日期（只检查格式和月日范围）
+fn is_iso_date(text: &str) -> bool {
+    let parts: Vec<&str> = text.split('-').collect();
+    let [year, month, day] = parts.as_slice() else {
+        return false;
+    };
+    let all_digits = |part: &str, len: usize| part.len() == len && part.bytes().all(|b| b.is_ascii_digit());
+    all_digits(year, 4)
+        && all_digits(month, 2)
+        && all_digits(day, 2)
+        && (1..=12).contains(&month.parse::<u32>().unwrap_or(0))
+        && (1..=31).contains(&day.parse::<u32>().unwrap_or(0))
+}
+
+/// 词性列表处理器 - 返回所有不同的词性及其词条数，按词条数排序
+async fn pos_handler(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+    let db_path = state.db_path.clone();
+    let fts5_available = state.fts5_available;
+    let permit = acquire_db_permit(&state).await?;
+    let result = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        let db = open_readonly_db(&db_path, fts5_available)?;
+        let parts_of_speech = db.list_parts_of_speech()?;
+        Ok::<_, Box<dyn std::error::Error + Send + Sync>>(parts_of_speech)
+    }).await;
+
+    let parts_of_speech = match result {
+        Ok(Ok(parts_of_speech)) => parts_of_speech,
+        Ok(Err(e)) => {
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("数据库查询失败: {}", e)));
+        }
+        Err(e) => {
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("查询任务失败: {}", e)));
+        }
+    };
+
+    let items: Vec<serde_json::Value> = parts_of_speech
+        .into_iter()
+        .map(|(part_of_speech, count)| serde_json::json!({ "part_of_speech": part_of_speech, "count": count }))
+        .collect();
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "count": items.len(),
+        "parts_of_speech": items
+    })))
+}
+
+/// 浏览窗口处理器 - 返回目标读音及其前后的词条，用于"翻页"式浏览
+async fn window_handler(
+    Query(params): Query<WindowQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+    let at = params.at.trim().to_string();
+    if at.is_empty() {
+        return Err(error_response(StatusCode::BAD_REQUEST, "at参数不能为空".to_string()));
+    }
+
+    let db_path = state.db_path.clone();
+    let fts5_available = state.fts5_available;
+    let before = params.before.min(MAX_WINDOW_SIZE);
+    let after = params.after.min(MAX_WINDOW_SIZE);
+    let query_at = at.clone();
+
+    let permit = acquire_db_permit(&state).await?;
+    let result = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        let db = open_readonly_db(&db_path, fts5_available)?;
+        let window = db.entries_window(&query_at, before, after)?;
+        Ok::<_, Box<dyn std::error::Error + Send + Sync>>(window)
+    }).await;
+
+    let window = match result {
+        Ok(Ok(window)) => window,
+        Ok(Err(e)) => {
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("数据库查询失败: {}", e)));
+        }
+        Err(e) => {
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("查询任务失败: {}", e)));
+        }
+    };
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "at": at,
+        "found": window.target.is_some(),
+        "window": window
+    })))
+}
+
+/// 按拍模式搜索处理器 - 只返回标题、读音和汉字表记，用于填字等文字游戏
+async fn pattern_handler(
+    Query(params): Query<PatternQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+    let pattern = params.pattern.trim().to_string();
+    if pattern.is_empty() {
+        return Err(error_response(StatusCode::BAD_REQUEST, "pattern参数不能为空".to_string()));
+    }
+    if pattern.chars().count() > MAX_PATTERN_CHARS {
+        return Err(error_response(StatusCode::BAD_REQUEST, format!("pattern不能超过{}个字符", MAX_PATTERN_CHARS)));
+    }
+    if pattern.matches('*').count() > MAX_PATTERN_WILDCARDS {
+        return Err(error_response(StatusCode::BAD_REQUEST, format!("pattern中的*不能超过{}个", MAX_PATTERN_WILDCARDS)));
+    }
+
+    let db_path = state.db_path.clone();
+    let fts5_available = state.fts5_available;
+    let limit = params.limit.min(MAX_PATTERN_LIMIT);
+    let query_pattern = pattern.clone();
+
+    let permit = acquire_db_permit(&state).await?;
+    let result = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        let db = open_readonly_db(&db_path, fts5_available)?;
+        let entries = db.search_by_kana_pattern(&query_pattern, limit)?;
+        Ok::<_, Box<dyn std::error::Error + Send + Sync>>(entries)
+    }).await;
+
+    let entries = match result {
+        Ok(Ok(entries)) => entries,
+        Ok(Err(e)) => {
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("数据库查询失败: {}", e)));
+        }
+        Err(e) => {
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("查询任务失败: {}", e)));
+        }
+    };
+
+    let headwords: Vec<Suggestion> = entries.into_iter().map(Suggestion::from).collect();
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "pattern": pattern,
+        "count": headwords.len(),
+        "headwords": headwords
+    })))
+}
+
+/// 查询耗时是否超过慢查询阈值（未设置阈值时始终为false）
+fn is_slow_query(duration_ms: u128, threshold_ms: Option<u64>) -> bool {
+    threshold_ms.is_some_and(|threshold| duration_ms > threshold as u128)
+}
+
+/// 查询参数解析失败时的错误响应，错误信息中列出原因（如支持的search_type取值）
+fn query_rejection_response(rejection: QueryRejection) -> (StatusCode, Json<ErrorResponse>) {
+    error_response(StatusCode::BAD_REQUEST, rejection.body_text())
+}
+
+/// 等待一个数据库连接名额，许可在阻塞任务结束（被drop）时归还
+async fn acquire_db_permit(state: &AppState) -> Result<OwnedSemaphorePermit, (StatusCode, Json<ErrorResponse>)> {
+    state
+        .db_permits
+        .clone()
+        .acquire_owned()
+        .await
+        .map_err(|_| error_response(StatusCode::SERVICE_UNAVAILABLE, "服务器正在关闭".to_string()))
+}
+
+/// 构造错误响应
+fn error_response(status: StatusCode, error: String) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        status,
+        Json(ErrorResponse {
+            success: false,
+            error,
+        }),
+    )
+}
+
+/// 不在词频表中的词使用的频率排名（排在所有已知词之后）
+const DEFAULT_FREQUENCY_RANK: u32 = u32::MAX;
+
+/// 读取词频表CSV（每行"词,排名"），无法解析排名的行（如表头）被跳过
+/// 同一个词出现多次时保留最小的排名
+pub fn load_frequency_list(path: &str) -> Result<HashMap<String, u32>, Box<dyn std::error::Error>> {
+    let content = std::fs::read_to_string(path)?;
+    let mut frequencies = HashMap::new();
+
+    for line in content.lines() {
+        let Some((word, rank)) = line.split_once(',') else {
+            continue;
+        };
+        let Ok(rank) = rank.trim().parse::<u32>() else {
+            continue;
+        };
+        let word = word.trim().trim_matches('"').to_string();
+        if word.is_empty() {
+            continue;
+        }
+        let best = frequencies.entry(word).or_insert(rank);
+        *best = (*best).min(rank);
+    }
+
+    Ok(frequencies)
+}
+
+/// 词条的词频排名：汉字表记（各个表记）、假名读音、标题中最常用的一个
+fn frequency_rank(entry: &ObunshaDictEntry, frequencies: &HashMap<String, u32>) -> u32 {
+    let kanji_writings = entry.kanji_writing.as_deref().map(split_kanji_variants).unwrap_or_default();
+    kanji_writings
+        .iter()
+        .map(String::as_str)
+        .chain(entry.kana_reading.as_deref())
+        .chain(std::iter::once(entry.headword.as_str()))
+        .filter_map(|word| frequencies.get(word).copied())
+        .min()
+        .unwrap_or(DEFAULT_FREQUENCY_RANK)
+}
+
+/// 对搜索结果排序
+/// 带得分的结果（释义搜索、容错搜索）得分高的在前，得分相同时常用词在前；
+/// 不带得分的结果按词频排序，词频相同时保持原有顺序
+fn rank_results(entries: &mut [RankedEntry], frequencies: &HashMap<String, u32>) {
+    if frequencies.is_empty() {
+        return;
+    }
+    entries.sort_by(|a, b| {
+        let by_rank = b.rank.unwrap_or(0.0).partial_cmp(&a.rank.unwrap_or(0.0)).unwrap_or(std::cmp::Ordering::Equal);
+        by_rank.then_with(|| {
+            frequency_rank(&a.entry, frequencies).cmp(&frequency_rank(&b.entry, frequencies))
+        })
+    });
+}
+
+/// 将文本截断为不超过max_chars个字符，并加省略号
+/// 按字符截断，不会切断多字节字符；截断点落在注音括号内或紧接注音括号时，
+/// 退到被注音的汉字之前，不把"漢字(かんじ)"拆开
+fn truncate_preview(text: &str, max_chars: usize) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    if chars.len() <= max_chars {
+        return text.to_string();
+    }
+
+    let is_open = |ch: char| ch == '(' || ch == '（';
+    let is_close = |ch: char| ch == ')' || ch == '）';
+
+    let mut cut = max_chars;
+    let open = chars[..cut].iter().rposition(|&ch| is_open(ch));
+    let close = chars[..cut].iter().rposition(|&ch| is_close(ch));
+    let inside_reading = match (open, close) {
+        (Some(open), Some(close)) => open > close,
+        (Some(_), None) => true,
+        _ => false,
+    };
+    if let (true, Some(open)) = (inside_reading, open) {
+        cut = open;
+    }
+    if chars.get(cut).is_some_and(|&ch| is_open(ch)) {
+        while cut > 0 && ('\u{4e00}'..='\u{9fff}').contains(&chars[cut - 1]) {
+            cut -= 1;
+        }
+    }
+
+    let preview: String = chars[..cut].iter().collect();
+    format!("{}…", preview.trim_end())
+}
+
+/// 用<mark></mark>包裹文本中与查询词匹配的部分（不区分大小写）
+/// 按字符而非字节比较，不会切断多字节字符
+fn highlight_matches(text: &str, query: &str) -> String {
+    let query: Vec<char> = query.chars().flat_map(char::to_lowercase).collect();
+    if query.is_empty() {
+        return text.to_string();
+    }
+
+    let chars: Vec<char> = text.chars().collect();
+    let mut result = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        match match_length(&chars[i..], &query) {
+            Some(len) => {
+                result.push_str("<mark>");
+                result.extend(&chars[i..i + len]);
+                result.push_str("</mark>");
+                i += len;
+            }
+            None => {
+                result.push(chars[i]);
+                i += 1;
+            }
+        }
+    }
+    result
+}
+
+/// 文本开头与查询词（已转小写）匹配时，返回匹配占用的字符数
+fn match_length(chars: &[char], query: &[char]) -> Option<usize> {
+    let mut matched = 0;
+    for (consumed, ch) in chars.iter().enumerate() {
+        for lower in ch.to_lowercase() {
+            if matched >= query.len() || lower != query[matched] {
+                return None;
+            }
+            matched += 1;
+        }
+        if matched == query.len() {
+            return Some(consumed + 1);
+        }
+    }
+    None
+}
+
+/// 统计信息处理器
+async fn stats_handler(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+    // 优先返回缓存；缓存为空（刚启动或导入后失效）时现场统计并写入缓存
+    let (cached, generation) = {
+        let cache = state.stats_cache.read().unwrap();
+        (cache.stats(), cache.generation())
+    };
+    let result = match cached {
+        Some(stats) => Ok(Ok(stats)),
+        None => {
+            let db_path = state.db_path.clone();
+            let permit = acquire_db_permit(&state).await?;
+            tokio::task::spawn_blocking(move || {
+                let _permit = permit;
+                compute_stats(&db_path)
+            }).await
+        }
+    };
+
+    let stats = match result {
+        Ok(Ok(stats)) => {
+            state.stats_cache.write().unwrap().store(generation, stats.clone());
+            stats
+        }
+        Ok(Err(e)) => {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    success: false,
+                    error: format!("获取统计信息失败: {}", e),
+                }),
+            ));
+        }
+        Err(e) => {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    success: false,
+                    error: format!("统计任务失败: {}", e),
+                }),
+            ));
+        }
+    };
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "database": {
+            "path": state.db_path,
+            "total_entries": stats.total_entries,
+            "unique_headwords": stats.unique_headwords,
+            "pure_redirects": stats.pure_redirects,
+            "status": "已连接"
+        },
+        "metadata": stats.metadata,
+        "stale_seconds": stats.refreshed_at.elapsed().as_secs(),
+        "api": {
+            "version": "1.0.0",
+            "supported_search_types": SearchType::names()
+        }
+    })))
+}
+
+/// 存储统计处理器 - 返回各文本列占用的字节数及占比
+async fn storage_stats_handler(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+    let db_path = state.db_path.clone();
+    let fts5_available = state.fts5_available;
+
+    let permit = acquire_db_permit(&state).await?;
+    let result = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        let db = open_readonly_db(&db_path, fts5_available)?;
+        let stats = db.storage_stats()?;
+        Ok::<_, Box<dyn std::error::Error + Send + Sync>>(stats)
+    }).await;
+
+    let stats = match result {
+        Ok(Ok(stats)) => stats,
+        Ok(Err(e)) => {
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("获取存储统计失败: {}", e)));
+        }
+        Err(e) => {
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("统计任务失败: {}", e)));
+        }
+    };
+
+    let column_bytes: i64 = stats.columns.iter().map(|column| column.bytes).sum();
+    let columns: Vec<serde_json::Value> = stats
+        .columns
+        .iter()
+        .map(|column| {
+            let share = if column_bytes > 0 {
+                (column.bytes as f64 / column_bytes as f64 * 1000.0).round() / 10.0
+            } else {
+                0.0
+            };
+            serde_json::json!({
+                "column": column.column,
+                "bytes": column.bytes,
+                "percent": share
+            })
+        })
+        .collect();
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "database_bytes": stats.database_bytes,
+        "column_bytes": column_bytes,
+        "columns": columns
+    })))
+}
+
+/// 标题长度分布处理器
+/// unit=chars 按标题字符数统计，unit=mora 按假名读音的拍数统计（不含没有读音的词条）
+async fn histogram_handler(
+    State(state): State<AppState>,
+    Query(params): Query<HistogramQuery>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+    let by_mora = match params.unit.as_deref() {
+        None | Some("chars") => false,
+        Some("mora") => true,
+        Some(other) => {
+            return Err(error_response(
+                StatusCode::BAD_REQUEST,
+                format!("不支持的统计单位 {:?}，支持: chars, mora", other),
+            ));
+        }
+    };
+    let db_path = state.db_path.clone();
+    let fts5_available = state.fts5_available;
+
+    let permit = acquire_db_permit(&state).await?;
+    let result = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        let db = open_readonly_db(&db_path, fts5_available)?;
+        let histogram = if by_mora { db.reading_mora_histogram()? } else { db.headword_length_histogram()? };
+        Ok::<_, Box<dyn std::error::Error + Send + Sync>>(histogram)
+    }).await;
+
+    let histogram = match result {
+        Ok(Ok(histogram)) => histogram,
+        Ok(Err(e)) => {
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("获取长度分布失败: {}", e)));
+        }
+        Err(e) => {
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("统计任务失败: {}", e)));
+        }
+    };
+
+    let total: i64 = histogram.iter().map(|(_, count)| count).sum();
+    let buckets: Vec<serde_json::Value> = histogram
+        .iter()
+        .map(|(length, count)| serde_json::json!({ "length": length, "count": count }))
+        .collect();
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "unit": if by_mora { "mora" } else { "chars" },
+        "total": total,
+        "buckets": buckets
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(data_id: &str, kana: &str, kanji: &str) -> RankedEntry {
+        RankedEntry::from(ObunshaDictEntry {
+            id: None,
+            data_id: data_id.to_string(),
+            data_type: "1".to_string(),
+            headword: format!("{}【{}】", kana, kanji),
+            kana_reading: Some(kana.to_string()),
+            kanji_writing: Some(kanji.to_string()),
+            kanji_variants: vec![kanji.to_string()],
+            expansion: None,
+            part_of_speech: None,
+            conjugation: None,
+            definition_html: String::new(),
+            definition_text: String::new(),
+            raw_mdx_content: String::new(),
+        })
+    }
+
+    #[test]
+    fn test_search_type_round_trip() {
+        for search_type in SearchType::ALL {
+            assert_eq!(search_type.to_string().parse::<SearchType>(), Ok(search_type));
+            assert_eq!(serde_json::json!(search_type), serde_json::json!(search_type.as_str()));
+        }
+        assert_eq!(SearchType::names().len(), SearchType::ALL.len());
+        assert!("kanii".parse::<SearchType>().is_err());
+    }
+
+    #[test]
+    fn test_parse_multiple_search_types() {
+        let types = SearchTypes::try_from("kana, kanji,kana".to_string()).unwrap();
+        assert_eq!(types.as_slice(), [SearchType::Kana, SearchType::Kanji]);
+        assert_eq!(types.to_string(), "kana,kanji");
+
+        let error = SearchTypes::try_from("kana,kanii".to_string()).unwrap_err();
+        assert!(error.contains("kanii"), "{}", error);
+    }
+
+    #[test]
+    fn test_parse_search_chain() {
+        let chain = SearchChain::try_from("kanji, kana,kanji,headword".to_string()).unwrap();
+        assert_eq!(chain.as_slice(), [SearchTier::Kanji, SearchTier::Kana, SearchTier::Headword]);
+        assert_eq!(chain.to_string(), "kanji,kana,headword");
+
+        let uri: axum::http::Uri = "/search?word=ai&chain=kana,fuzzy".parse().unwrap();
+        let rejection = Query::<SearchQuery>::try_from_uri(&uri).unwrap_err();
+        let (status, Json(body)) = query_rejection_response(rejection);
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body.error.contains("fuzzy"), "{}", body.error);
+    }
+
+    #[test]
+    fn test_unknown_search_type_is_rejected() {
+        let uri: axum::http::Uri = "/search?word=ai&search_type=kanii".parse().unwrap();
+        let rejection = Query::<SearchQuery>::try_from_uri(&uri).unwrap_err();
+        let (status, Json(body)) = query_rejection_response(rejection);
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(!body.success);
+        assert!(body.error.contains("kanii"), "{}", body.error);
+        assert!(body.error.contains("exact") && body.error.contains("fuzzy"), "{}", body.error);
+
+        let uri: axum::http::Uri = "/search?word=ai".parse().unwrap();
+        assert_eq!(Query::<SearchQuery>::try_from_uri(&uri).unwrap().search_type.as_slice(), [SearchType::Exact]);
+    }
+
+    #[test]
+    fn test_jsonapi_document() {
+        let entries = vec![entry("1", "あい", "愛")];
+        let document = jsonapi_document(&entries, serde_json::json!({ "count": 1 }));
+
+        assert_eq!(document["meta"]["count"], 1);
+        assert_eq!(document["data"][0]["type"], "entry");
+        assert_eq!(document["data"][0]["id"], "1");
+        assert_eq!(document["data"][0]["attributes"]["headword"], "あい【愛】");
+        assert!(document["data"][0]["attributes"].get("data_id").is_none());
+    }
+
+    #[test]
+    fn test_is_iso_date() {
+        assert!(is_iso_date("2024-05-01"));
+        assert!(!is_iso_date("2024-5-1"));
+        assert!(!is_iso_date("2024-13-01"));
+        assert!(!is_iso_date("2024-05-01T00:00:00"));
+        assert!(!is_iso_date("today"));
+    }
+
+    #[test]
+    fn test_date_from_unix_days() {
+        assert_eq!(date_from_unix_days(0), "1970-01-01");
+        assert_eq!(date_from_unix_days(11016), "2000-02-29");
+        assert_eq!(date_from_unix_days(19844), "2024-05-01");
+        assert!(is_iso_date(&today_utc()));
+    }
+
+    #[test]
+    fn test_bearer_token_matches() {
+        let mut headers = HeaderMap::new();
+        assert!(!bearer_token_matches(&headers, "secret"));
+
+        headers.insert(header::AUTHORIZATION, "Bearer secret".parse().unwrap());
+        assert!(bearer_token_matches(&headers, "secret"));
+        assert!(!bearer_token_matches(&headers, "secret2"));
+        assert!(!bearer_token_matches(&headers, "secreT"));
+
+        headers.insert(header::AUTHORIZATION, "Basic secret".parse().unwrap());
+        assert!(!bearer_token_matches(&headers, "secret"));
+    }
+
+    #[test]
+    fn test_parse_ndjson_entries() {
+        let body = concat!(
+            r#"{"data_id":"1","data_type":"1","headword":"あい【愛】","kana_reading":"あい","definition_html":"<div>愛</div>","definition_text":"愛","raw_mdx_content":""}"#,
+            "\n\n",
+            "not json\n",
+            r#"{"data_id":"2"}"#,
+            "\n",
+        );
+
+        let (entries, errors) = parse_ndjson_entries(body);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].kana_reading.as_deref(), Some("あい"));
+        let failed_lines: Vec<usize> = errors.iter().map(|(line, _)| *line).collect();
+        assert_eq!(failed_lines, vec![3, 4]);
+    }
+
+    #[test]
+    fn test_default_db_pool_size() {
+        assert!(default_db_pool_size() >= 1);
+    }
+
+    /// 在临时文件中建立示例数据库，返回使用它的应用状态（搜索名额为db_pool_size）
+    fn demo_state(name: &str, db_pool_size: usize) -> AppState {
+        let db_path = std::env::temp_dir().join(format!("jp_dict_{}_{}.db", name, std::process::id()));
+        let db_path = db_path.to_string_lossy().into_owned();
+        let _ = std::fs::remove_file(&db_path);
+        let db = ObunshaDictDatabase::new(&db_path).unwrap();
+        db.initialize().unwrap();
+        db.seed_demo().unwrap();
+
+        AppState {
+            db_path,
+            frequencies: Arc::new(HashMap::new()),
+            slow_query_threshold_ms: None,
+            admin_token: None,
+            stats_cache: Arc::new(RwLock::new(StatsCache::default())),
+            db_permits: Arc::new(Semaphore::new(db_pool_size)),
+            fts5_available: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_compact_export_pages_by_after_id() {
+        let state = demo_state("compact_test", 1);
+        let page = |uri: &str| {
+            let query = Query::<CompactQuery>::try_from_uri(&uri.parse().unwrap()).unwrap();
+            compact_export_handler(query, State(state.clone()))
+        };
+
+        let Err((status, _)) = page("http://localhost/export/compact?limit=0").await else {
+            panic!("limit=0 应返回400");
+        };
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        // 逐页拉取直到has_more为false，拿到的行数等于全部词条数
+        let total = page("http://localhost/export/compact").await.unwrap().0["count"].as_u64().unwrap();
+        let mut after_id = 0;
+        let mut fetched = 0;
+        loop {
+            let Json(body) = page(&format!("http://localhost/export/compact?limit=2&after_id={}", after_id)).await.unwrap();
+            fetched += body["count"].as_u64().unwrap();
+            after_id = body["page_info"]["next_after_id"].as_i64().unwrap();
+            if !body["page_info"]["has_more"].as_bool().unwrap() {
+                break;
+            }
+        }
+        assert_eq!(fetched, total);
+
+        let _ = std::fs::remove_file(&state.db_path);
+    }
+
+    #[tokio::test]
+    async fn test_sync_has_more() {
+        let state = demo_state("sync_test", 1);
+        let page = |limit: usize| {
+            let uri = format!("http://localhost/sync?since=2000-01-01T00:00:00Z&limit={}", limit);
+            let query = Query::<SyncQuery>::try_from_uri(&uri.parse().unwrap()).unwrap();
+            sync_handler(query, State(state.clone()))
+        };
+
+        let Err((status, _)) = page(0).await else {
+            panic!("limit=0 应返回400");
+        };
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let total = page(MAX_SYNC_LIMIT).await.unwrap().0["count"].as_u64().unwrap() as usize;
+        assert!(total > 1);
+        // 恰好取完时没有下一页，少取一条时还有
+        assert!(!page(total).await.unwrap().0["sync_info"]["has_more"].as_bool().unwrap());
+        assert!(page(total - 1).await.unwrap().0["sync_info"]["has_more"].as_bool().unwrap());
+
+        let _ = std::fs::remove_file(&state.db_path);
+    }
+
+    #[tokio::test]
+    async fn test_created_entries_range() {
+        let state = demo_state("created_test", 1);
+        // 第n个词条的创建时间为2024-01-01之后的第n天
+        rusqlite::Connection::open(&state.db_path).unwrap().execute(
+            "UPDATE obunsha_kokugo_dict SET created_at = datetime('2024-01-01', '+' || id || ' days')",
+            [],
+        ).unwrap();
+        let page = |limit: usize| {
+            let uri = format!(
+                "http://localhost/entries?created_from=2024-01-03T00:00:00Z&created_to=2024-01-05T00:00:00Z&limit={}",
+                limit
+            );
+            let query = Query::<CreatedQuery>::try_from_uri(&uri.parse().unwrap()).unwrap();
+            created_entries_handler(query, State(state.clone()))
+        };
+
+        let Err((status, _)) = page(0).await else {
+            panic!("limit=0 应返回400");
+        };
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        // 两端都包含在内
+        let Json(body) = page(10).await.unwrap();
+        let ids: Vec<i64> = body["entries"].as_array().unwrap().iter().map(|entry| entry["id"].as_i64().unwrap()).collect();
+        assert_eq!(ids, vec![2, 3, 4]);
+        assert!(!body["query_info"]["has_more"].as_bool().unwrap());
+        // 恰好取完时没有下一页，少取一条时还有
+        assert!(!page(3).await.unwrap().0["query_info"]["has_more"].as_bool().unwrap());
+        assert!(page(2).await.unwrap().0["query_info"]["has_more"].as_bool().unwrap());
+
+        let _ = std::fs::remove_file(&state.db_path);
+    }
+
+    #[tokio::test]
+    async fn test_search_grouped_by_headword() {
+        // 同一标题的词条归入一组，组按标题首次出现的顺序排列
+        let groups = group_by_headword(vec![
+            entry("1", "あい", "愛"),
+            entry("2", "こい", "恋"),
+            entry("3", "あい", "愛"),
+        ]);
+        let grouped: Vec<(&str, Vec<&str>)> = groups
+            .iter()
+            .map(|group| (group.headword.as_str(), group.entries.iter().map(|ranked| ranked.entry.data_id.as_str()).collect()))
+            .collect();
+        assert_eq!(grouped, vec![("あい【愛】", vec!["1", "3"]), ("こい【恋】", vec!["2"])]);
+
+        let state = demo_state("grouped_test", 1);
+        let uri = "http://localhost/search?word=%E3%81%82%E3%81%84&search_type=fuzzy&grouped=true";
+        let query = Query::<SearchQuery>::try_from_uri(&uri.parse().unwrap());
+        let Json(body) = search_handler(query, State(state.clone())).await.unwrap();
+        assert!(body.get("entries").is_none());
+        let groups = body["groups"].as_array().unwrap();
+        assert!(!groups.is_empty());
+        assert_eq!(body["group_count"].as_u64().unwrap() as usize, groups.len());
+        let grouped_entries: usize = groups.iter().map(|group| group["entries"].as_array().unwrap().len()).sum();
+        assert_eq!(body["count"].as_u64().unwrap() as usize, grouped_entries);
+
+        let _ = std::fs::remove_file(&state.db_path);
+    }
+
+    #[tokio::test]
+    async fn test_search_suggestions_are_opt_in() {
+        let state = demo_state("suggest_test", 1);
+        let search = |uri: &str| {
+            let query = Query::<SearchQuery>::try_from_uri(&uri.parse().unwrap());
+            search_handler(query, State(state.clone()))
+        };
+
+        // 默认不做候选扫描，无结果时也没有suggestions
+        let Json(body) = search("http://localhost/search?word=%E3%81%82%E3%81%88").await.unwrap();
+        assert_eq!(body["count"], 0);
+        assert!(body.get("suggestions").is_none());
+
+        let Json(body) = search("http://localhost/search?word=%E3%81%82%E3%81%88&suggest=true").await.unwrap();
+        assert!(body["suggestions"].is_array());
+
+        let _ = std::fs::remove_file(&state.db_path);
+    }
+
+    #[tokio::test]
+    async fn test_search_rejects_unknown_format() {
+        let state = demo_state("format_test", 1);
+        let search = |uri: &str| {
+            let query = Query::<SearchQuery>::try_from_uri(&uri.parse().unwrap());
+            search_handler(query, State(state.clone()))
+        };
+
+        for uri in [
+            "http://localhost/search?word=%E3%81%82%E3%81%84&format=html",
+            "http://localhost/search?word=%E3%81%82%E3%81%84&structured=list",
+        ] {
+            let Err((status, _)) = search(uri).await else {
+                panic!("{} 应返回400", uri);
+            };
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+        }
+        let Json(body) = search("http://localhost/search?word=%E3%81%82%E3%81%84&format=markdown").await.unwrap();
+        assert!(body["entries"][0]["definition_markdown"].is_string());
+
+        let _ = std::fs::remove_file(&state.db_path);
+    }
+
+    #[tokio::test]
+    async fn test_multi_type_search_reports_each_group() {
+        let state = demo_state("multi_type_test", 1);
+        let search = |uri: &str| {
+            let query = Query::<SearchQuery>::try_from_uri(&uri.parse().unwrap());
+            search_handler(query, State(state.clone()))
+        };
+
+        let Json(body) = search("http://localhost/search?word=%E3%81%82%E3%81%84&search_type=kana,prefix").await.unwrap();
+        for search_type in ["kana", "prefix"] {
+            let returned = body["results"][search_type].as_array().unwrap().len();
+            let info = &body["query_info"]["groups"][search_type];
+            assert_eq!(info["truncated"], false);
+            assert_eq!(info["total_available"].as_u64().unwrap() as usize, returned);
+            assert!(info["redirected_to"].is_null());
+        }
+        assert!(body.get("suggestions").is_none());
+
+        // 所有类型都没有结果时，suggest=true 附带候选
+        let Json(body) = search("http://localhost/search?word=%E3%81%82%E3%81%88&search_type=kana,kanji&suggest=true").await.unwrap();
+        assert_eq!(body["count"], 0);
+        assert!(body["suggestions"].is_array());
+
+        let _ = std::fs::remove_file(&state.db_path);
+    }
+
+    #[tokio::test]
+    async fn test_stats_exposes_metadata() {
+        let state = demo_state("stats_metadata_test", 1);
+        let db = ObunshaDictDatabase::new(&state.db_path).unwrap();
+        db.set_metadata("title", "旺文社国語辞典 第十一版").unwrap();
+        // 重新导入时覆盖旧值
+        db.set_metadata("imported_entries", "1").unwrap();
+        db.set_metadata("imported_entries", "2").unwrap();
+
+        let Json(body) = stats_handler(State(state.clone())).await.unwrap();
+        assert_eq!(body["metadata"]["title"], "旺文社国語辞典 第十一版");
+        assert_eq!(body["metadata"]["source_file"], "fixtures/demo_entries.txt");
+        assert_eq!(body["metadata"]["imported_entries"], "2");
+
+        let _ = std::fs::remove_file(&state.db_path);
+    }
+
+    #[tokio::test]
+    async fn test_kanji_usage_has_more() {
+        let state = demo_state("kanji_usage_test", 1);
+        let page = |limit: usize| {
+            let uri = format!("http://localhost/kanji/usage?kanji=%E7%89%A9&limit={}", limit);
+            let query = Query::<KanjiUsageQuery>::try_from_uri(&uri.parse().unwrap()).unwrap();
+            kanji_usage_handler(query, State(state.clone()))
+        };
+
+        let Err((status, _)) = page(0).await else {
+            panic!("limit=0 应返回400");
+        };
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let Json(body) = page(MAX_KANJI_USAGE_LIMIT + 1).await.unwrap();
+        assert_eq!(body["query_info"]["limit"], MAX_KANJI_USAGE_LIMIT);
+        let total = body["count"].as_u64().unwrap() as usize;
+        assert!(total > 1);
+        // 恰好取完时没有下一页，少取一条时还有
+        assert!(!page(total).await.unwrap().0["query_info"]["has_more"].as_bool().unwrap());
+        assert!(page(total - 1).await.unwrap().0["query_info"]["has_more"].as_bool().unwrap());
+
+        let _ = std::fs::remove_file(&state.db_path);
+    }
+
+    #[tokio::test]
+    async fn test_batch_body_limit() {
+        const LIMIT: usize = 64;
+        let state = demo_state("body_limit_test", 1);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let options = ServerOptions { batch_body_limit: Some(LIMIT), ..ServerOptions::default() };
+        let db_path = state.db_path.clone();
+        let server = tokio::spawn(async move { serve(listener, &db_path, options).await.map_err(|e| e.to_string()) });
+
+        // 用空白把请求体补到指定的字节数
+        let post = |size: usize| {
+            let body = format!("{:<width$}", r#"["1"]"#, width = size);
+            assert_eq!(body.len(), size);
+            reqwest::Client::new()
+                .post(format!("http://{}/entries", addr))
+                .header("Content-Type", "application/json")
+                .body(body)
+                .send()
+        };
+        assert_eq!(post(LIMIT).await.unwrap().status(), reqwest::StatusCode::OK);
+        assert_eq!(post(LIMIT + 1).await.unwrap().status(), reqwest::StatusCode::PAYLOAD_TOO_LARGE);
+
+        server.abort();
+        let _ = std::fs::remove_file(&state.db_path);
+    }
+
+    #[tokio::test]
+    async fn test_db_pool_size_one_serialises_searches() {
+        let state = demo_state("pool_test", 1);
+        let db_path = state.db_path.clone();
+        let query = || Query::<SearchQuery>::try_from_uri(&"http://localhost/search?word=%E3%81%82%E3%81%84".parse().unwrap());
+
+        // 唯一的名额被占用时，搜索一直等待
+        let held = acquire_db_permit(&state).await.unwrap();
+        let mut pending = tokio::spawn(search_handler(query(), State(state.clone())));
+        assert!(tokio::time::timeout(Duration::from_millis(200), &mut pending).await.is_err());
+
+        // 名额归还后搜索完成，结束后名额也被归还
+        drop(held);
+        let response = tokio::time::timeout(Duration::from_secs(10), pending).await.unwrap().unwrap();
+        assert!(response.is_ok());
+        assert_eq!(state.db_permits.available_permits(), 1);
+        assert!(search_handler(query(), State(state.clone())).await.is_ok());
+
+        // 其他读取数据库的接口同样占用名额
+        let held = acquire_db_permit(&state).await.unwrap();
+        let mut pending = tokio::spawn(pos_handler(State(state.clone())));
+        assert!(tokio::time::timeout(Duration::from_millis(200), &mut pending).await.is_err());
+        drop(held);
+        let response = tokio::time::timeout(Duration::from_secs(10), pending).await.unwrap().unwrap();
+        assert!(response.is_ok());
+        assert_eq!(state.db_permits.available_permits(), 1);
+
+        let _ = std::fs::remove_file(&db_path);
+    }
+
+    #[test]
+    fn test_stats_cache_drops_results_from_before_invalidate() {
+        let stats = |total_entries| CachedStats {
+            total_entries,
+            unique_headwords: total_entries,
+            pure_redirects: 0,
+            metadata: BTreeMap::new(),
+            refreshed_at: Instant::now(),
+        };
+        let mut cache = StatsCache::default();
+
+        // 统计开始后发生导入：导入前的结果不能写回缓存
+        let generation = cache.generation();
+        cache.invalidate();
+        assert!(!cache.store(generation, stats(1)));
+        assert!(cache.stats().is_none());
+
+        // 导入之后开始的统计正常写入
+        assert!(cache.store(cache.generation(), stats(2)));
+        assert_eq!(cache.stats().unwrap().total_entries, 2);
+
+        cache.invalidate();
+        assert!(cache.stats().is_none());
+    }
+
+    #[test]
+    fn test_is_slow_query() {
+        assert!(!is_slow_query(500, None));
+        assert!(!is_slow_query(100, Some(100)));
+        assert!(is_slow_query(101, Some(100)));
+    }
+
+    #[test]
+    fn test_rank_results_by_frequency() {
+        let frequencies: HashMap<String, u32> = [("暑い".to_string(), 300), ("熱い".to_string(), 900)].into_iter().collect();
+        let mut entries = vec![entry("1", "あつい", "厚い"), entry("2", "あつい", "熱い"), entry("3", "あつい", "暑い")];
+
+        rank_results(&mut entries, &frequencies);
+        let order: Vec<&str> = entries.iter().map(|ranked| ranked.entry.data_id.as_str()).collect();
+        assert_eq!(order, vec!["3", "2", "1"]);
+
+        // 多重表记以・或·分隔时取最常用的一个
+        let frequencies: HashMap<String, u32> = [("聴く".to_string(), 500), ("効く".to_string(), 2000)].into_iter().collect();
+        assert_eq!(frequency_rank(&entry("2", "きく", "聞く・聴く").entry, &frequencies), 500);
+        assert_eq!(frequency_rank(&entry("3", "きく", "効く·利く").entry, &frequencies), 2000);
+    }
+
+    #[test]
+    fn test_has_examples_filters_before_cap() {
+        let db = ObunshaDictDatabase::new(":memory:").unwrap();
+        db.initialize().unwrap();
+        let kana = ['か', 'き', 'く', 'け', 'こ', 'さ', 'し', 'す', 'せ', 'そ', 'た'];
+        for (i, (first, second)) in kana.iter().flat_map(|&a| kana.iter().map(move |&b| (a, b))).enumerate() {
+            let mut ranked = entry(&i.to_string(), &format!("あい{}{}", first, second), "愛");
+            ranked.entry.headword = format!("あい{}{}", first, second);
+            db.insert_entry(&ranked.entry).unwrap();
+        }
+        // 排在上限之后的唯一一个含例句的词条
+        let mut last = entry("last", "あいん", "愛").entry;
+        last.headword = "あいん".to_string();
+        last.definition_html = r#"<span class="ex_text">例文。</span>"#.to_string();
+        db.insert_entry(&last).unwrap();
+
+        let (mut entries, _) =
+            run_search(&db, SearchType::Prefix, "あい", None, filtered_search_limit(true)).unwrap();
+        assert!(entries.len() > DEFINITION_SEARCH_LIMIT);
+        retain_with_examples(&db, &mut entries).unwrap();
+        let ids: Vec<&str> = entries.iter().map(|ranked| ranked.entry.data_id.as_str()).collect();
+        assert_eq!(ids, vec!["last"]);
+
+        let (entries, _) = run_search(&db, SearchType::Prefix, "あい", None, filtered_search_limit(false)).unwrap();
+        assert_eq!(entries.len(), DEFINITION_SEARCH_LIMIT);
+    }
+
+    #[test]
+    fn test_hide_redirects_filters_before_cap() {
+        let db = ObunshaDictDatabase::new(":memory:").unwrap();
+        db.initialize().unwrap();
+        let kana = ['か', 'き', 'く', 'け', 'こ', 'さ', 'し', 'す', 'せ', 'そ', 'た'];
+        for (i, (first, second)) in kana.iter().flat_map(|&a| kana.iter().map(move |&b| (a, b))).enumerate() {
+            let mut ranked = entry(&i.to_string(), &format!("あい{}{}", first, second), "愛");
+            ranked.entry.headword = format!("あい{}{}", first, second);
+            ranked.entry.definition_text = "⇒あい（愛）".to_string();
+            db.insert_entry(&ranked.entry).unwrap();
+        }
+        let mut last = entry("last", "あいん", "愛").entry;
+        last.headword = "あいん".to_string();
+        last.definition_text = "いとしく思う心。".to_string();
+        db.insert_entry(&last).unwrap();
+
+        let (mut entries, _) =
+            run_search(&db, SearchType::Prefix, "あい", None, filtered_search_limit(true)).unwrap();
+        entries.retain(|ranked| !ranked.entry.is_pure_redirect());
+        let ids: Vec<&str> = entries.iter().map(|ranked| ranked.entry.data_id.as_str()).collect();
+        assert_eq!(ids, vec!["last"]);
+    }
+
+    #[test]
+    fn test_truncate_preview() {
+        assert_eq!(truncate_preview("いとしく思う心。", 20), "いとしく思う心。");
+        assert_eq!(truncate_preview("いとしく思う心。", 4), "いとしく…");
+        // 不拆开注音
+        assert_eq!(truncate_preview("親子の愛情(あいじょう)を注ぐ", 7), "親子の…");
+        assert_eq!(truncate_preview("親子の愛情(あいじょう)を注ぐ", 5), "親子の…");
+        assert_eq!(truncate_preview("親子の愛情(あいじょう)を注ぐ", 12), "親子の愛情(あいじょう)…");
+    }
+
+    #[test]
+    fn test_highlight_matches() {
+        assert_eq!(highlight_matches("愛情と愛", "愛"), "<mark>愛</mark>情と<mark>愛</mark>");
+        assert_eq!(highlight_matches("DNAの略", "dna"), "<mark>DNA</mark>の略");
+        assert_eq!(highlight_matches("あいさつ", "かな"), "あいさつ");
+        assert_eq!(highlight_matches("あいさつ", ""), "あいさつ");
+    }
+}
@@ -0,0 +1,24 @@
+//! 作为库从外部crate调用的集成测试（只使用crate根部导出的API）
+
+use dict::{ObunshaDictDatabase, ObunshaParser};
+
+#[test]
+fn test_search_through_public_api() {
+    let db = ObunshaDictDatabase::new(":memory:").unwrap();
+    db.initialize().unwrap();
+    db.seed_demo().unwrap();
+
+    let entries = db.search_by_kana_exact("かんしょう").unwrap();
+    assert_eq!(entries.len(), 4);
+    assert!(!db.suggest("あつ", 10).unwrap().is_empty());
+}
+
+#[test]
+fn test_parser_through_public_api() {
+    let parser = ObunshaParser::new();
+    let html = r#"<container data-id="1" data-type="1"><div class="mean_normal">いとしく思う心。</div></container>"#;
+
+    let entry = parser.parse_entry_from_html("あい【愛】", html).unwrap();
+    assert_eq!(entry.kana_reading.as_deref(), Some("あい"));
+    assert_eq!(entry.kanji_writing.as_deref(), Some("愛"));
+}